@@ -54,12 +54,21 @@ impl Client {
     }
 
     /// Start a new HTTP request with given [http::Request].
+    ///
+    /// With `compress-zs` feature `Accept-Encoding: zstd` header is added when request does not have one.
     #[inline]
     pub fn request<B, E>(&self, req: http::Request<B>) -> Request<'_, B>
     where
         B: Stream<Item = Result<Bytes, E>>,
         BodyError: From<E>,
     {
+        #[cfg(feature = "compress-zs")]
+        let mut req = req;
+        #[cfg(feature = "compress-zs")]
+        req.headers_mut()
+            .entry(http::header::ACCEPT_ENCODING)
+            .or_insert(http::header::HeaderValue::from_static("zstd"));
+
         Request::new(req, self)
     }
 
@@ -110,6 +110,10 @@ pub enum ParseError {
     String(str::Utf8Error),
     #[cfg(feature = "json")]
     Json(serde_json::Error),
+    #[cfg(feature = "msgpack")]
+    MsgPack(rmp_serde::decode::Error),
+    #[cfg(feature = "cbor")]
+    Cbor(ciborium::de::Error<io::Error>),
     #[cfg(feature = "websocket")]
     WebSocket(http_ws::ProtocolError),
 }
@@ -180,6 +184,20 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+#[cfg(feature = "msgpack")]
+impl From<rmp_serde::decode::Error> for Error {
+    fn from(e: rmp_serde::decode::Error) -> Self {
+        Self::Parse(ParseError::MsgPack(e))
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl From<ciborium::de::Error<io::Error>> for Error {
+    fn from(e: ciborium::de::Error<io::Error>) -> Self {
+        Self::Parse(ParseError::Cbor(e))
+    }
+}
+
 #[cfg(feature = "http2")]
 impl From<crate::h2::Error> for Error {
     fn from(e: crate::h2::Error) -> Self {
@@ -26,6 +26,7 @@ pub mod ws;
 
 pub mod error;
 
+pub use self::body::ResponseBody;
 pub use self::builder::ClientBuilder;
 pub use self::client::Client;
 pub use self::resolver::Resolve;
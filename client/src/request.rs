@@ -104,14 +104,34 @@ impl<'a, B> Request<'a, B> {
     #[cfg(feature = "json")]
     /// Use json object as request body.
     pub fn json(mut self, body: impl serde::ser::Serialize) -> Result<Request<'a>, Error> {
-        // TODO: handle serialize error.
-        let body = serde_json::to_vec(&body).unwrap();
+        let body = serde_json::to_vec(&body).map_err(|e| Error::Std(Box::new(e)))?;
 
         self.headers_mut().insert(CONTENT_TYPE, const_header_value::JSON);
 
         Ok(self.body(body))
     }
 
+    #[cfg(feature = "msgpack")]
+    /// Use msgpack object as request body.
+    pub fn msgpack(mut self, body: impl serde::ser::Serialize) -> Result<Request<'a>, Error> {
+        let body = rmp_serde::to_vec_named(&body).map_err(|e| Error::Std(Box::new(e)))?;
+
+        self.headers_mut().insert(CONTENT_TYPE, const_header_value::MSGPACK);
+
+        Ok(self.body(body))
+    }
+
+    #[cfg(feature = "cbor")]
+    /// Use cbor object as request body.
+    pub fn cbor(mut self, body: impl serde::ser::Serialize) -> Result<Request<'a>, Error> {
+        let mut buf = Vec::new();
+        ciborium::ser::into_writer(&body, &mut buf).map_err(|e| Error::Std(Box::new(e)))?;
+
+        self.headers_mut().insert(CONTENT_TYPE, const_header_value::CBOR);
+
+        Ok(self.body(buf))
+    }
+
     /// Use pre allocated bytes as request body.
     ///
     /// Input type must implement [From] trait with [Bytes].
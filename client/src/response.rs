@@ -60,6 +60,15 @@ impl<'a, const PAYLOAD_LIMIT: usize> Response<'a, PAYLOAD_LIMIT> {
         &mut self.res
     }
 
+    /// Consume Self and return the inner response type.
+    ///
+    /// The response body is not bound to the response timeout anymore and it's up to caller
+    /// to decide how long the body would be streamed. The body is not decoded according to
+    /// `Content-Encoding` header.
+    pub fn into_inner(self) -> http::Response<ResponseBody<'a>> {
+        self.res
+    }
+
     /// Set payload size limit in bytes. Payload size beyond limit would be discarded.
     ///
     /// Default to 8 Mb.
@@ -111,6 +120,34 @@ impl<'a, const PAYLOAD_LIMIT: usize> Response<'a, PAYLOAD_LIMIT> {
         Ok(serde_json::from_slice(bytes.chunk())?)
     }
 
+    #[cfg(feature = "msgpack")]
+    /// Collect response body as msgpack object. Response is consumed.
+    ///
+    /// The output type must impl [serde::de::DeserializeOwned] trait.
+    pub async fn msgpack<T>(self) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        use xitca_http::bytes::Buf;
+
+        let bytes = self.collect::<BytesMut>().await?;
+        Ok(rmp_serde::from_slice(bytes.chunk())?)
+    }
+
+    #[cfg(feature = "cbor")]
+    /// Collect response body as cbor object. Response is consumed.
+    ///
+    /// The output type must impl [serde::de::DeserializeOwned] trait.
+    pub async fn cbor<T>(self) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        use xitca_http::bytes::Buf;
+
+        let bytes = self.collect::<BytesMut>().await?;
+        Ok(ciborium::de::from_reader(bytes.chunk())?)
+    }
+
     #[cfg(feature = "websocket")]
     pub fn ws(self) -> Result<crate::ws::WebSocket<'a>, Error> {
         let body = self.res.into_body();
@@ -126,16 +163,24 @@ impl<'a, const PAYLOAD_LIMIT: usize> Response<'a, PAYLOAD_LIMIT> {
 
         tokio::pin!(body);
 
-        let limit = res
-            .headers
-            .get(http::header::CONTENT_LENGTH)
-            .and_then(|v| v.to_str().ok().and_then(|str| str.parse::<usize>().ok()))
-            .unwrap_or(PAYLOAD_LIMIT);
+        // content length of encoded body does not bound the size of decoded payload.
+        let limit = match res.headers.get(http::header::CONTENT_ENCODING) {
+            Some(_) => PAYLOAD_LIMIT,
+            None => res
+                .headers
+                .get(http::header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok().and_then(|str| str.parse::<usize>().ok()))
+                .unwrap_or(PAYLOAD_LIMIT),
+        };
 
         let limit = std::cmp::min(limit, PAYLOAD_LIMIT);
 
+        #[cfg(feature = "compress-zs")]
+        let mut decoder = decode::decoder(&res.headers, limit)?;
+
         // TODO: use a meaningful capacity.
         let mut b = B::with_capacity(1024);
+        let mut received = 0;
 
         timer.as_mut().reset(Instant::now() + self.timeout);
 
@@ -150,10 +195,22 @@ impl<'a, const PAYLOAD_LIMIT: usize> Response<'a, PAYLOAD_LIMIT> {
                             return Err(e.into());
                         }
                     };
-                    if buf.len() + b.len() > limit {
+                    received += buf.len();
+                    if received > limit {
                         body.destroy_on_drop();
                         return Err(BodyError::OverFlow.into());
                     }
+
+                    #[cfg(feature = "compress-zs")]
+                    let buf = match decode::code(&mut decoder, buf) {
+                        Ok(Some(buf)) => buf,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            body.destroy_on_drop();
+                            return Err(e);
+                        }
+                    };
+
                     b.try_extend_from_slice(&buf)?;
                 }
                 Ok(None) => break,
@@ -164,6 +221,11 @@ impl<'a, const PAYLOAD_LIMIT: usize> Response<'a, PAYLOAD_LIMIT> {
             }
         }
 
+        #[cfg(feature = "compress-zs")]
+        if let Some(buf) = decode::code_eof(&mut decoder)? {
+            b.try_extend_from_slice(&buf)?;
+        }
+
         Ok(b)
     }
 
@@ -183,8 +245,6 @@ trait Collectable {
     fn with_capacity(cap: usize) -> Self;
 
     fn try_extend_from_slice(&mut self, slice: &[u8]) -> Result<(), Error>;
-
-    fn len(&self) -> usize;
 }
 
 impl Collectable for BytesMut {
@@ -198,11 +258,6 @@ impl Collectable for BytesMut {
         self.extend_from_slice(slice);
         Ok(())
     }
-
-    #[inline]
-    fn len(&self) -> usize {
-        Self::len(self)
-    }
 }
 
 impl Collectable for Vec<u8> {
@@ -216,11 +271,6 @@ impl Collectable for Vec<u8> {
         self.extend_from_slice(slice);
         Ok(())
     }
-
-    #[inline]
-    fn len(&self) -> usize {
-        Self::len(self)
-    }
 }
 
 impl Collectable for String {
@@ -234,9 +284,47 @@ impl Collectable for String {
         self.push_str(str);
         Ok(())
     }
+}
+
+#[cfg(feature = "compress-zs")]
+mod decode {
+    use std::{convert::Infallible, io};
+
+    use http_encoding::{error::CoderError, Code, ContentEncoding, DecodeLimit, FeaturedCode};
+    use xitca_http::{
+        bytes::Bytes,
+        http::header::{HeaderMap, CONTENT_ENCODING},
+    };
+
+    use super::{BodyError, Error};
+
+    // construct decoder from Content-Encoding header with given limit of decoded output.
+    pub(super) fn decoder(headers: &HeaderMap, limit: usize) -> Result<FeaturedCode, Error> {
+        let encoding = match headers.get(CONTENT_ENCODING) {
+            Some(value) => {
+                let value = value.to_str().map_err(|e| Error::Std(Box::new(e)))?;
+                ContentEncoding::try_parse(value).map_err(|e| Error::Std(Box::new(e)))?
+            }
+            None => ContentEncoding::NoOp,
+        };
+
+        FeaturedCode::try_decoder(encoding, DecodeLimit::new().max_size(limit)).map_err(|e| Error::Std(Box::new(e)))
+    }
 
-    #[inline]
-    fn len(&self) -> usize {
-        Self::len(self)
+    pub(super) fn code(decoder: &mut FeaturedCode, buf: Bytes) -> Result<Option<Bytes>, Error> {
+        decoder.code(buf).map_err(from_io)
+    }
+
+    pub(super) fn code_eof(decoder: &mut FeaturedCode) -> Result<Option<Bytes>, Error> {
+        <FeaturedCode as Code<Bytes>>::code_eof(decoder).map_err(from_io)
+    }
+
+    // decoded output exceeding limit is treated the same as overflowed payload.
+    fn from_io(e: io::Error) -> Error {
+        match CoderError::<Infallible>::from(e) {
+            CoderError::LimitExceeded => BodyError::OverFlow.into(),
+            CoderError::Io(e) => e.into(),
+            e => Error::Std(Box::new(e)),
+        }
     }
 }
@@ -1,23 +1,16 @@
-//! A Http/2 server handling low level grpc call.
+//! A Http/2 server handling grpc call.
 
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-use futures_util::TryStreamExt;
-use prost::Message;
-use xitca_http::{
-    body::Once,
-    bytes::{BufMut, Bytes, BytesMut},
-    h2::RequestBody,
-    http::{
-        const_header_value::GRPC,
-        header::{HeaderName, HeaderValue, CONTENT_TYPE, TRAILER},
-        IntoResponse, Response,
-    },
-    util::service::{route::post, Router},
-    HttpServiceBuilder, Request,
+use xitca_http::{h2, HttpServiceBuilder, Request, RequestBody};
+use xitca_service::{BuildServiceExt, Service};
+use xitca_web::{
+    grpc::{EnforceDeadline, Grpc, Status},
+    handler::handler_service,
+    route::post,
+    App,
 };
-use xitca_service::fn_service;
 
 mod hello_world {
     include!(concat!(env!("OUT_DIR"), "/helloworld.rs"));
@@ -29,9 +22,13 @@ fn main() -> std::io::Result<()> {
         .init();
 
     let factory = || {
-        let route = Router::new().insert("/helloworld.Greeter/SayHello", post(fn_service(grpc)));
+        let app = App::new()
+            .at("/helloworld.Greeter/SayHello", post(handler_service(say_hello)))
+            .enclosed(EnforceDeadline::new())
+            .finish()
+            .enclosed_fn(map_body);
 
-        HttpServiceBuilder::h2(route)
+        HttpServiceBuilder::h2(app)
     };
 
     xitca_server::Builder::new()
@@ -40,35 +37,14 @@ fn main() -> std::io::Result<()> {
         .wait()
 }
 
-async fn grpc(mut req: Request<RequestBody>) -> Result<Response<Once<Bytes>>, anyhow::Error> {
-    let body = req.body_mut();
-
-    let mut buf = BytesMut::new();
-
-    while let Some(bytes) = body.try_next().await? {
-        buf.extend_from_slice(bytes.as_ref());
-    }
-
-    let msg: hello_world::HelloRequest = Message::decode(buf.split_off(5))?;
-
-    Message::encode(&hello_world::HelloReply { response: msg.request }, &mut buf)?;
-
-    let len = buf.len() - 5;
-
-    (&mut buf[1..5]).put_u32(len as u32);
-
-    let mut res = req.into_response(buf.freeze());
-
-    #[allow(clippy::declare_interior_mutable_const)]
-    {
-        const GRPC_STATUS_NAME: HeaderName = HeaderName::from_static("grpc-status");
-        const GRPC_STATUS_VALUE: HeaderValue = HeaderValue::from_static("0");
-        const GRPC_STATUS_NAME_AS_VALUE: HeaderValue = HeaderValue::from_static("grpc-status");
-
-        res.headers_mut().insert(CONTENT_TYPE, GRPC);
-        res.headers_mut().insert(GRPC_STATUS_NAME, GRPC_STATUS_VALUE);
-        res.headers_mut().insert(TRAILER, GRPC_STATUS_NAME_AS_VALUE);
-    }
+async fn say_hello(Grpc(req): Grpc<hello_world::HelloRequest>) -> Result<Grpc<hello_world::HelloReply>, Status> {
+    Ok(Grpc(hello_world::HelloReply { response: req.request }))
+}
 
-    Ok(res)
+// Http/2 service uses it's own request body type. map it to the unified request body type of App.
+async fn map_body<S>(service: &S, req: Request<h2::RequestBody>) -> Result<S::Response, S::Error>
+where
+    S: Service<Request<RequestBody>>,
+{
+    service.call(req.map_body(RequestBody::from)).await
 }
@@ -12,11 +12,15 @@ use super::error::CoderError;
 
 pin_project! {
     /// A coder type that can be used for either encode or decode which determined by De type.
+    ///
+    /// With [FeaturedCode] an empty chunk from body stream is treated as flush marker and all pending
+    /// output of coder is emitted. See [Coder::sync_flush] for flushing every chunk.
     #[derive(Default)]
     pub struct Coder<S, C = FeaturedCode>{
         #[pin]
         body: S,
         coder: C,
+        sync_flush: bool,
     }
 }
 
@@ -28,7 +32,20 @@ where
 {
     /// Construct a new coder.
     pub fn new(body: S, coder: C) -> Self {
-        Self { body, coder }
+        Self {
+            body,
+            coder,
+            sync_flush: false,
+        }
+    }
+
+    /// Flush pending output of coder after every chunk of body stream.
+    ///
+    /// Useful for long-lived streaming response (Server-Sent Events, NDJSON etc) where chunks must
+    /// reach the client without waiting for more input.
+    pub fn sync_flush(mut self) -> Self {
+        self.sync_flush = true;
+        self
     }
 }
 
@@ -45,7 +62,12 @@ where
         while let Some(res) = ready!(this.body.as_mut().poll_next(cx)) {
             match res {
                 Ok(item) => {
-                    if let Some(item) = this.coder.code(item)? {
+                    let item = if *this.sync_flush {
+                        this.coder.code_flush(item)?
+                    } else {
+                        this.coder.code(item)?
+                    };
+                    if let Some(item) = item {
                         return Poll::Ready(Some(Ok(item)));
                     }
                 }
@@ -65,6 +87,11 @@ pub trait Code<T>: Sized {
 
     fn code(&mut self, item: T) -> io::Result<Option<Self::Item>>;
 
+    /// Code item and flush all pending output of coder. Default to [Code::code].
+    fn code_flush(&mut self, item: T) -> io::Result<Option<Self::Item>> {
+        self.code(item)
+    }
+
     fn code_eof(&mut self) -> io::Result<Option<Self::Item>>;
 }
 
@@ -102,6 +129,10 @@ pub enum FeaturedCode {
     DecodeDe(super::deflate::Decoder),
     #[cfg(feature = "de")]
     EncodeDe(super::deflate::Encoder),
+    #[cfg(feature = "zs")]
+    DecodeZs(super::zstd::Decoder),
+    #[cfg(feature = "zs")]
+    EncodeZs(super::zstd::Encoder),
 }
 
 impl Default for FeaturedCode {
@@ -117,6 +148,11 @@ where
     type Item = Bytes;
 
     fn code(&mut self, item: T) -> io::Result<Option<Self::Item>> {
+        // empty chunk is a flush marker.
+        if item.as_ref().is_empty() {
+            return self.code_flush(item);
+        }
+
         match self {
             Self::NoOp(ref mut coder) => coder.code(item),
             #[cfg(feature = "br")]
@@ -131,6 +167,32 @@ where
             Self::DecodeDe(ref mut coder) => coder.code(item),
             #[cfg(feature = "de")]
             Self::EncodeDe(ref mut coder) => coder.code(item),
+            #[cfg(feature = "zs")]
+            Self::DecodeZs(ref mut coder) => coder.code(item),
+            #[cfg(feature = "zs")]
+            Self::EncodeZs(ref mut coder) => coder.code(item),
+        }
+    }
+
+    fn code_flush(&mut self, item: T) -> io::Result<Option<Self::Item>> {
+        match self {
+            Self::NoOp(ref mut coder) => coder.code_flush(item),
+            #[cfg(feature = "br")]
+            Self::DecodeBr(ref mut coder) => coder.code_flush(item),
+            #[cfg(feature = "br")]
+            Self::EncodeBr(ref mut coder) => coder.code_flush(item),
+            #[cfg(feature = "gz")]
+            Self::DecodeGz(ref mut coder) => coder.code_flush(item),
+            #[cfg(feature = "gz")]
+            Self::EncodeGz(ref mut coder) => coder.code_flush(item),
+            #[cfg(feature = "de")]
+            Self::DecodeDe(ref mut coder) => coder.code_flush(item),
+            #[cfg(feature = "de")]
+            Self::EncodeDe(ref mut coder) => coder.code_flush(item),
+            #[cfg(feature = "zs")]
+            Self::DecodeZs(ref mut coder) => coder.code_flush(item),
+            #[cfg(feature = "zs")]
+            Self::EncodeZs(ref mut coder) => coder.code_flush(item),
         }
     }
 
@@ -149,6 +211,10 @@ where
             Self::DecodeDe(ref mut coder) => <super::deflate::Decoder as Code<T>>::code_eof(coder),
             #[cfg(feature = "de")]
             Self::EncodeDe(ref mut coder) => <super::deflate::Encoder as Code<T>>::code_eof(coder),
+            #[cfg(feature = "zs")]
+            Self::DecodeZs(ref mut coder) => <super::zstd::Decoder as Code<T>>::code_eof(coder),
+            #[cfg(feature = "zs")]
+            Self::EncodeZs(ref mut coder) => <super::zstd::Encoder as Code<T>>::code_eof(coder),
         }
     }
 }
@@ -165,7 +231,22 @@ macro_rules! code_impl {
             fn code(&mut self, item: T) -> ::std::io::Result<Option<Self::Item>> {
                 use ::std::io::Write;
 
+                self.get_mut().feed(item.as_ref().len());
+                self.write_all(item.as_ref())?;
+                let b = self.get_mut().take();
+                if !b.is_empty() {
+                    Ok(Some(b))
+                } else {
+                    Ok(None)
+                }
+            }
+
+            fn code_flush(&mut self, item: T) -> ::std::io::Result<Option<Self::Item>> {
+                use ::std::io::Write;
+
+                self.get_mut().feed(item.as_ref().len());
                 self.write_all(item.as_ref())?;
+                self.flush()?;
                 let b = self.get_mut().take();
                 if !b.is_empty() {
                     Ok(Some(b))
@@ -208,4 +289,76 @@ mod test {
         let bytes = Vec::<u8>::new();
         assert!(try_downcast_to_bytes(bytes).is_err());
     }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn sync_flush() {
+        use std::{
+            sync::Arc,
+            task::{Wake, Waker},
+        };
+
+        struct NoopWaker;
+
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        // a stream yield one chunk and then pending forever.
+        struct Body(bool);
+
+        impl Stream for Body {
+            type Item = Result<Bytes, ()>;
+
+            fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                if self.0 {
+                    Poll::Pending
+                } else {
+                    self.0 = true;
+                    Poll::Ready(Some(Ok(Bytes::from_static(b"data: hello\n\n"))))
+                }
+            }
+        }
+
+        let encoder = FeaturedCode::EncodeGz(crate::gzip::Encoder::new(
+            crate::writer::Writer::new(),
+            flate2::Compression::fast(),
+        ));
+
+        let mut coder = Coder::new(Body(false), encoder).sync_flush();
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+
+        let chunk = match Pin::new(&mut coder).poll_next(&mut cx) {
+            Poll::Ready(Some(Ok(chunk))) => chunk,
+            _ => panic!("flushed chunk must be emitted without more input"),
+        };
+
+        let mut decoder = flate2::write::GzDecoder::new(Vec::new());
+        io::Write::write_all(&mut decoder, &chunk).unwrap();
+        io::Write::flush(&mut decoder).unwrap();
+        assert_eq!(decoder.get_ref().as_slice(), b"data: hello\n\n");
+    }
+
+    #[cfg(feature = "zs")]
+    #[test]
+    fn zstd() {
+        const DATA: &[u8] = b"zstandard is a fast compression algorithm";
+
+        let mut encoder = FeaturedCode::EncodeZs(super::super::zstd::Encoder::new(3));
+        let mut encoded = Vec::new();
+        encoded.extend(encoder.code(DATA).unwrap().unwrap_or_default());
+        encoded.extend(<FeaturedCode as Code<&[u8]>>::code_eof(&mut encoder).unwrap().unwrap());
+
+        let mut decoder = FeaturedCode::DecodeZs(super::super::zstd::Decoder::new(crate::writer::Writer::new()));
+        let mut decoded = Vec::new();
+        decoded.extend(decoder.code(encoded).unwrap().unwrap_or_default());
+        decoded.extend(
+            <FeaturedCode as Code<&[u8]>>::code_eof(&mut decoder)
+                .unwrap()
+                .unwrap_or_default(),
+        );
+
+        assert_eq!(decoded, DATA);
+    }
 }
@@ -11,6 +11,8 @@ pub enum ContentEncoding {
     Deflate,
     /// Gzip algorithm.
     Gzip,
+    /// A format using the Zstandard algorithm.
+    Zstd,
     /// Indicates no operation is done with encoding.
     #[default]
     NoOp,
@@ -18,11 +20,26 @@ pub enum ContentEncoding {
 
 impl ContentEncoding {
     pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self::from_headers_with_preference(headers, &[])
+    }
+
+    /// Select encoding from `Accept-Encoding` headers. When multiple encodings share the highest
+    /// q-value the one appears first in `preference` is selected. Encodings not in `preference`
+    /// rank after the ones in it and keep their order in headers.
+    pub fn from_headers_with_preference(headers: &HeaderMap, preference: &[Self]) -> Self {
+        let rank = |encoding: &Self| {
+            preference
+                .iter()
+                .position(|e| e == encoding)
+                .unwrap_or(preference.len())
+        };
+
         let mut preferred_encoding = Self::NoOp;
         let mut max_qval = 0;
 
         for (encoding, qval) in Self::_from_headers(headers) {
-            if qval.0 > max_qval {
+            if qval.0 > max_qval || (qval.0 == max_qval && max_qval > 0 && rank(&encoding) < rank(&preferred_encoding))
+            {
                 preferred_encoding = encoding;
                 max_qval = qval.0;
             }
@@ -54,19 +71,33 @@ impl ContentEncoding {
             })
     }
 
-    pub(super) fn try_parse(s: &str) -> Result<Self, FeatureError> {
+    /// Parse encoding from its name. `identity` is parsed as [ContentEncoding::NoOp].
+    pub fn try_parse(s: &str) -> Result<Self, FeatureError> {
         if s.eq_ignore_ascii_case("gzip") {
             Ok(Self::Gzip)
         } else if s.eq_ignore_ascii_case("deflate") {
             Ok(Self::Deflate)
         } else if s.eq_ignore_ascii_case("br") {
             Ok(Self::Br)
+        } else if s.eq_ignore_ascii_case("zstd") {
+            Ok(Self::Zstd)
         } else if s.eq_ignore_ascii_case("identity") {
             Ok(Self::NoOp)
         } else {
             Err(FeatureError::Unknown(s.to_string().into_boxed_str()))
         }
     }
+
+    /// Name of encoding. [ContentEncoding::NoOp] is named as `identity`.
+    pub const fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Br => "br",
+            Self::Deflate => "deflate",
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::NoOp => "identity",
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -131,3 +162,29 @@ impl QValue {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use http::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn preference() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ACCEPT_ENCODING,
+            HeaderValue::from_static("gzip, br;q=1.0, zstd, deflate;q=0.5"),
+        );
+
+        assert_eq!(ContentEncoding::from_headers(&headers), ContentEncoding::Gzip);
+        assert_eq!(
+            ContentEncoding::from_headers_with_preference(&headers, &[ContentEncoding::Zstd, ContentEncoding::Br]),
+            ContentEncoding::Zstd
+        );
+        assert_eq!(
+            ContentEncoding::from_headers_with_preference(&headers, &[ContentEncoding::Deflate]),
+            ContentEncoding::Gzip
+        );
+    }
+}
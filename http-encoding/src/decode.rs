@@ -10,60 +10,242 @@ use super::{
 };
 
 /// Construct from headers and stream body. Use for decoding.
+///
+/// Decoded output is limited to 64MiB with [DecodeLimit::new]. Use [try_decoder_with_limit] for other
+/// limit or [DecodeLimit::unlimited] for no limit.
 pub fn try_decoder<Req, S, T, E>(req: Req, body: S) -> Result<Coder<S, FeaturedCode>, EncodingError>
 where
     Req: std::borrow::Borrow<http::Request<()>>,
     S: Stream<Item = Result<T, E>>,
     T: AsRef<[u8]> + 'static,
 {
-    let decoder = from_headers(req.borrow().headers())?;
+    try_decoder_with_limit(req, body, DecodeLimit::new())
+}
+
+/// Construct from headers and stream body with limit of decoded output. Use for decoding.
+///
+/// When limit is exceeded the decoder stream yields [CoderError::LimitExceeded] and the exceeding
+/// output is not buffered.
+///
+/// [CoderError::LimitExceeded]: crate::error::CoderError::LimitExceeded
+pub fn try_decoder_with_limit<Req, S, T, E>(
+    req: Req,
+    body: S,
+    limit: DecodeLimit,
+) -> Result<Coder<S, FeaturedCode>, EncodingError>
+where
+    Req: std::borrow::Borrow<http::Request<()>>,
+    S: Stream<Item = Result<T, E>>,
+    T: AsRef<[u8]> + 'static,
+{
+    let decoder = from_headers(req.borrow().headers(), limit)?;
     Ok(Coder::new(body, decoder))
 }
 
-fn from_headers(headers: &HeaderMap) -> Result<FeaturedCode, EncodingError> {
+/// Limit of decoded output of decoder.
+///
+/// Default to maximum 64MiB of total decoded output and no limit on expansion ratio.
+#[derive(Clone, Copy, Debug)]
+pub struct DecodeLimit {
+    max_size: Option<usize>,
+    max_ratio: Option<usize>,
+}
+
+impl Default for DecodeLimit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecodeLimit {
+    const DEFAULT_MAX_SIZE: usize = 64 * 1024 * 1024;
+
+    /// Construct a new limit with default maximum size of 64MiB.
+    pub const fn new() -> Self {
+        Self {
+            max_size: Some(Self::DEFAULT_MAX_SIZE),
+            max_ratio: None,
+        }
+    }
+
+    /// Construct a new limit with no limit.
+    pub const fn unlimited() -> Self {
+        Self {
+            max_size: None,
+            max_ratio: None,
+        }
+    }
+
+    /// Set maximum size of total decoded output in bytes.
+    pub const fn max_size(mut self, size: usize) -> Self {
+        self.max_size = Some(size);
+        self
+    }
+
+    /// Set maximum ratio of total decoded output size to total input size.
+    pub const fn max_ratio(mut self, ratio: usize) -> Self {
+        self.max_ratio = Some(ratio);
+        self
+    }
+
+    #[cfg(any(feature = "br", feature = "gz", feature = "de", feature = "zs"))]
+    pub(super) fn exceeded(&self, input: usize, output: usize) -> bool {
+        matches!(self.max_size, Some(size) if output > size)
+            || matches!(self.max_ratio, Some(ratio) if output > input.saturating_mul(ratio))
+    }
+}
+
+fn from_headers(headers: &HeaderMap, limit: DecodeLimit) -> Result<FeaturedCode, EncodingError> {
     match headers.get(&CONTENT_ENCODING) {
         None => Ok(FeaturedCode::default()),
         Some(value) => {
             let encoding = value.to_str().map_err(|_| EncodingError::ParseAcceptEncoding)?;
-            match ContentEncoding::try_parse(encoding)? {
-                ContentEncoding::Br => {
-                    #[cfg(feature = "br")]
-                    {
-                        Ok(FeaturedCode::DecodeBr(super::brotli::Decoder::new(
-                            super::writer::Writer::new(),
-                        )))
-                    }
-                    #[cfg(not(feature = "br"))]
-                    {
-                        Err(super::error::FeatureError::Br.into())
-                    }
+            FeaturedCode::try_decoder(ContentEncoding::try_parse(encoding)?, limit)
+        }
+    }
+}
+
+impl FeaturedCode {
+    /// Construct a decoder of given encoding with limit of decoded output. Use for decoding payload
+    /// not described by `Content-Encoding` header. e.g. compressed message of a framed protocol.
+    pub fn try_decoder(encoding: ContentEncoding, limit: DecodeLimit) -> Result<Self, EncodingError> {
+        #[cfg(not(any(feature = "br", feature = "gz", feature = "de", feature = "zs")))]
+        let _ = limit;
+
+        match encoding {
+            ContentEncoding::Br => {
+                #[cfg(feature = "br")]
+                {
+                    Ok(Self::DecodeBr(super::brotli::Decoder::new(
+                        super::writer::Writer::with_limit(limit),
+                    )))
+                }
+                #[cfg(not(feature = "br"))]
+                {
+                    Err(super::error::FeatureError::Br.into())
+                }
+            }
+            ContentEncoding::Gzip => {
+                #[cfg(feature = "gz")]
+                {
+                    Ok(Self::DecodeGz(super::gzip::Decoder::new(
+                        super::writer::Writer::with_limit(limit),
+                    )))
                 }
-                ContentEncoding::Gzip => {
-                    #[cfg(feature = "gz")]
-                    {
-                        Ok(FeaturedCode::DecodeGz(super::gzip::Decoder::new(
-                            super::writer::Writer::new(),
-                        )))
-                    }
-                    #[cfg(not(feature = "gz"))]
-                    {
-                        Err(super::error::FeatureError::Gzip.into())
-                    }
+                #[cfg(not(feature = "gz"))]
+                {
+                    Err(super::error::FeatureError::Gzip.into())
                 }
-                ContentEncoding::Deflate => {
-                    #[cfg(feature = "de")]
-                    {
-                        Ok(FeaturedCode::DecodeDe(super::deflate::Decoder::new(
-                            super::writer::Writer::new(),
-                        )))
-                    }
-                    #[cfg(not(feature = "de"))]
-                    {
-                        Err(super::error::FeatureError::Deflate.into())
-                    }
+            }
+            ContentEncoding::Deflate => {
+                #[cfg(feature = "de")]
+                {
+                    Ok(Self::DecodeDe(super::deflate::Decoder::new(
+                        super::writer::Writer::with_limit(limit),
+                    )))
+                }
+                #[cfg(not(feature = "de"))]
+                {
+                    Err(super::error::FeatureError::Deflate.into())
                 }
-                ContentEncoding::NoOp => Ok(FeaturedCode::default()),
             }
+            ContentEncoding::Zstd => {
+                #[cfg(feature = "zs")]
+                {
+                    Ok(Self::DecodeZs(super::zstd::Decoder::new(
+                        super::writer::Writer::with_limit(limit),
+                    )))
+                }
+                #[cfg(not(feature = "zs"))]
+                {
+                    Err(super::error::FeatureError::Zstd.into())
+                }
+            }
+            ContentEncoding::NoOp => Ok(Self::default()),
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "br", feature = "gz", feature = "zs")))]
+mod test {
+    use http::header::HeaderValue;
+
+    use crate::{coder::Code, error::CoderError};
+
+    use super::*;
+
+    fn encode(mut encoder: FeaturedCode, data: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        encoded.extend(encoder.code(data.to_vec()).unwrap().unwrap_or_default());
+        encoded.extend(
+            <FeaturedCode as Code<Vec<u8>>>::code_eof(&mut encoder)
+                .unwrap()
+                .unwrap(),
+        );
+        encoded
+    }
+
+    fn decode(encoding: &'static str, encoded: &[u8], limit: DecodeLimit) -> Result<usize, CoderError<()>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        let mut decoder = from_headers(&headers, limit).unwrap();
+
+        let mut len = 0;
+        for chunk in encoded.chunks(1024) {
+            len += decoder.code(chunk.to_vec())?.map(|b| b.len()).unwrap_or(0);
+        }
+        len += <FeaturedCode as Code<Vec<u8>>>::code_eof(&mut decoder)?
+            .map(|b| b.len())
+            .unwrap_or(0);
+        Ok(len)
+    }
+
+    #[test]
+    fn limit() {
+        let data = vec![0; 1024 * 1024];
+
+        let encoded = [
+            #[cfg(feature = "br")]
+            (
+                "br",
+                encode(FeaturedCode::EncodeBr(crate::brotli::Encoder::new(3)), &data),
+            ),
+            #[cfg(feature = "gz")]
+            (
+                "gzip",
+                encode(
+                    FeaturedCode::EncodeGz(crate::gzip::Encoder::new(
+                        crate::writer::Writer::new(),
+                        flate2::Compression::best(),
+                    )),
+                    &data,
+                ),
+            ),
+            #[cfg(feature = "zs")]
+            (
+                "zstd",
+                encode(FeaturedCode::EncodeZs(crate::zstd::Encoder::new(3)), &data),
+            ),
+        ];
+
+        for (encoding, encoded) in encoded {
+            assert_eq!(decode(encoding, &encoded, DecodeLimit::new()).unwrap(), data.len());
+            assert_eq!(
+                decode(encoding, &encoded, DecodeLimit::unlimited()).unwrap(),
+                data.len()
+            );
+            assert_eq!(
+                decode(encoding, &encoded, DecodeLimit::new().max_size(data.len())).unwrap(),
+                data.len()
+            );
+            assert!(matches!(
+                decode(encoding, &encoded, DecodeLimit::new().max_size(1024)),
+                Err(CoderError::LimitExceeded)
+            ));
+            assert!(matches!(
+                decode(encoding, &encoded, DecodeLimit::new().max_ratio(10)),
+                Err(CoderError::LimitExceeded)
+            ));
         }
     }
 }
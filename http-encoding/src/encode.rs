@@ -6,15 +6,35 @@ use http::{header, Response, StatusCode};
 use super::{
     coder::{Coder, FeaturedCode},
     coding::ContentEncoding,
+    error::FeatureError,
 };
 
 /// Construct from headers and stream body. Use for encoding.
-pub fn encoder<S, T, E>(response: Response<S>, mut encoding: ContentEncoding) -> Response<Coder<S, FeaturedCode>>
+pub fn encoder<S, T, E>(response: Response<S>, encoding: ContentEncoding) -> Response<Coder<S, FeaturedCode>>
+where
+    S: Stream<Item = Result<T, E>>,
+    T: AsRef<[u8]> + 'static,
+{
+    encoder_with_level(response, encoding, None)
+}
+
+/// Construct from headers and stream body with given compression level. Use for encoding.
+///
+/// Level is interpreted by the algorithm of encoding and clamped to its valid range:
+/// - `Br`: 0-11. Default to 3.
+/// - `Deflate` and `Gzip`: 0-9. Default to 1.
+/// - `Zstd`: 1-22. Default to 3.
+///
+/// `None` level uses the default level of algorithm.
+pub fn encoder_with_level<S, T, E>(
+    response: Response<S>,
+    mut encoding: ContentEncoding,
+    level: Option<u32>,
+) -> Response<Coder<S, FeaturedCode>>
 where
     S: Stream<Item = Result<T, E>>,
     T: AsRef<[u8]> + 'static,
 {
-    #[allow(unused_mut)]
     let (mut parts, body) = response.into_parts();
 
     if parts.headers.contains_key(&header::CONTENT_ENCODING)
@@ -24,38 +44,89 @@ where
         encoding = ContentEncoding::NoOp
     }
 
-    let encoder = {
+    let encoder = match FeaturedCode::try_encoder(encoding, level) {
+        Ok(encoder) if encoding != ContentEncoding::NoOp => {
+            update_header(&mut parts.headers, encoding.as_str());
+            encoder
+        }
+        _ => FeaturedCode::default(),
+    };
+
+    let body = Coder::new(body, encoder);
+    Response::from_parts(parts, body)
+}
+
+impl FeaturedCode {
+    /// Construct an encoder of given encoding with given compression level. Use for encoding payload
+    /// not described by `Content-Encoding` header. e.g. compressed message of a framed protocol.
+    ///
+    /// See [encoder_with_level] for interpretation of level.
+    pub fn try_encoder(encoding: ContentEncoding, level: Option<u32>) -> Result<Self, FeatureError> {
+        #[cfg(not(any(feature = "br", feature = "gz", feature = "de", feature = "zs")))]
+        let _ = level;
+
         match encoding {
-            #[cfg(feature = "de")]
             ContentEncoding::Deflate => {
-                update_header(&mut parts.headers, "deflate");
-                FeaturedCode::EncodeDe(super::deflate::Encoder::new(
-                    super::writer::Writer::new(),
-                    flate2::Compression::fast(),
-                ))
+                #[cfg(feature = "de")]
+                {
+                    Ok(Self::EncodeDe(super::deflate::Encoder::new(
+                        super::writer::Writer::new(),
+                        flate_level(level),
+                    )))
+                }
+                #[cfg(not(feature = "de"))]
+                {
+                    Err(FeatureError::Deflate)
+                }
             }
-            #[cfg(feature = "gz")]
             ContentEncoding::Gzip => {
-                update_header(&mut parts.headers, "gzip");
-                FeaturedCode::EncodeGz(super::gzip::Encoder::new(
-                    super::writer::Writer::new(),
-                    flate2::Compression::fast(),
-                ))
+                #[cfg(feature = "gz")]
+                {
+                    Ok(Self::EncodeGz(super::gzip::Encoder::new(
+                        super::writer::Writer::new(),
+                        flate_level(level),
+                    )))
+                }
+                #[cfg(not(feature = "gz"))]
+                {
+                    Err(FeatureError::Gzip)
+                }
             }
-            #[cfg(feature = "br")]
             ContentEncoding::Br => {
-                update_header(&mut parts.headers, "br");
-                FeaturedCode::EncodeBr(super::brotli::Encoder::new(3))
+                #[cfg(feature = "br")]
+                {
+                    Ok(Self::EncodeBr(super::brotli::Encoder::new(level.unwrap_or(3).min(11))))
+                }
+                #[cfg(not(feature = "br"))]
+                {
+                    Err(FeatureError::Br)
+                }
             }
-            _ => FeaturedCode::default(),
+            ContentEncoding::Zstd => {
+                #[cfg(feature = "zs")]
+                {
+                    Ok(Self::EncodeZs(super::zstd::Encoder::new(
+                        level.unwrap_or(3).clamp(1, 22) as i32,
+                    )))
+                }
+                #[cfg(not(feature = "zs"))]
+                {
+                    Err(FeatureError::Zstd)
+                }
+            }
+            ContentEncoding::NoOp => Ok(Self::default()),
         }
-    };
+    }
+}
 
-    let body = Coder::new(body, encoder);
-    Response::from_parts(parts, body)
+#[cfg(any(feature = "gz", feature = "de"))]
+fn flate_level(level: Option<u32>) -> flate2::Compression {
+    match level {
+        Some(level) => flate2::Compression::new(level.min(9)),
+        None => flate2::Compression::fast(),
+    }
 }
 
-#[cfg(any(feature = "br", feature = "gz", feature = "de"))]
 fn update_header(headers: &mut header::HeaderMap, value: &'static str) {
     headers.insert(header::CONTENT_ENCODING, header::HeaderValue::from_static(value));
     headers.remove(header::CONTENT_LENGTH);
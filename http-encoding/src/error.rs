@@ -6,6 +6,9 @@ use std::{error, fmt, io};
 pub enum EncodingError {
     MissingFeature(FeatureError),
     ParseAcceptEncoding,
+    /// Decoded request body exceeded [DecodeLimit](crate::DecodeLimit). Used by consumer of
+    /// decoder to report [CoderError::LimitExceeded] as request error.
+    LimitExceeded,
 }
 
 impl fmt::Display for EncodingError {
@@ -13,10 +16,13 @@ impl fmt::Display for EncodingError {
         match *self {
             Self::MissingFeature(ref e) => write!(f, "{e}"),
             Self::ParseAcceptEncoding => write!(f, "failed to parse Accept-Encoding header value"),
+            Self::LimitExceeded => f.write_str("decoded body exceeded limit"),
         }
     }
 }
 
+impl error::Error for EncodingError {}
+
 /// Error for missing required feature.
 #[derive(Debug)]
 #[non_exhaustive]
@@ -24,6 +30,7 @@ pub enum FeatureError {
     Br,
     Gzip,
     Deflate,
+    Zstd,
     Unknown(Box<str>),
 }
 
@@ -33,6 +40,7 @@ impl fmt::Display for FeatureError {
             Self::Br => feature_error_fmt("brotil", f),
             Self::Gzip => feature_error_fmt("gzip", f),
             Self::Deflate => feature_error_fmt("deflate", f),
+            Self::Zstd => feature_error_fmt("zstd", f),
             Self::Unknown(ref encoding) => feature_error_fmt(encoding, f),
         }
     }
@@ -53,9 +61,12 @@ impl From<FeatureError> for EncodingError {
 }
 
 /// Error occur when decode/encode request/response body stream.
+#[non_exhaustive]
 pub enum CoderError<E> {
     Io(io::Error),
     Stream(E),
+    /// Decoded output exceeded [DecodeLimit](crate::DecodeLimit).
+    LimitExceeded,
 }
 
 impl<E> fmt::Debug for CoderError<E>
@@ -66,6 +77,7 @@ where
         match *self {
             Self::Io(ref e) => write!(f, "{:?}", e),
             Self::Stream(ref e) => write!(f, "{:?}", e),
+            Self::LimitExceeded => f.write_str("LimitExceeded"),
         }
     }
 }
@@ -78,6 +90,7 @@ where
         match *self {
             Self::Io(ref e) => write!(f, "{e}"),
             Self::Stream(ref e) => write!(f, "{e}"),
+            Self::LimitExceeded => f.write_str("decoded body exceeded limit"),
         }
     }
 }
@@ -86,6 +99,21 @@ impl<E> error::Error for CoderError<E> where E: fmt::Debug + fmt::Display {}
 
 impl<E> From<io::Error> for CoderError<E> {
     fn from(e: io::Error) -> Self {
-        Self::Io(e)
+        match e.get_ref() {
+            Some(inner) if inner.is::<DecodeLimitExceeded>() => Self::LimitExceeded,
+            _ => Self::Io(e),
+        }
+    }
+}
+
+// io error payload emitted by decoder's writer when decode limit is exceeded.
+#[derive(Debug)]
+pub(crate) struct DecodeLimitExceeded;
+
+impl fmt::Display for DecodeLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("decoded body exceeded limit")
     }
 }
+
+impl error::Error for DecodeLimitExceeded {}
@@ -10,7 +10,7 @@ mod coding;
 mod decode;
 mod encode;
 
-#[cfg(any(feature = "br", feature = "gz", feature = "de"))]
+#[cfg(any(feature = "br", feature = "gz", feature = "de", feature = "zs"))]
 mod writer;
 
 #[cfg(feature = "br")]
@@ -38,6 +38,7 @@ mod brotli {
         type Item = Bytes;
 
         fn code(&mut self, item: T) -> io::Result<Option<Self::Item>> {
+            self.get_mut().feed(item.as_ref().len());
             self.write_all(item.as_ref())?;
             self.flush()?;
             let b = self.get_mut().take();
@@ -114,7 +115,94 @@ mod deflate {
     code_impl!(DeflateEncoder);
 }
 
+#[cfg(feature = "zs")]
+mod zstd {
+    use std::io::{self, Write};
+
+    use ::zstd::stream::write::{Decoder as ZstdDecoder, Encoder as ZstdEncoder};
+    use bytes::Bytes;
+
+    use super::{coder::Code, writer::Writer};
+
+    pub struct Decoder(ZstdDecoder<'static, Writer>);
+    pub struct Encoder(Option<ZstdEncoder<'static, Writer>>);
+
+    impl Decoder {
+        pub(crate) fn new(writer: Writer) -> Self {
+            // zstd context creation only fails when allocation failed.
+            Self(ZstdDecoder::new(writer).expect("zstd decoder context allocation failed"))
+        }
+    }
+
+    impl Encoder {
+        pub(crate) fn new(level: i32) -> Self {
+            Self(Some(
+                ZstdEncoder::new(Writer::new(), level).expect("zstd encoder context allocation failed"),
+            ))
+        }
+    }
+
+    impl<T> Code<T> for Decoder
+    where
+        T: AsRef<[u8]>,
+    {
+        type Item = Bytes;
+
+        fn code(&mut self, item: T) -> io::Result<Option<Self::Item>> {
+            self.0.get_mut().feed(item.as_ref().len());
+            self.0.write_all(item.as_ref())?;
+            self.0.flush()?;
+            let b = self.0.get_mut().take();
+            if !b.is_empty() {
+                Ok(Some(b))
+            } else {
+                Ok(None)
+            }
+        }
+
+        fn code_eof(&mut self) -> io::Result<Option<Self::Item>> {
+            self.0.flush()?;
+            let b = self.0.get_mut().take();
+            if !b.is_empty() {
+                Ok(Some(b))
+            } else {
+                Ok(None)
+            }
+        }
+    }
+
+    impl<T> Code<T> for Encoder
+    where
+        T: AsRef<[u8]>,
+    {
+        type Item = Bytes;
+
+        fn code(&mut self, item: T) -> io::Result<Option<Self::Item>> {
+            let encoder = self.0.as_mut().unwrap();
+            encoder.write_all(item.as_ref())?;
+            encoder.flush()?;
+            let b = encoder.get_mut().take();
+            if !b.is_empty() {
+                Ok(Some(b))
+            } else {
+                Ok(None)
+            }
+        }
+
+        fn code_eof(&mut self) -> io::Result<Option<Self::Item>> {
+            match self.0.take() {
+                Some(encoder) => {
+                    let b = encoder.finish()?.take_owned();
+                    assert!(!b.is_empty());
+                    Ok(Some(b))
+                }
+                None => Ok(None),
+            }
+        }
+    }
+}
+
 pub use self::coder::{Code, Coder, FeaturedCode};
 pub use self::coding::ContentEncoding;
-pub use self::decode::try_decoder;
-pub use self::encode::encoder;
+pub use self::decode::{try_decoder, try_decoder_with_limit, DecodeLimit};
+pub use self::encode::{encoder, encoder_with_level};
@@ -2,20 +2,39 @@ use std::io;
 
 use bytes::{BufMut, Bytes, BytesMut};
 
+use super::{decode::DecodeLimit, error::DecodeLimitExceeded};
+
 pub struct Writer {
     buf: BytesMut,
+    limit: DecodeLimit,
+    input: usize,
+    output: usize,
 }
 
 impl Writer {
     pub(super) fn new() -> Writer {
-        Writer { buf: BytesMut::new() }
+        Self::with_limit(DecodeLimit::unlimited())
+    }
+
+    pub(super) fn with_limit(limit: DecodeLimit) -> Writer {
+        Writer {
+            buf: BytesMut::new(),
+            limit,
+            input: 0,
+            output: 0,
+        }
+    }
+
+    // count bytes of input fed to the coder writing to self. used for checking expansion ratio.
+    pub(super) fn feed(&mut self, len: usize) {
+        self.input += len;
     }
 
     pub(super) fn take(&mut self) -> Bytes {
         self.buf.split().freeze()
     }
 
-    #[cfg(feature = "br")]
+    #[cfg(any(feature = "br", feature = "zs"))]
     pub(super) fn take_owned(self) -> Bytes {
         self.buf.freeze()
     }
@@ -24,6 +43,10 @@ impl Writer {
 impl io::Write for Writer {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output += buf.len();
+        if self.limit.exceeded(self.input, self.output) {
+            return Err(io::Error::new(io::ErrorKind::Other, DecodeLimitExceeded));
+        }
         self.buf.put_slice(buf);
         Ok(buf.len())
     }
@@ -57,6 +57,19 @@ impl Stream for RequestBody {
             Self::None => Poll::Ready(None),
         }
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            #[cfg(feature = "http1")]
+            Self::H1(body) => body.size_hint(),
+            #[cfg(feature = "http2")]
+            Self::H2(body) => body.size_hint(),
+            #[cfg(feature = "http3")]
+            Self::H3(body) => body.size_hint(),
+            Self::None => (0, None),
+        }
+    }
 }
 
 /// None body type.
@@ -119,7 +132,7 @@ where
     fn size_hint(&self) -> (usize, Option<usize>) {
         match self.0 {
             Some(ref b) => exact_body_hint(b.remaining()),
-            None => unreachable!("Once must check size_hint before it got polled"),
+            None => exact_body_hint(0),
         }
     }
 }
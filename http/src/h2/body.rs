@@ -6,7 +6,7 @@ use std::{
 use futures_core::Stream;
 use h2::RecvStream;
 
-use crate::{bytes::Bytes, error::BodyError};
+use crate::{body::exact_body_hint, bytes::Bytes, error::BodyError};
 
 /// Request body type for Http/2 specifically.
 pub struct RequestBody(RecvStream);
@@ -26,6 +26,14 @@ impl Stream for RequestBody {
             })
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.0.is_end_stream() {
+            exact_body_hint(0)
+        } else {
+            (0, None)
+        }
+    }
 }
 
 impl From<RequestBody> for crate::body::RequestBody {
@@ -32,6 +32,7 @@ use crate::{
         Version,
     },
     request::{RemoteAddr, Request},
+    response::Trailers,
     util::{futures::Queue, keep_alive::KeepAlive},
 };
 
@@ -245,10 +246,16 @@ where
 
     let mut trailers = HeaderMap::with_capacity(0);
 
-    while let Some(value) = res.headers_mut().remove(TRAILER) {
-        let name = HeaderName::from_bytes(value.as_bytes()).unwrap();
-        let value = res.headers_mut().remove(name.clone()).unwrap();
-        trailers.append(name, value);
+    // trailers only known when response body ends.
+    let deferred = res.extensions_mut().remove::<Trailers>();
+
+    // response without body can not have trailers. headers named by trailer header are sent as they are.
+    if !is_eof {
+        while let Some(value) = res.headers_mut().remove(TRAILER) {
+            let name = HeaderName::from_bytes(value.as_bytes()).unwrap();
+            let value = res.headers_mut().remove(name.clone()).unwrap();
+            trailers.append(name, value);
+        }
     }
 
     if !res.headers().contains_key(DATE) {
@@ -291,9 +298,13 @@ where
                 stream.send_data(bytes, false)?;
             }
         }
-    }
 
-    stream.send_trailers(trailers)?;
+        if let Some(deferred) = deferred {
+            trailers.extend(deferred.take());
+        }
+
+        stream.send_trailers(trailers)?;
+    }
 
     Ok(state)
 }
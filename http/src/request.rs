@@ -62,6 +62,14 @@ impl<B> Request<B> {
         &self.remote_addr
     }
 
+    /// Get mutable reference of remote socket address of this request's source.
+    ///
+    /// Useful for overriding it with the real client address when the request comes from a proxy.
+    #[inline]
+    pub fn remote_addr_mut(&mut self) -> &mut RemoteAddr {
+        &mut self.remote_addr
+    }
+
     #[inline]
     pub fn map_body<F, B1>(self, func: F) -> Request<B1>
     where
@@ -159,6 +167,12 @@ impl<B> BorrowReq<http::Method> for http::Request<B> {
     }
 }
 
+impl<B> BorrowReq<http::HeaderMap> for http::Request<B> {
+    fn borrow(&self) -> &http::HeaderMap {
+        self.headers()
+    }
+}
+
 impl<B> BorrowReqMut<http::Extensions> for http::Request<B> {
     fn borrow_mut(&mut self) -> &mut http::Extensions {
         self.extensions_mut()
@@ -1,8 +1,40 @@
 #[cfg(feature = "http1")]
 pub(super) use h1_impl::*;
 
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+};
+
+use crate::http::header::HeaderMap;
+
 pub use crate::http::response::Response;
 
+/// Trailers of response that are only known when response body stream ends. e.g. status of a
+/// streaming call.
+///
+/// Insert it into [Response]'s extensions and keep a clone of it in response body stream. Headers
+/// added before body stream ends are sent as trailers after the body. Only http/2 is supported.
+#[derive(Clone, Debug, Default)]
+pub struct Trailers(Arc<Mutex<HeaderMap>>);
+
+impl Trailers {
+    /// Construct an empty trailers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add headers to trailers. Existing trailer with the same name is replaced.
+    pub fn extend(&self, headers: HeaderMap) {
+        self.0.lock().unwrap().extend(headers);
+    }
+
+    /// Take all trailers added so far.
+    pub fn take(&self) -> HeaderMap {
+        mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
 #[cfg(feature = "http1")]
 mod h1_impl {
     use crate::{body::Once, bytes::Bytes, http::status::StatusCode};
@@ -0,0 +1,230 @@
+//! Request guards for discriminating services registered with [Route](super::route::Route)
+//! beyond http method.
+//!
+//! A guard rejecting a request makes the request fall through to the next route in chain.
+
+use crate::{
+    http::{
+        header::{HeaderName, CONTENT_TYPE},
+        HeaderMap, Uri,
+    },
+    request::BorrowReq,
+};
+
+/// Predicate on request deciding if a route can handle it.
+pub trait Guard<Req> {
+    fn check(&self, req: &Req) -> bool;
+}
+
+/// Combinator methods for [Guard] types.
+pub trait GuardExt<Req>: Guard<Req> + Sized {
+    /// Pass when both self and other guard pass.
+    fn and<G>(self, other: G) -> And<Self, G>
+    where
+        G: Guard<Req>,
+    {
+        And(self, other)
+    }
+
+    /// Pass when either self or other guard pass.
+    fn or<G>(self, other: G) -> Or<Self, G>
+    where
+        G: Guard<Req>,
+    {
+        Or(self, other)
+    }
+
+    /// Pass when self guard does not.
+    fn not(self) -> Not<Self> {
+        Not(self)
+    }
+}
+
+impl<G, Req> GuardExt<Req> for G where G: Guard<Req> {}
+
+/// Guard that always pass. Used by [Route](super::route::Route) when no guard is set.
+impl<Req> Guard<Req> for () {
+    #[inline]
+    fn check(&self, _: &Req) -> bool {
+        true
+    }
+}
+
+#[derive(Clone)]
+pub struct And<A, B>(A, B);
+
+impl<A, B, Req> Guard<Req> for And<A, B>
+where
+    A: Guard<Req>,
+    B: Guard<Req>,
+{
+    #[inline]
+    fn check(&self, req: &Req) -> bool {
+        self.0.check(req) && self.1.check(req)
+    }
+}
+
+#[derive(Clone)]
+pub struct Or<A, B>(A, B);
+
+impl<A, B, Req> Guard<Req> for Or<A, B>
+where
+    A: Guard<Req>,
+    B: Guard<Req>,
+{
+    #[inline]
+    fn check(&self, req: &Req) -> bool {
+        self.0.check(req) || self.1.check(req)
+    }
+}
+
+#[derive(Clone)]
+pub struct Not<G>(G);
+
+impl<G, Req> Guard<Req> for Not<G>
+where
+    G: Guard<Req>,
+{
+    #[inline]
+    fn check(&self, req: &Req) -> bool {
+        !self.0.check(req)
+    }
+}
+
+/// Guard on presence of header with given name.
+pub fn header(name: HeaderName) -> Header {
+    Header { name, value: None }
+}
+
+/// Guard on header with given name and value. Pass when any of the header values equals to given value.
+pub fn header_eq(name: HeaderName, value: &'static str) -> Header {
+    Header {
+        name,
+        value: Some(value),
+    }
+}
+
+#[derive(Clone)]
+pub struct Header {
+    name: HeaderName,
+    value: Option<&'static str>,
+}
+
+impl<Req> Guard<Req> for Header
+where
+    Req: BorrowReq<HeaderMap>,
+{
+    fn check(&self, req: &Req) -> bool {
+        let mut values = req.borrow().get_all(&self.name).iter();
+        match self.value {
+            Some(value) => values.any(|v| v == value),
+            None => values.next().is_some(),
+        }
+    }
+}
+
+/// Guard on mime type of `Content-Type` header. Parameters like charset are ignored.
+///
+/// # Example:
+/// ```rust
+/// # use xitca_http::util::service::guard::content_type;
+/// // pass on `Content-Type: application/json; charset=utf-8`
+/// let guard = content_type("application/json");
+/// ```
+pub fn content_type(mime: &'static str) -> ContentType {
+    ContentType(mime)
+}
+
+#[derive(Clone)]
+pub struct ContentType(&'static str);
+
+impl<Req> Guard<Req> for ContentType
+where
+    Req: BorrowReq<HeaderMap>,
+{
+    fn check(&self, req: &Req) -> bool {
+        req.borrow()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(';').next())
+            .map(|mime| mime.trim().eq_ignore_ascii_case(self.0))
+            .unwrap_or(false)
+    }
+}
+
+/// Guard on presence of query parameter with given name. Name is compared without percent decoding.
+pub fn query(name: &'static str) -> Query {
+    Query(name)
+}
+
+#[derive(Clone)]
+pub struct Query(&'static str);
+
+impl<Req> Guard<Req> for Query
+where
+    Req: BorrowReq<Uri>,
+{
+    fn check(&self, req: &Req) -> bool {
+        req.borrow()
+            .query()
+            .map(|query| query.split('&').any(|pair| pair.split('=').next() == Some(self.0)))
+            .unwrap_or(false)
+    }
+}
+
+/// Guard with custom predicate function.
+pub fn fn_guard<F>(f: F) -> FnGuard<F> {
+    FnGuard(f)
+}
+
+#[derive(Clone)]
+pub struct FnGuard<F>(F);
+
+impl<F, Req> Guard<Req> for FnGuard<F>
+where
+    F: Fn(&Req) -> bool,
+{
+    #[inline]
+    fn check(&self, req: &Req) -> bool {
+        (self.0)(req)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::http::{header::ACCEPT, HeaderValue, Method, Request};
+
+    use super::*;
+
+    #[test]
+    fn guards() {
+        let mut req = Request::new(());
+        req.headers_mut().append(ACCEPT, HeaderValue::from_static("text/html"));
+        req.headers_mut()
+            .append(ACCEPT, HeaderValue::from_static("application/json"));
+        req.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("Application/Json; charset=utf-8"),
+        );
+        *req.uri_mut() = Uri::from_static("/foo?bar=1&baz");
+
+        assert!(header(ACCEPT).check(&req));
+        assert!(header_eq(ACCEPT, "application/json").check(&req));
+        assert!(!header_eq(ACCEPT, "text/plain").check(&req));
+        assert!(!header(HeaderName::from_static("accept-version")).check(&req));
+
+        assert!(content_type("application/json").check(&req));
+        assert!(!content_type("application").check(&req));
+
+        assert!(query("bar").check(&req));
+        assert!(query("baz").check(&req));
+        assert!(!query("ba").check(&req));
+
+        let get = fn_guard(|req: &Request<()>| req.method() == Method::GET);
+        assert!(get.clone().check(&req));
+        assert!(!get.clone().not().check(&req));
+        assert!(get.clone().and(query("bar")).check(&req));
+        assert!(!get.clone().and(query("qux")).check(&req));
+        assert!(get.not().or(query("bar")).check(&req));
+    }
+}
@@ -0,0 +1,377 @@
+use std::{future::Future, marker::PhantomData};
+
+use xitca_service::{
+    object::{DefaultFactoryObject, DefaultObjectConstructor, ObjectConstructor},
+    ready::ReadyService,
+    BuildService, Service,
+};
+
+use crate::{
+    http::{header::HOST, Extensions, HeaderMap, Uri},
+    request::{BorrowReq, BorrowReqMut},
+};
+
+use super::router_priv::{MatchError, RouterError};
+
+/// A [GenericHostRouter] specialized with [DefaultObjectConstructor]
+pub type HostRouter<Req, Arg, BErr, Res, Err> =
+    GenericHostRouter<DefaultObjectConstructor<Req, Arg>, DefaultFactoryObject<Arg, Req, BErr, Res, Err>>;
+
+/// Simple router for matching on [Request]'s host and call according service.
+///
+/// Host is taken from `Host` header and falls back to the authority of request uri
+/// (`:authority` pseudo header for HTTP/2 and HTTP/3). Port is ignored when matching.
+///
+/// A host pattern is a list of dot separated labels where each label can be:
+/// - a literal label. e.g. `api.example.com`. compared case-insensitively.
+/// - `*` matching any single label. e.g. `*.example.com`.
+/// - `{name}` matching any single label and capture it as named parameter. e.g. `{tenant}.example.com`.
+///   captured parameters are stored in request's [Extensions] as [HostParams].
+///
+/// Patterns without wildcard are always matched first. Patterns with wildcard are matched in the
+/// order they are inserted.
+///
+/// [Request]: crate::request::Request
+pub struct GenericHostRouter<ObjCons, SF> {
+    hosts: Vec<(&'static str, SF)>,
+    _req_body: PhantomData<ObjCons>,
+}
+
+/// Named parameters captured from request's host by [GenericHostRouter].
+#[derive(Clone, Debug, Default)]
+pub struct HostParams {
+    params: Vec<(&'static str, String)>,
+}
+
+impl HostParams {
+    /// Get captured label by name of parameter.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, label)| label.as_str())
+    }
+
+    /// Iterate over captured (name, label) pairs in the order they appear in host pattern.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(n, label)| (*n, label.as_str()))
+    }
+}
+
+impl<ObjCons, SF> Default for GenericHostRouter<ObjCons, SF> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<SF> GenericHostRouter<(), SF> {
+    /// Creates a new host router with the [default object constructor](DefaultObjectConstructor).
+    pub fn with_default_object<Req, Arg>() -> GenericHostRouter<DefaultObjectConstructor<Req, Arg>, SF> {
+        GenericHostRouter::new()
+    }
+
+    /// Creates a new host router with a custom [object constructor](ObjectConstructor).
+    pub fn with_custom_object<ObjCons>() -> GenericHostRouter<ObjCons, SF> {
+        GenericHostRouter::new()
+    }
+}
+
+impl<ObjCons, SF> GenericHostRouter<ObjCons, SF> {
+    pub fn new() -> Self {
+        Self {
+            hosts: Vec::new(),
+            _req_body: PhantomData,
+        }
+    }
+
+    /// Insert a new service factory to given host pattern.
+    ///
+    /// # Panic:
+    ///
+    /// When multiple services inserted with the same host pattern or host pattern is empty.
+    pub fn insert<F>(mut self, host: &'static str, factory: F) -> Self
+    where
+        ObjCons: ObjectConstructor<F, Object = SF>,
+    {
+        assert!(!host.is_empty(), "host pattern must not be empty");
+        assert!(
+            !self.hosts.iter().any(|(h, _)| h.eq_ignore_ascii_case(host)),
+            "host pattern {host} is already inserted"
+        );
+        self.hosts.push((host, ObjCons::into_object(factory)));
+        self
+    }
+}
+
+impl<ObjCons, SF, Arg> BuildService<Arg> for GenericHostRouter<ObjCons, SF>
+where
+    SF: BuildService<Arg>,
+    Arg: Clone,
+{
+    type Service = HostRouterService<SF::Service>;
+    type Error = SF::Error;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, arg: Arg) -> Self::Future {
+        let futs = self
+            .hosts
+            .iter()
+            .map(|(host, obj)| (*host, obj.build(arg.clone())))
+            .collect::<Vec<_>>();
+
+        async move {
+            let mut hosts = Vec::with_capacity(futs.len());
+
+            for (host, fut) in futs {
+                let service = fut.await?;
+                hosts.push((HostPattern::new(host), service));
+            }
+
+            // stable sort keeps insertion order among wildcard patterns.
+            hosts.sort_by_key(|(pattern, _)| pattern.is_wildcard());
+
+            Ok(HostRouterService { hosts })
+        }
+    }
+}
+
+pub struct HostRouterService<S> {
+    hosts: Vec<(HostPattern, S)>,
+}
+
+impl<S> HostRouterService<S> {
+    /// Find the service matching request's host.
+    ///
+    /// On success captured [HostParams] (if any) are inserted into request's [Extensions].
+    pub fn find<Req>(&self, req: &mut Req) -> Option<&S>
+    where
+        Req: BorrowReq<Uri> + BorrowReq<HeaderMap> + BorrowReqMut<Extensions>,
+    {
+        let host = request_host(req)?;
+
+        let mut params = Vec::new();
+        let service = self.hosts.iter().find_map(|(pattern, service)| {
+            params.clear();
+            pattern.matches(host, &mut params).then_some(service)
+        })?;
+
+        if !params.is_empty() {
+            BorrowReqMut::<Extensions>::borrow_mut(req).insert(HostParams { params });
+        }
+
+        Some(service)
+    }
+}
+
+impl<S, Req> Service<Req> for HostRouterService<S>
+where
+    S: Service<Req>,
+    Req: BorrowReq<Uri> + BorrowReq<HeaderMap> + BorrowReqMut<Extensions>,
+{
+    type Response = S::Response;
+    type Error = RouterError<S::Error>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    #[inline]
+    fn call(&self, mut req: Req) -> Self::Future<'_> {
+        async move {
+            let service = self
+                .find(&mut req)
+                .ok_or_else(|| RouterError::First(MatchError::not_found()))?;
+
+            service.call(req).await.map_err(RouterError::Second)
+        }
+    }
+}
+
+impl<S, Req> ReadyService<Req> for HostRouterService<S>
+where
+    S: Service<Req>,
+    Req: BorrowReq<Uri> + BorrowReq<HeaderMap> + BorrowReqMut<Extensions>,
+{
+    type Ready = ();
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where S: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async {}
+    }
+}
+
+enum Label {
+    Exact(&'static str),
+    Any,
+    Param(&'static str),
+}
+
+struct HostPattern {
+    labels: Vec<Label>,
+}
+
+impl HostPattern {
+    fn new(host: &'static str) -> Self {
+        let labels = host
+            .trim_end_matches('.')
+            .split('.')
+            .map(|label| match label {
+                "*" => Label::Any,
+                l if l.len() > 2 && l.starts_with('{') && l.ends_with('}') => Label::Param(&l[1..l.len() - 1]),
+                l => Label::Exact(l),
+            })
+            .collect();
+
+        Self { labels }
+    }
+
+    fn is_wildcard(&self) -> bool {
+        self.labels.iter().any(|label| !matches!(label, Label::Exact(_)))
+    }
+
+    fn matches(&self, host: &str, params: &mut Vec<(&'static str, String)>) -> bool {
+        let mut split = host.split('.');
+
+        for label in self.labels.iter() {
+            let part = match split.next() {
+                Some(part) => part,
+                None => return false,
+            };
+
+            match *label {
+                Label::Exact(exact) => {
+                    if !exact.eq_ignore_ascii_case(part) {
+                        return false;
+                    }
+                }
+                Label::Any => {
+                    if part.is_empty() {
+                        return false;
+                    }
+                }
+                Label::Param(name) => {
+                    if part.is_empty() {
+                        return false;
+                    }
+                    params.push((name, part.to_ascii_lowercase()));
+                }
+            }
+        }
+
+        split.next().is_none()
+    }
+}
+
+// extract host from request without port and trailing dot.
+fn request_host<Req>(req: &Req) -> Option<&str>
+where
+    Req: BorrowReq<Uri> + BorrowReq<HeaderMap>,
+{
+    let host = match BorrowReq::<HeaderMap>::borrow(req).get(HOST) {
+        Some(host) => {
+            let host = host.to_str().ok()?;
+            match host.strip_prefix('[') {
+                // ipv6 literal.
+                Some(rest) => &host[..rest.find(']')? + 2],
+                None => host.split(':').next()?,
+            }
+        }
+        None => BorrowReq::<Uri>::borrow(req).host()?,
+    };
+
+    Some(host.trim_end_matches('.'))
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::Infallible;
+
+    use xitca_service::{fn_service, Service};
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{http, request::Request, response::Response};
+
+    use super::*;
+
+    fn req(host: &str) -> Request<()> {
+        let mut req = Request::new(());
+        req.headers_mut().insert(HOST, host.parse().unwrap());
+        req
+    }
+
+    #[test]
+    fn host_match() {
+        let service = HostRouter::new()
+            .insert(
+                "api.example.com",
+                fn_service(|_: Request<()>| async { Ok::<_, Infallible>(Response::new("api")) }),
+            )
+            .insert(
+                "*.example.com",
+                fn_service(|_: Request<()>| async { Ok::<_, Infallible>(Response::new("any")) }),
+            )
+            .insert(
+                "{tenant}.{region}.example.com",
+                fn_service(|req: Request<()>| async move {
+                    let params = req.extensions().get::<HostParams>().unwrap();
+                    assert_eq!(params.get("tenant"), Some("foo"));
+                    assert_eq!(params.get("region"), Some("eu"));
+                    assert_eq!(params.iter().count(), 2);
+                    Ok::<_, Infallible>(Response::new("tenant"))
+                }),
+            )
+            .build(())
+            .now_or_panic()
+            .unwrap();
+
+        let res = service.call(req("api.example.com")).now_or_panic().unwrap();
+        assert_eq!(*res.body(), "api");
+
+        let res = service.call(req("API.Example.com:8080")).now_or_panic().unwrap();
+        assert_eq!(*res.body(), "api");
+
+        let res = service.call(req("www.example.com.")).now_or_panic().unwrap();
+        assert_eq!(*res.body(), "any");
+
+        let res = service.call(req("Foo.eu.example.com")).now_or_panic().unwrap();
+        assert_eq!(*res.body(), "tenant");
+
+        let err = service.call(req("example.com")).now_or_panic().unwrap_err();
+        assert!(matches!(err, RouterError::First(_)));
+
+        let err = service.call(req("a.b.c.example.com")).now_or_panic().unwrap_err();
+        assert!(matches!(err, RouterError::First(_)));
+
+        let err = service.call(Request::new(())).now_or_panic().unwrap_err();
+        assert!(matches!(err, RouterError::First(_)));
+    }
+
+    #[test]
+    fn host_from_authority() {
+        let service = HostRouter::new()
+            .insert(
+                "example.com",
+                fn_service(|_: http::Request<()>| async { Ok::<_, Infallible>(Response::new(())) }),
+            )
+            .build(())
+            .now_or_panic()
+            .unwrap();
+
+        let mut req = http::Request::new(());
+        *req.uri_mut() = http::Uri::from_static("https://example.com:8443/foo");
+        service.call(req).now_or_panic().unwrap();
+    }
+
+    #[test]
+    fn host_ipv6() {
+        let service = HostRouter::new()
+            .insert(
+                "[::1]",
+                fn_service(|_: Request<()>| async { Ok::<_, Infallible>(Response::new(())) }),
+            )
+            .build(())
+            .now_or_panic()
+            .unwrap();
+
+        service.call(req("[::1]:8080")).now_or_panic().unwrap();
+        service.call(req("[::1]")).now_or_panic().unwrap();
+    }
+}
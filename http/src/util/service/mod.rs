@@ -1,15 +1,22 @@
+pub mod guard;
 pub mod handler;
 pub mod route;
 
 mod context_priv;
+mod host_priv;
 mod router_priv;
 
 pub mod context {
     pub use super::context_priv::{object, Context, ContextBuilder, ContextError};
 }
 
+pub mod host {
+    pub use super::host_priv::{GenericHostRouter, HostParams, HostRouter, HostRouterService};
+}
+
 pub mod router {
-    pub use super::router_priv::{GenericRouter, MatchError, Router, RouterError};
+    pub use super::router_priv::{GenericRouter, MatchError, Router, RouterError, RouterService};
 }
 
+pub use host_priv::{GenericHostRouter, HostRouter};
 pub use router_priv::{GenericRouter, Router, RouterError};
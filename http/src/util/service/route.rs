@@ -11,6 +11,8 @@ use crate::{
     request::BorrowReq,
 };
 
+use super::guard::Guard;
+
 mod next {
     pub struct Exist<S>(pub S);
     pub struct Empty;
@@ -34,8 +36,16 @@ method!(connect, CONNECT);
 method!(patch, PATCH);
 method!(trace, TRACE);
 
-pub struct Route<R, N, const M: usize> {
+/// Route service matching on request's method and optional [Guard].
+///
+/// Requests not matching method or rejected by guard fall through to next route in chain.
+/// When no route can handle the request [MethodNotAllowed] error is returned.
+///
+/// Methods of routes accepting a request can be collected with [RouteMethods] for building
+/// `Allow` header.
+pub struct Route<R, N, const M: usize, G = ()> {
     methods: [Method; M],
+    guard: G,
     route: R,
     next: N,
 }
@@ -44,22 +54,54 @@ impl Route<(), (), 0> {
     pub fn new<R>(route: R) -> Route<R, next::Empty, 0> {
         Route {
             methods: [],
+            guard: (),
             route,
             next: next::Empty,
         }
     }
 }
 
+impl<R, N, const M: usize> Route<R, N, M> {
+    /// Add guard to route. Route would only handle request passing the guard.
+    ///
+    /// Multiple guards can be combined with [GuardExt](super::guard::GuardExt).
+    ///
+    /// # Example:
+    /// ```rust
+    /// # use std::convert::Infallible;
+    /// # use xitca_http::{
+    /// #     http::{header::HeaderName, Request, Response},
+    /// #     util::service::{guard::header_eq, route::get},
+    /// # };
+    /// # use xitca_service::fn_service;
+    /// # async fn handler(_: Request<()>) -> Result<Response<()>, Infallible> {
+    /// #     Ok(Response::new(()))
+    /// # }
+    /// // requests with accept-version: 2 header go to first route. all other go to second route.
+    /// let route = get(fn_service(handler))
+    ///     .guard(header_eq(HeaderName::from_static("accept-version"), "2"))
+    ///     .next(get(fn_service(handler)));
+    /// ```
+    pub fn guard<G>(self, guard: G) -> Route<R, N, M, G> {
+        Route {
+            methods: self.methods,
+            guard,
+            route: self.route,
+            next: self.next,
+        }
+    }
+}
+
 macro_rules! route_method {
     ($method_fn: ident, $method: ident) => {
-        pub fn $method_fn<R1>(self, $method_fn: R1) -> Route<R, next::Exist<Route<R1, N, 1>>, M> {
+        pub fn $method_fn<R1>(self, $method_fn: R1) -> Route<R, next::Exist<Route<R1, N, 1>>, M, G> {
             self.next(Route::new($method_fn).methods([Method::$method]))
         }
     };
 }
 
-impl<R, N, const M: usize> Route<R, N, M> {
-    pub fn methods<const M1: usize>(self, methods: [Method; M1]) -> Route<R, N, M1> {
+impl<R, N, G, const M: usize> Route<R, N, M, G> {
+    pub fn methods<const M1: usize>(self, methods: [Method; M1]) -> Route<R, N, M1, G> {
         assert!(M1 > 0, "Route method can not be empty");
 
         if M != 0 {
@@ -71,21 +113,24 @@ impl<R, N, const M: usize> Route<R, N, M> {
 
         Route {
             methods,
+            guard: self.guard,
             route: self.route,
             next: self.next,
         }
     }
 
     // TODO is this really the intended behavior? insert `next` between `self` and `self.next`?
-    pub fn next<R1, const M1: usize>(
+    pub fn next<R1, G1, const M1: usize>(
         self,
-        next: Route<R1, next::Empty, M1>,
-    ) -> Route<R, next::Exist<Route<R1, N, M1>>, M> {
+        next: Route<R1, next::Empty, M1, G1>,
+    ) -> Route<R, next::Exist<Route<R1, N, M1, G1>>, M, G> {
         Route {
             methods: self.methods,
+            guard: self.guard,
             route: self.route,
             next: next::Exist(Route {
                 methods: next.methods,
+                guard: next.guard,
                 route: next.route,
                 next: self.next,
             }),
@@ -103,13 +148,14 @@ impl<R, N, const M: usize> Route<R, N, M> {
     route_method!(trace, TRACE);
 }
 
-impl<Arg, R, N, const M: usize> BuildService<Arg> for Route<R, next::Exist<N>, M>
+impl<Arg, R, N, G, const M: usize> BuildService<Arg> for Route<R, next::Exist<N>, M, G>
 where
     R: BuildService<Arg>,
     N: BuildService<Arg, Error = R::Error>,
+    G: Clone,
     Arg: Clone,
 {
-    type Service = Route<R::Service, next::Exist<N::Service>, M>;
+    type Service = Route<R::Service, next::Exist<N::Service>, M, G>;
     type Error = R::Error;
     type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
 
@@ -118,41 +164,55 @@ where
         let next = self.next.0.build(arg);
 
         let methods = self.methods.clone();
+        let guard = self.guard.clone();
 
         async move {
             let route = route.await?;
             let next = next::Exist(next.await?);
             // re-use Route for Service trait type.
-            Ok(Route { methods, route, next })
+            Ok(Route {
+                methods,
+                guard,
+                route,
+                next,
+            })
         }
     }
 }
 
-impl<Arg, R, const M: usize> BuildService<Arg> for Route<R, next::Empty, M>
+impl<Arg, R, G, const M: usize> BuildService<Arg> for Route<R, next::Empty, M, G>
 where
     R: BuildService<Arg>,
+    G: Clone,
 {
-    type Service = Route<R::Service, next::Empty, M>;
+    type Service = Route<R::Service, next::Empty, M, G>;
     type Error = R::Error;
     type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
 
     fn build(&self, arg: Arg) -> Self::Future {
         let route = self.route.build(arg);
         let methods = self.methods.clone();
+        let guard = self.guard.clone();
 
         async move {
             let route = route.await?;
             let next = next::Empty;
             // re-use Route for Service trait type.
-            Ok(Route { methods, route, next })
+            Ok(Route {
+                methods,
+                guard,
+                route,
+                next,
+            })
         }
     }
 }
 
-impl<Req, R, N, E, const M: usize> Service<Req> for Route<R, next::Exist<N>, M>
+impl<Req, R, N, G, E, const M: usize> Service<Req> for Route<R, next::Exist<N>, M, G>
 where
     R: Service<Req, Error = E>,
     N: Service<Req, Response = R::Response, Error = RouteError<E>>,
+    G: Guard<Req>,
     Req: BorrowReq<http::Method>,
 {
     type Response = R::Response;
@@ -162,7 +222,7 @@ where
     #[inline]
     fn call(&self, req: Req) -> Self::Future<'_> {
         async move {
-            if self.methods.contains(req.borrow()) {
+            if self.methods.contains(req.borrow()) && self.guard.check(&req) {
                 self.route.call(req).await.map_err(RouteError::Second)
             } else {
                 self.next.0.call(req).await
@@ -171,9 +231,10 @@ where
     }
 }
 
-impl<Req, R, const M: usize> Service<Req> for Route<R, next::Empty, M>
+impl<Req, R, G, const M: usize> Service<Req> for Route<R, next::Empty, M, G>
 where
     R: Service<Req>,
+    G: Guard<Req>,
     Req: BorrowReq<http::Method>,
 {
     type Response = R::Response;
@@ -183,7 +244,7 @@ where
     #[inline]
     fn call(&self, req: Req) -> Self::Future<'_> {
         async move {
-            if self.methods.contains(req.borrow()) {
+            if self.methods.contains(req.borrow()) && self.guard.check(&req) {
                 self.route.call(req).await.map_err(RouteError::Second)
             } else {
                 Err(RouteError::First(MethodNotAllowed))
@@ -192,7 +253,7 @@ where
     }
 }
 
-impl<Req, R, N, const M: usize> ReadyService<Req> for Route<R, N, M>
+impl<Req, R, N, G, const M: usize> ReadyService<Req> for Route<R, N, M, G>
 where
     Self: Service<Req>,
 {
@@ -205,6 +266,44 @@ where
     }
 }
 
+/// Methods of routes in chain accepting the request. Route whose guard rejects the request is skipped.
+pub trait RouteMethods<Req> {
+    /// Push methods of routes accepting the request to allowed methods.
+    fn allowed(&self, req: &Req, allowed: &mut Vec<Method>);
+}
+
+impl<Req> RouteMethods<Req> for next::Empty {
+    #[inline]
+    fn allowed(&self, _: &Req, _: &mut Vec<Method>) {}
+}
+
+impl<Req, N> RouteMethods<Req> for next::Exist<N>
+where
+    N: RouteMethods<Req>,
+{
+    #[inline]
+    fn allowed(&self, req: &Req, allowed: &mut Vec<Method>) {
+        self.0.allowed(req, allowed)
+    }
+}
+
+impl<Req, R, N, G, const M: usize> RouteMethods<Req> for Route<R, N, M, G>
+where
+    N: RouteMethods<Req>,
+    G: Guard<Req>,
+{
+    fn allowed(&self, req: &Req, allowed: &mut Vec<Method>) {
+        if self.guard.check(req) {
+            for method in self.methods.iter() {
+                if !allowed.contains(method) {
+                    allowed.push(method.clone());
+                }
+            }
+        }
+        self.next.allowed(req, allowed)
+    }
+}
+
 /// Error type of Route service.
 /// `First` variant contains [MethodNotAllowed] error.
 /// `Second` variant contains error returned by the service passed to Route.
@@ -335,6 +434,68 @@ mod test {
         assert_eq!(res.status().as_u16(), 200);
     }
 
+    #[tokio::test]
+    async fn route_guard() {
+        use crate::{
+            bytes::Bytes,
+            http::header::{HeaderName, HeaderValue, CONTENT_TYPE},
+            util::service::guard::{content_type, header_eq},
+        };
+
+        async fn v2(_: Request<RequestBody>) -> Result<Response<ResponseBody>, Infallible> {
+            Ok(Response::new(ResponseBody::bytes(Bytes::from_static(b"v2"))))
+        }
+
+        const VERSION: HeaderName = HeaderName::from_static("accept-version");
+
+        let route = get(fn_service(v2))
+            .guard(header_eq(VERSION, "2"))
+            .next(get(fn_service(index)))
+            .next(post(fn_service(index)).guard(content_type("application/json")));
+
+        let service = route.build(()).await.ok().unwrap();
+
+        let mut req = Request::new(RequestBody::None);
+        req.headers_mut().insert(VERSION, HeaderValue::from_static("2"));
+        let res = service.call(req).await.ok().unwrap();
+        assert!(matches!(res.body(), ResponseBody::Bytes { .. }));
+
+        let mut req = Request::new(RequestBody::None);
+        req.headers_mut().insert(VERSION, HeaderValue::from_static("1"));
+        let res = service.call(req).await.ok().unwrap();
+        assert!(matches!(res.body(), ResponseBody::None));
+
+        let mut req = Request::new(RequestBody::None);
+        *req.method_mut() = Method::POST;
+        req.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let res = service.call(req).await.ok().unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+
+        let mut req = Request::new(RequestBody::None);
+        *req.method_mut() = Method::POST;
+        let err = service.call(req).await.err().unwrap();
+        assert!(matches!(err, RouteError::First(MethodNotAllowed)));
+
+        // route rejected by guard is not allowed.
+        let mut allowed = Vec::new();
+        service.allowed(&req_with_version("1"), &mut allowed);
+        assert_eq!(allowed, [Method::GET]);
+
+        let mut req = req_with_version("2");
+        req.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let mut allowed = Vec::new();
+        service.allowed(&req, &mut allowed);
+        assert_eq!(allowed, [Method::GET, Method::POST]);
+
+        fn req_with_version(version: &'static str) -> Request<RequestBody> {
+            let mut req = Request::new(RequestBody::None);
+            req.headers_mut().insert(VERSION, HeaderValue::from_static(version));
+            req
+        }
+    }
+
     #[should_panic]
     #[test]
     fn overwrite_method_panic() {
@@ -39,6 +39,12 @@ pub struct MatchError {
 }
 
 impl MatchError {
+    pub(super) fn not_found() -> Self {
+        Self {
+            inner: matchit::MatchError::NotFound,
+        }
+    }
+
     /// Indicates whether a route exists at the same path with/without a trailing slash.
     pub fn is_trailing_slash(&self) -> bool {
         matches!(self.inner, matchit::MatchError::MissingTrailingSlash)
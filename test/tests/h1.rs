@@ -1,4 +1,5 @@
 use futures_util::StreamExt;
+use http_encoding::{Code, ContentEncoding, FeaturedCode};
 use std::{
     io::{Read, Write},
     net::TcpStream,
@@ -42,6 +43,27 @@ async fn h1_get() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn h1_get_zstd() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(handle))?;
+
+    let server_url = format!("http://{}/zstd", handle.ip_port_string());
+
+    let c = Client::new();
+
+    let mut res = c.get(&server_url)?.send().await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert!(!res.can_close_connection());
+    let body = res.string().await?;
+    assert_eq!("GET Response", body);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn h1_post() -> Result<(), Error> {
     let mut handle = test_h1_server(|| fn_service(handle))?;
@@ -224,6 +246,19 @@ async fn handle(req: Request<h1::RequestBody>) -> Result<Response<ResponseBody>,
 
     match (req.method(), req.uri().path()) {
         (&Method::GET, "/") => Ok(Response::new(Bytes::from("GET Response").into())),
+        (&Method::GET, "/zstd") => {
+            assert_eq!(req.headers().get(header::ACCEPT_ENCODING).unwrap(), "zstd");
+
+            let mut encoder = FeaturedCode::try_encoder(ContentEncoding::Zstd, None)?;
+            let mut body = BytesMut::new();
+            body.extend(encoder.code(Bytes::from("GET Response"))?.unwrap_or_default());
+            body.extend(Code::<Bytes>::code_eof(&mut encoder)?.unwrap_or_default());
+
+            let mut res = Response::new(body.freeze().into());
+            res.headers_mut()
+                .insert(header::CONTENT_ENCODING, HeaderValue::from_static("zstd"));
+            Ok(res)
+        }
         (&Method::POST, "/") => {
             let length = req.headers().get(header::CONTENT_LENGTH).unwrap().clone();
             let ty = req.headers().get(header::CONTENT_TYPE).unwrap().clone();
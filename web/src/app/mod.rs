@@ -1,4 +1,5 @@
-mod object;
+pub(crate) mod object;
+mod router;
 
 use std::{
     cell::RefCell,
@@ -10,10 +11,7 @@ use std::{
 use futures_core::stream::Stream;
 use xitca_http::{
     request::Request,
-    util::service::{
-        context::{Context, ContextBuilder},
-        router::GenericRouter,
-    },
+    util::service::context::{Context, ContextBuilder},
 };
 
 use crate::{
@@ -29,14 +27,14 @@ use crate::{
     response::{ResponseBody, WebResponse},
 };
 
-use self::object::WebObjectConstructor;
+use self::{object::WebObjectConstructor, router::AppRouter};
 
 pub struct App<CF = (), R = ()> {
     ctx_factory: CF,
     router: R,
 }
 
-type Router<C, B, SF> = GenericRouter<WebObjectConstructor<C, B>, SF>;
+type Router<C, B, SF> = AppRouter<WebObjectConstructor<C, B>, SF>;
 
 impl App {
     pub fn new<B, SF>() -> App<impl Fn() -> Ready<Result<(), Infallible>>, Router<(), B, SF>> {
@@ -76,7 +74,7 @@ impl App {
     {
         App {
             ctx_factory,
-            router: GenericRouter::with_custom_object(),
+            router: AppRouter::new(),
         }
     }
 }
@@ -89,6 +87,25 @@ impl<CF, C, B, SF> App<CF, Router<C, B, SF>> {
         self.router = self.router.insert(path, factory);
         self
     }
+
+    /// Dispatch requests with given host to service produced by factory.
+    ///
+    /// Host is matched against `Host` header or uri authority when header is absent. Pattern can
+    /// contain `*` label for matching any subdomain and `{name}` label for capturing subdomain which
+    /// can be extracted as [HostParams](crate::route::HostParams). e.g. `{tenant}.example.com`.
+    ///
+    /// Requests not matching any host fall back to routes registered with [App::at].
+    ///
+    /// # Panic:
+    ///
+    /// When multiple services inserted with the same host.
+    pub fn host<F>(mut self, host: &'static str, factory: F) -> App<CF, Router<C, B, SF>>
+    where
+        WebObjectConstructor<C, B>: ObjectConstructor<F, Object = SF>,
+    {
+        self.router = self.router.host(host, factory);
+        self
+    }
 }
 
 impl<CF, R> App<CF, R>
@@ -190,10 +207,15 @@ mod test {
             extension::ExtensionRef, extension::ExtensionsRef, handler_service, path::PathRef, state::StateRef,
             uri::UriRef, Responder,
         },
-        http::{const_header_value::TEXT_UTF8, header::CONTENT_TYPE, Method, Uri},
+        http::{
+            const_header_value::TEXT_UTF8,
+            header::{CONTENT_TYPE, HOST},
+            HeaderValue, Method, Uri,
+        },
         middleware::UncheckedReady,
         request::RequestBody,
-        route::get,
+        route::{get, HostParams},
+        test::collect_string_body,
     };
 
     use super::*;
@@ -323,4 +345,36 @@ mod test {
     }
 
     struct Foo;
+
+    #[test]
+    fn test_app_host() {
+        async fn tenant(ExtensionRef(params): ExtensionRef<'_, HostParams>) -> String {
+            params.get("tenant").unwrap().to_string()
+        }
+
+        let service = App::new()
+            .host("api.example.com", get(handler_service(|| async { "api" })))
+            .host("{tenant}.example.com", get(handler_service(tenant)))
+            .at("/", get(handler_service(|| async { "fallback" })))
+            .finish()
+            .build(())
+            .now_or_panic()
+            .ok()
+            .unwrap();
+
+        let call = |host: Option<&'static str>| {
+            let mut req = Request::<RequestBody>::default();
+            if let Some(host) = host {
+                req.headers_mut().insert(HOST, HeaderValue::from_static(host));
+            }
+            let res = service.call(req).now_or_panic().unwrap();
+            assert_eq!(res.status().as_u16(), 200);
+            collect_string_body(res.into_body()).now_or_panic().unwrap()
+        };
+
+        assert_eq!(call(Some("api.example.com:8080")), "api");
+        assert_eq!(call(Some("foo.example.com")), "foo");
+        assert_eq!(call(Some("example.com")), "fallback");
+        assert_eq!(call(None), "fallback");
+    }
 }
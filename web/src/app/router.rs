@@ -0,0 +1,105 @@
+use std::future::Future;
+
+use xitca_http::{
+    http::{Extensions, HeaderMap, Uri},
+    request::{BorrowReq, BorrowReqMut},
+    util::service::{
+        host::{GenericHostRouter, HostRouterService},
+        router::{GenericRouter, RouterError, RouterService},
+    },
+};
+
+use crate::dev::service::{object::ObjectConstructor, ready::ReadyService, BuildService, Service};
+
+// router of App. requests matching a host pattern are dispatched to host services and
+// all others fall back to path routing.
+pub struct AppRouter<ObjCons, SF> {
+    hosts: GenericHostRouter<ObjCons, SF>,
+    paths: GenericRouter<ObjCons, SF>,
+}
+
+impl<ObjCons, SF> AppRouter<ObjCons, SF> {
+    pub(super) fn new() -> Self {
+        Self {
+            hosts: GenericHostRouter::new(),
+            paths: GenericRouter::new(),
+        }
+    }
+
+    pub(super) fn insert<F>(mut self, path: &'static str, factory: F) -> Self
+    where
+        ObjCons: ObjectConstructor<F, Object = SF>,
+    {
+        self.paths = self.paths.insert(path, factory);
+        self
+    }
+
+    pub(super) fn host<F>(mut self, host: &'static str, factory: F) -> Self
+    where
+        ObjCons: ObjectConstructor<F, Object = SF>,
+    {
+        self.hosts = self.hosts.insert(host, factory);
+        self
+    }
+}
+
+impl<ObjCons, SF, Arg> BuildService<Arg> for AppRouter<ObjCons, SF>
+where
+    SF: BuildService<Arg>,
+    Arg: Clone,
+{
+    type Service = AppRouterService<SF::Service>;
+    type Error = SF::Error;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, arg: Arg) -> Self::Future {
+        let hosts = self.hosts.build(arg.clone());
+        let paths = self.paths.build(arg);
+
+        async move {
+            Ok(AppRouterService {
+                hosts: hosts.await?,
+                paths: paths.await?,
+            })
+        }
+    }
+}
+
+pub struct AppRouterService<S> {
+    hosts: HostRouterService<S>,
+    paths: RouterService<S>,
+}
+
+impl<S, Req> Service<Req> for AppRouterService<S>
+where
+    S: Service<Req>,
+    Req: BorrowReq<Uri> + BorrowReq<HeaderMap> + BorrowReqMut<Extensions>,
+{
+    type Response = S::Response;
+    type Error = RouterError<S::Error>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    #[inline]
+    fn call(&self, mut req: Req) -> Self::Future<'_> {
+        async move {
+            match self.hosts.find(&mut req) {
+                Some(service) => service.call(req).await.map_err(RouterError::Second),
+                None => self.paths.call(req).await,
+            }
+        }
+    }
+}
+
+impl<S, Req> ReadyService<Req> for AppRouterService<S>
+where
+    S: Service<Req>,
+    Req: BorrowReq<Uri> + BorrowReq<HeaderMap> + BorrowReqMut<Extensions>,
+{
+    type Ready = ();
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where S: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async {}
+    }
+}
@@ -0,0 +1,244 @@
+use std::{convert::Infallible, fmt, io};
+
+use http_encoding::{
+    error::{CoderError, EncodingError},
+    Code as _, ContentEncoding, DecodeLimit, FeaturedCode,
+};
+use prost::Message;
+
+use crate::{
+    dev::bytes::{Buf, BufMut, Bytes, BytesMut},
+    http::HeaderMap,
+};
+
+use super::{
+    status::{Code, Status},
+    GRPC_ENCODING,
+};
+
+// length of message prefix. 1 byte compressed flag and 4 bytes big endian message length.
+const PREFIX_LEN: usize = 5;
+
+// resolve message encoding from grpc-encoding header.
+pub(super) fn encoding(headers: &HeaderMap) -> Result<ContentEncoding, Status> {
+    match headers.get(GRPC_ENCODING) {
+        None => Ok(ContentEncoding::NoOp),
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|v| ContentEncoding::try_parse(v).ok())
+            .ok_or_else(|| Status::new(Code::Unimplemented, "grpc-encoding is not supported")),
+    }
+}
+
+// resolve response message encoding. response is compressed with the same encoding of request when
+// it's enabled by feature.
+pub(super) fn response_encoding(headers: &HeaderMap) -> ContentEncoding {
+    match encoding(headers) {
+        Ok(ContentEncoding::Br) if cfg!(feature = "compress-br") => ContentEncoding::Br,
+        Ok(ContentEncoding::Gzip) if cfg!(feature = "compress-gz") => ContentEncoding::Gzip,
+        Ok(ContentEncoding::Deflate) if cfg!(feature = "compress-de") => ContentEncoding::Deflate,
+        Ok(ContentEncoding::Zstd) if cfg!(feature = "compress-zs") => ContentEncoding::Zstd,
+        _ => ContentEncoding::NoOp,
+    }
+}
+
+/// Encode message to a length prefixed frame. Message is compressed with given encoding when it's
+/// not [ContentEncoding::NoOp].
+pub(super) fn encode<M>(msg: &M, encoding: ContentEncoding) -> Result<Bytes, Status>
+where
+    M: Message,
+{
+    let mut buf = BytesMut::with_capacity(PREFIX_LEN + msg.encoded_len());
+    buf.put_bytes(0, PREFIX_LEN);
+
+    // encoding to BytesMut only fails when there is not enough capacity which is grown on demand.
+    msg.encode(&mut buf).unwrap();
+
+    if encoding != ContentEncoding::NoOp {
+        let mut encoder = FeaturedCode::try_encoder(encoding, None).map_err(|e| internal(&e))?;
+        let compressed = code_all(&mut encoder, buf.split_off(PREFIX_LEN).freeze())?;
+        buf.extend_from_slice(&compressed);
+        buf[0] = 1;
+    }
+
+    let len = (buf.len() - PREFIX_LEN) as u32;
+    (&mut buf[1..PREFIX_LEN]).put_u32(len);
+
+    Ok(buf.freeze())
+}
+
+/// Decoder of length prefixed message frames from request body chunks.
+pub(super) struct Decoder {
+    buf: BytesMut,
+    encoding: ContentEncoding,
+    limit: usize,
+}
+
+impl Decoder {
+    pub(super) fn new(encoding: ContentEncoding, limit: usize) -> Self {
+        Self {
+            buf: BytesMut::new(),
+            encoding,
+            limit,
+        }
+    }
+
+    pub(super) fn extend(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Try to decode one message from buffered bytes. Return `Ok(None)` when more bytes are needed.
+    pub(super) fn decode<M>(&mut self) -> Result<Option<M>, Status>
+    where
+        M: Message + Default,
+    {
+        if self.buf.len() < PREFIX_LEN {
+            return Ok(None);
+        }
+
+        let compressed = match self.buf[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(Status::new(Code::Internal, "invalid compressed flag of message")),
+        };
+
+        let len = (&self.buf[1..PREFIX_LEN]).get_u32() as usize;
+
+        if len > self.limit {
+            return Err(too_large());
+        }
+
+        if self.buf.len() < PREFIX_LEN + len {
+            self.buf.reserve(PREFIX_LEN + len - self.buf.len());
+            return Ok(None);
+        }
+
+        self.buf.advance(PREFIX_LEN);
+        let mut msg = self.buf.split_to(len).freeze();
+
+        if compressed {
+            if self.encoding == ContentEncoding::NoOp {
+                return Err(Status::new(
+                    Code::Internal,
+                    "compressed message received without grpc-encoding",
+                ));
+            }
+
+            let limit = DecodeLimit::new().max_size(self.limit);
+            let mut decoder = FeaturedCode::try_decoder(self.encoding, limit).map_err(|e| match e {
+                EncodingError::MissingFeature(_) => Status::new(Code::Unimplemented, e.to_string()),
+                e => internal(&e),
+            })?;
+            msg = code_all(&mut decoder, msg)?;
+        }
+
+        M::decode(msg)
+            .map(Some)
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))
+    }
+}
+
+// feed all bytes to coder and collect it's output.
+fn code_all(coder: &mut FeaturedCode, bytes: Bytes) -> Result<Bytes, Status> {
+    let map_err = |e: io::Error| match CoderError::<Infallible>::from(e) {
+        CoderError::LimitExceeded => too_large(),
+        e => internal(&e),
+    };
+
+    let mut buf = BytesMut::new();
+
+    if let Some(bytes) = coder.code(bytes).map_err(map_err)? {
+        buf.extend_from_slice(&bytes);
+    }
+    if let Some(bytes) = <FeaturedCode as http_encoding::Code<Bytes>>::code_eof(coder).map_err(map_err)? {
+        buf.extend_from_slice(&bytes);
+    }
+
+    Ok(buf.freeze())
+}
+
+fn too_large() -> Status {
+    Status::new(Code::ResourceExhausted, "message is larger than limit")
+}
+
+fn internal(e: &dyn fmt::Display) -> Status {
+    Status::new(Code::Internal, e.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Msg {
+        #[prost(string, tag = "1")]
+        value: String,
+    }
+
+    fn msg(value: &str) -> Msg {
+        Msg {
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn frame() {
+        let frame = encode(&msg("hello"), ContentEncoding::NoOp).unwrap();
+        assert_eq!(frame[0], 0);
+        assert_eq!(&frame[1..5], &7u32.to_be_bytes());
+
+        let mut decoder = Decoder::new(ContentEncoding::NoOp, 1024);
+
+        // feed frames byte by byte to test partial frame.
+        for b in frame.iter().chain(frame.iter()) {
+            decoder.extend(&[*b]);
+        }
+        decoder.extend(&frame[..3]);
+
+        assert_eq!(decoder.decode::<Msg>().unwrap().unwrap(), msg("hello"));
+        assert_eq!(decoder.decode::<Msg>().unwrap().unwrap(), msg("hello"));
+        assert!(decoder.decode::<Msg>().unwrap().is_none());
+        assert!(!decoder.is_empty());
+    }
+
+    #[test]
+    fn limit() {
+        let frame = encode(&msg("hello"), ContentEncoding::NoOp).unwrap();
+        let mut decoder = Decoder::new(ContentEncoding::NoOp, 4);
+        decoder.extend(&frame);
+        let status = decoder.decode::<Msg>().unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+    }
+
+    #[test]
+    fn compressed_without_encoding() {
+        let mut frame = BytesMut::from(&encode(&msg("hello"), ContentEncoding::NoOp).unwrap()[..]);
+        frame[0] = 1;
+        let mut decoder = Decoder::new(ContentEncoding::NoOp, 1024);
+        decoder.extend(&frame);
+        let status = decoder.decode::<Msg>().unwrap_err();
+        assert_eq!(status.code(), Code::Internal);
+    }
+
+    #[cfg(feature = "compress-gz")]
+    #[test]
+    fn compressed() {
+        let value = "hello".repeat(64);
+        let frame = encode(&msg(&value), ContentEncoding::Gzip).unwrap();
+        assert_eq!(frame[0], 1);
+
+        let mut decoder = Decoder::new(ContentEncoding::Gzip, 1024);
+        decoder.extend(&frame);
+        assert_eq!(decoder.decode::<Msg>().unwrap().unwrap(), msg(&value));
+
+        let mut decoder = Decoder::new(ContentEncoding::Gzip, 64);
+        decoder.extend(&frame);
+        let status = decoder.decode::<Msg>().unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+    }
+}
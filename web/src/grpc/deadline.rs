@@ -0,0 +1,202 @@
+use std::{convert::Infallible, future::Future, time::Duration};
+
+use tokio::time::{timeout_at, Instant};
+use xitca_http::request::Request;
+
+use crate::{
+    dev::service::{pipeline::PipelineE, ready::ReadyService, BuildService, Service},
+    handler::{ExtractError, FromRequest},
+    http::HeaderMap,
+    request::WebRequest,
+    stream::WebStream,
+};
+
+use super::{
+    status::{Code, Status},
+    GRPC_TIMEOUT,
+};
+
+/// Extract deadline of gRPC call from `grpc-timeout` header. `None` when client did not set a timeout.
+///
+/// The deadline can be propagated to downstream calls made by handler. When [EnforceDeadline]
+/// middleware is used the extracted deadline is the one enforced by it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Deadline(pub Option<Instant>);
+
+impl Deadline {
+    fn from_req(req: &Request<()>) -> Self {
+        match req.extensions().get::<Self>() {
+            Some(deadline) => *deadline,
+            None => Self(timeout(req.headers()).and_then(|dur| Instant::now().checked_add(dur))),
+        }
+    }
+
+    /// Remaining duration before deadline. `None` when there is no deadline.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.0
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for Deadline
+where
+    B: WebStream,
+{
+    type Type<'b> = Deadline;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let deadline = Self::from_req(req.req());
+        async move { Ok(deadline) }
+    }
+}
+
+/// A middleware cancel gRPC call when it does not produce response before deadline set by client
+/// with `grpc-timeout` header. Cancelled call is responded with [Code::DeadlineExceeded] status.
+///
+/// Request without `grpc-timeout` header is not affected unless [EnforceDeadline::max] is set.
+///
+/// Like `Timeout` middleware the deadline only covers producing response head.
+#[derive(Clone, Copy, Default)]
+pub struct EnforceDeadline {
+    max: Option<Duration>,
+}
+
+impl EnforceDeadline {
+    /// Construct a new middleware enforce deadline set by client.
+    pub const fn new() -> Self {
+        Self { max: None }
+    }
+
+    /// Cap timeout of call with given duration. It's also applied to calls without `grpc-timeout`
+    /// header.
+    pub const fn max(mut self, dur: Duration) -> Self {
+        self.max = Some(dur);
+        self
+    }
+}
+
+impl<S> BuildService<S> for EnforceDeadline {
+    type Service = EnforceDeadlineService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        let max = self.max;
+        async move { Ok(EnforceDeadlineService { service, max }) }
+    }
+}
+
+pub struct EnforceDeadlineService<S> {
+    service: S,
+    max: Option<Duration>,
+}
+
+/// Error type of [EnforceDeadlineService].
+/// `First` variant contains [Status] with [Code::DeadlineExceeded] when call timed out.
+/// `Second` variant contains error returned by the service passed to EnforceDeadline.
+pub type EnforceDeadlineError<E> = PipelineE<Status, E>;
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for EnforceDeadlineService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = EnforceDeadlineError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, mut req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let dur = match (timeout(req.req().headers()), self.max) {
+                (Some(dur), Some(max)) => Some(dur.min(max)),
+                (dur, max) => dur.or(max),
+            };
+
+            // timeout too large to be represented is treated as no deadline.
+            match dur.and_then(|dur| Instant::now().checked_add(dur)) {
+                Some(deadline) => {
+                    req.req_mut().extensions_mut().insert(Deadline(Some(deadline)));
+                    match timeout_at(deadline, self.service.call(req)).await {
+                        Ok(res) => res.map_err(EnforceDeadlineError::Second),
+                        Err(_) => Err(EnforceDeadlineError::First(Status::new(
+                            Code::DeadlineExceeded,
+                            "deadline exceeded",
+                        ))),
+                    }
+                }
+                None => self.service.call(req).await.map_err(EnforceDeadlineError::Second),
+            }
+        }
+    }
+}
+
+impl<'r, S, C, B, Res, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for EnforceDeadlineService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, B>, Response = Res, Error = Err, Ready = Rdy>,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+// parse grpc-timeout header. value is at most 8 digits followed by a unit char.
+fn timeout(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(GRPC_TIMEOUT)?.as_bytes();
+
+    let (unit, digits) = value.split_last()?;
+
+    if digits.is_empty() || digits.len() > 8 || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    let num = digits.iter().fold(0u64, |n, d| n * 10 + (d - b'0') as u64);
+
+    match unit {
+        b'H' => Some(Duration::from_secs(num * 60 * 60)),
+        b'M' => Some(Duration::from_secs(num * 60)),
+        b'S' => Some(Duration::from_secs(num)),
+        b'm' => Some(Duration::from_millis(num)),
+        b'u' => Some(Duration::from_micros(num)),
+        b'n' => Some(Duration::from_nanos(num)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::http::HeaderValue;
+
+    use super::*;
+
+    fn parse(value: &'static str) -> Option<Duration> {
+        let mut headers = HeaderMap::new();
+        headers.insert(GRPC_TIMEOUT, HeaderValue::from_static(value));
+        timeout(&headers)
+    }
+
+    #[test]
+    fn grpc_timeout() {
+        assert_eq!(parse("1H"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse("2M"), Some(Duration::from_secs(120)));
+        assert_eq!(parse("30S"), Some(Duration::from_secs(30)));
+        assert_eq!(parse("100m"), Some(Duration::from_millis(100)));
+        assert_eq!(parse("99999999u"), Some(Duration::from_micros(99999999)));
+        assert_eq!(parse("5n"), Some(Duration::from_nanos(5)));
+        assert_eq!(parse("123456789S"), None);
+        assert_eq!(parse("S"), None);
+        assert_eq!(parse("10"), None);
+        assert_eq!(parse("1x"), None);
+        assert_eq!(parse("-1S"), None);
+        assert_eq!(timeout(&HeaderMap::new()), None);
+    }
+}
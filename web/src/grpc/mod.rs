@@ -0,0 +1,620 @@
+//! gRPC server support on top of [App](crate::App).
+//!
+//! gRPC methods are plain handlers registered on `/package.Service/Method` path with [post] route.
+//! Request and response messages are [prost] message types:
+//! - [Grpc] extracts unary request message and responds unary response message.
+//! - [GrpcStreaming] extracts a stream of request messages for client and bidi streaming.
+//! - [GrpcStream] responds a stream of response messages for server and bidi streaming.
+//!
+//! Handler returns response as `Result<Grpc<M>, Status>` or `Result<GrpcStream<S>, Status>`. [Status]
+//! returned as error responds with it's code and message.
+//!
+//! Request without `application/grpc` content type is responded with `415 Unsupported Media Type`
+//! and [Code::InvalidArgument].
+//!
+//! Call to method not registered is responded with `404 Not Found` which gRPC clients treat as
+//! [Code::Unimplemented].
+//!
+//! Browser clients using gRPC-Web can call the same handlers when the App is enclosed by [GrpcWeb]
+//! middleware.
+//!
+//! # Examples
+//! ```rust
+//! use futures_core::stream::Stream;
+//! use xitca_web::{
+//!     grpc::{Grpc, GrpcStream, GrpcStreaming, Status},
+//!     handler::handler_service,
+//!     route::post,
+//!     App,
+//! };
+//!
+//! #[derive(Clone, PartialEq, prost::Message)]
+//! struct HelloRequest {
+//!     #[prost(string, tag = "1")]
+//!     name: String,
+//! }
+//!
+//! #[derive(Clone, PartialEq, prost::Message)]
+//! struct HelloReply {
+//!     #[prost(string, tag = "1")]
+//!     message: String,
+//! }
+//!
+//! // unary call.
+//! async fn say_hello(Grpc(req): Grpc<HelloRequest>) -> Result<Grpc<HelloReply>, Status> {
+//!     Ok(Grpc(HelloReply { message: format!("hello {}", req.name) }))
+//! }
+//!
+//! // bidi streaming call. reply every request message.
+//! async fn chat(
+//!     req: GrpcStreaming<HelloRequest>,
+//! ) -> Result<GrpcStream<impl Stream<Item = Result<HelloReply, Status>>>, Status> {
+//!     # struct Map<S>(S);
+//!     # impl<S: Stream<Item = Result<HelloRequest, Status>> + Unpin> Stream for Map<S> {
+//!     #     type Item = Result<HelloReply, Status>;
+//!     #     fn poll_next(
+//!     #         mut self: std::pin::Pin<&mut Self>,
+//!     #         cx: &mut std::task::Context<'_>,
+//!     #     ) -> std::task::Poll<Option<Self::Item>> {
+//!     #         std::pin::Pin::new(&mut self.0)
+//!     #             .poll_next(cx)
+//!     #             .map(|item| item.map(|res| res.map(|req| HelloReply { message: req.name })))
+//!     #     }
+//!     # }
+//!     Ok(GrpcStream(Map(req)))
+//! }
+//!
+//! App::new()
+//!     .at("/helloworld.Greeter/SayHello", post(handler_service(say_hello)))
+//!     .at("/helloworld.Greeter/Chat", post(handler_service(chat)));
+//! ```
+//!
+//! [post]: crate::route::post
+
+mod codec;
+mod deadline;
+mod status;
+mod web;
+
+pub use self::deadline::{Deadline, EnforceDeadline, EnforceDeadlineError, EnforceDeadlineService};
+pub use self::status::{Code, Status};
+pub use self::web::{GrpcWeb, GrpcWebBody, GrpcWebBodyError, GrpcWebResponseBody, GrpcWebService};
+
+use std::{
+    future::{poll_fn, Future},
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::stream::Stream;
+use http_encoding::ContentEncoding;
+use pin_project_lite::pin_project;
+use prost::Message;
+use xitca_http::{body::StreamBody, error::BodyError, response::Trailers};
+use xitca_unsafe_collection::pin;
+
+use crate::{
+    dev::bytes::Bytes,
+    handler::{body::Body, ExtractError, FromRequest, Responder},
+    http::{
+        const_header_value::GRPC,
+        header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    },
+    request::{RequestBody, WebRequest},
+    response::{ResponseBody, WebResponse},
+    stream::WebStream,
+};
+
+use self::codec::Decoder;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const GRPC_STATUS: HeaderName = HeaderName::from_static("grpc-status");
+#[allow(clippy::declare_interior_mutable_const)]
+const GRPC_MESSAGE: HeaderName = HeaderName::from_static("grpc-message");
+#[allow(clippy::declare_interior_mutable_const)]
+const GRPC_ENCODING: HeaderName = HeaderName::from_static("grpc-encoding");
+#[allow(clippy::declare_interior_mutable_const)]
+const GRPC_TIMEOUT: HeaderName = HeaderName::from_static("grpc-timeout");
+
+const DEFAULT_LIMIT: usize = 4 * 1024 * 1024;
+
+/// Extract and respond type for unary gRPC message. const generic param LIMIT is for max size of the
+/// message in bytes. Message larger than limit would be treated as [Code::ResourceExhausted] error.
+///
+/// Default limit is [DEFAULT_LIMIT] in bytes.
+///
+/// Response message is returned from handler as `Result<Grpc<M>, Status>`. When request message is
+/// compressed the response message is compressed with the same encoding.
+pub struct Grpc<M, const LIMIT: usize = DEFAULT_LIMIT>(pub M);
+
+impl<'a, 'r, C, B, M, const LIMIT: usize> FromRequest<'a, WebRequest<'r, C, B>> for Grpc<M, LIMIT>
+where
+    B: WebStream + Default,
+    M: Message + Default,
+{
+    type Type<'b> = Grpc<M, LIMIT>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        async move {
+            content_type(req.req().headers())?;
+
+            let mut decoder = Decoder::new(codec::encoding(req.req().headers())?, LIMIT);
+
+            let Body(body) = Body::from_request(req).await?;
+
+            pin!(body);
+
+            let mut msg = None;
+
+            loop {
+                if let Some(m) = decoder.decode()? {
+                    if msg.replace(m).is_some() {
+                        return Err(Status::new(Code::Internal, "unary call received multiple messages").into());
+                    }
+                    continue;
+                }
+
+                match poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                    Some(chunk) => decoder.extend(chunk.map_err(ExtractError::Body)?.as_ref()),
+                    None => break,
+                }
+            }
+
+            if !decoder.is_empty() {
+                return Err(incomplete().into());
+            }
+
+            msg.map(Grpc)
+                .ok_or_else(|| Status::new(Code::Internal, "unary call received no message").into())
+        }
+    }
+}
+
+impl<'r, C, B, M, const LIMIT: usize> Responder<WebRequest<'r, C, B>> for Result<Grpc<M, LIMIT>, Status>
+where
+    M: Message,
+{
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let encoding = codec::response_encoding(req.req().headers());
+        let res = match self.and_then(|Grpc(msg)| codec::encode(&msg, encoding)) {
+            Ok(bytes) => {
+                let trailers = Trailers::new();
+                Status::ok().insert_trailers(&trailers);
+                grpc_response(req, bytes, encoding, trailers)
+            }
+            Err(status) => status.into_response(req),
+        };
+        async { res }
+    }
+}
+
+/// Extract type for streaming gRPC request messages. Request body is decoded lazily as a [Stream]
+/// of `M`.
+///
+/// const generic param LIMIT is for max size of a single message in bytes. Message larger than limit
+/// would yield [Code::ResourceExhausted] error and end the stream.
+///
+/// Default limit is [DEFAULT_LIMIT] in bytes.
+pub struct GrpcStreaming<M, B = RequestBody, const LIMIT: usize = DEFAULT_LIMIT> {
+    body: B,
+    decoder: Decoder,
+    eof: bool,
+    _msg: PhantomData<fn() -> M>,
+}
+
+impl<'a, 'r, C, B, M, const LIMIT: usize> FromRequest<'a, WebRequest<'r, C, B>> for GrpcStreaming<M, B, LIMIT>
+where
+    B: WebStream + Default,
+    M: Message + Default,
+{
+    type Type<'b> = GrpcStreaming<M, B, LIMIT>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        async move {
+            content_type(req.req().headers())?;
+
+            let decoder = Decoder::new(codec::encoding(req.req().headers())?, LIMIT);
+
+            Ok(GrpcStreaming {
+                body: req.take_body_ref(),
+                decoder,
+                eof: false,
+                _msg: PhantomData,
+            })
+        }
+    }
+}
+
+impl<M, B, const LIMIT: usize> Stream for GrpcStreaming<M, B, LIMIT>
+where
+    B: WebStream + Unpin,
+    M: Message + Default,
+{
+    type Item = Result<M, Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.eof {
+                return Poll::Ready(None);
+            }
+
+            match this.decoder.decode() {
+                Ok(Some(msg)) => return Poll::Ready(Some(Ok(msg))),
+                Ok(None) => {}
+                Err(status) => {
+                    this.eof = true;
+                    return Poll::Ready(Some(Err(status)));
+                }
+            }
+
+            match futures_core::ready!(Pin::new(&mut this.body).poll_next(cx)) {
+                Some(Ok(chunk)) => this.decoder.extend(chunk.as_ref()),
+                Some(Err(e)) => {
+                    this.eof = true;
+                    return Poll::Ready(Some(Err(Status::new(Code::Internal, e.to_string()))));
+                }
+                None => {
+                    this.eof = true;
+                    if !this.decoder.is_empty() {
+                        return Poll::Ready(Some(Err(incomplete())));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Responder type for streaming gRPC response messages. Each item of the stream is encoded as one
+/// message and sent as soon as it's produced.
+///
+/// Response stream is returned from handler as `Result<GrpcStream<S>, Status>`.
+///
+/// `grpc-status` trailer is sent when the stream ends. When the stream yields a [Status] error in the
+/// middle of response the response ends and the status is sent as trailers.
+pub struct GrpcStream<S>(pub S);
+
+impl<'r, C, B, S, M> Responder<WebRequest<'r, C, B>> for Result<GrpcStream<S>, Status>
+where
+    S: Stream<Item = Result<M, Status>> + 'static,
+    M: Message,
+{
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let res = match self {
+            Ok(GrpcStream(stream)) => {
+                let encoding = codec::response_encoding(req.req().headers());
+                let trailers = Trailers::new();
+                let body: StreamBody = Box::pin(GrpcEncoder {
+                    stream,
+                    encoding,
+                    trailers: trailers.clone(),
+                    eof: false,
+                });
+                grpc_response(req, ResponseBody::stream(body), encoding, trailers)
+            }
+            Err(status) => status.into_response(req),
+        };
+        async { res }
+    }
+}
+
+pin_project! {
+    struct GrpcEncoder<S> {
+        #[pin]
+        stream: S,
+        encoding: ContentEncoding,
+        trailers: Trailers,
+        eof: bool,
+    }
+}
+
+impl<S, M> Stream for GrpcEncoder<S>
+where
+    S: Stream<Item = Result<M, Status>>,
+    M: Message,
+{
+    type Item = Result<Bytes, BodyError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if *this.eof {
+            return Poll::Ready(None);
+        }
+
+        // error status ends the response and it's sent as trailers.
+        let status = match futures_core::ready!(this.stream.poll_next(cx)) {
+            Some(res) => match res.and_then(|msg| codec::encode(&msg, *this.encoding)) {
+                Ok(bytes) => return Poll::Ready(Some(Ok(bytes))),
+                Err(status) => status,
+            },
+            None => Status::ok(),
+        };
+
+        *this.eof = true;
+        status.insert_trailers(this.trailers);
+
+        Poll::Ready(None)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+// construct a gRPC response with body. status of the call is sent with trailers after body.
+fn grpc_response<C, B>(
+    req: WebRequest<'_, C, B>,
+    body: impl Into<ResponseBody>,
+    encoding: ContentEncoding,
+    trailers: Trailers,
+) -> WebResponse {
+    let mut res = req.into_response(body);
+    let headers = res.headers_mut();
+    headers.insert(CONTENT_TYPE, GRPC);
+    if encoding != ContentEncoding::NoOp {
+        headers.insert(GRPC_ENCODING, HeaderValue::from_static(encoding.as_str()));
+    }
+    res.extensions_mut().insert(trailers);
+    res
+}
+
+// content type must be application/grpc optionally followed by message format or parameters.
+fn content_type(headers: &HeaderMap) -> Result<(), Status> {
+    match headers.get(CONTENT_TYPE).map(HeaderValue::as_bytes) {
+        Some(b"application/grpc") => Ok(()),
+        Some(ct) if ct.starts_with(b"application/grpc+") || ct.starts_with(b"application/grpc;") => Ok(()),
+        _ => Err(Status::unsupported_media_type()),
+    }
+}
+
+fn incomplete() -> Status {
+    Status::new(Code::Internal, "request body ended with incomplete message")
+}
+
+impl<E> From<Status> for ExtractError<E> {
+    fn from(status: Status) -> Self {
+        Self::Grpc(status)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fmt, time::Duration};
+
+    use xitca_http::{body::Once, request::Request};
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        dev::service::{BuildService, Service},
+        handler::handler_service,
+        http::{Method, StatusCode},
+        route::post,
+        test::collect_body,
+        App,
+    };
+
+    use super::{codec::Decoder, *};
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Msg {
+        #[prost(uint32, tag = "1")]
+        id: u32,
+    }
+
+    async fn unary(Grpc(msg): Grpc<Msg>) -> Result<Grpc<Msg>, Status> {
+        match msg.id {
+            0 => Err(Status::new(Code::InvalidArgument, "id can't be 0")),
+            id => Ok(Grpc(Msg { id: id * 2 })),
+        }
+    }
+
+    async fn bidi(
+        req: GrpcStreaming<Msg, Once<Bytes>>,
+    ) -> Result<GrpcStream<impl Stream<Item = Result<Msg, Status>>>, Status> {
+        struct Double<S>(S);
+
+        impl<S: Stream<Item = Result<Msg, Status>> + Unpin> Stream for Double<S> {
+            type Item = Result<Msg, Status>;
+
+            fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                Pin::new(&mut self.0)
+                    .poll_next(cx)
+                    .map(|item| item.map(|res| res.map(|msg| Msg { id: msg.id * 2 })))
+            }
+        }
+
+        Ok(GrpcStream(Double(req)))
+    }
+
+    // yield one message and then an error status.
+    async fn fail(
+        _: GrpcStreaming<Msg, Once<Bytes>>,
+    ) -> Result<GrpcStream<impl Stream<Item = Result<Msg, Status>>>, Status> {
+        struct Fail(u8);
+
+        impl Stream for Fail {
+            type Item = Result<Msg, Status>;
+
+            fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                self.0 += 1;
+                Poll::Ready(match self.0 {
+                    1 => Some(Ok(Msg { id: 1 })),
+                    2 => Some(Err(Status::new(Code::Aborted, "aborted"))),
+                    _ => Some(Ok(Msg { id: 2 })),
+                })
+            }
+        }
+
+        Ok(GrpcStream(Fail(0)))
+    }
+
+    async fn slow(_: Grpc<Msg>, Deadline(deadline): Deadline) -> Result<Grpc<Msg>, Status> {
+        assert!(deadline.is_some());
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok(Grpc(Msg { id: 0 }))
+    }
+
+    fn request(path: &str, ids: &[u32]) -> Request<Once<Bytes>> {
+        let mut body = Vec::new();
+        for id in ids {
+            body.extend_from_slice(&codec::encode(&Msg { id: *id }, ContentEncoding::NoOp).unwrap());
+        }
+        let mut req = Request::new(Once::new(Bytes::from(body)));
+        *req.method_mut() = Method::POST;
+        *req.uri_mut() = path.parse().unwrap();
+        req.headers_mut().insert(CONTENT_TYPE, GRPC);
+        req
+    }
+
+    fn decode_body(body: Vec<u8>) -> Vec<u32> {
+        let mut decoder = Decoder::new(ContentEncoding::NoOp, DEFAULT_LIMIT);
+        decoder.extend(&body);
+        let mut ids = Vec::new();
+        while let Some(msg) = decoder.decode::<Msg>().unwrap() {
+            ids.push(msg.id);
+        }
+        assert!(decoder.is_empty());
+        ids
+    }
+
+    fn app(
+    ) -> impl Service<Request<Once<Bytes>>, Response = WebResponse<ResponseBody<ResponseBody>>, Error = impl fmt::Debug>
+    {
+        App::new()
+            .at("/test.Service/Unary", post(handler_service(unary)))
+            .at("/test.Service/Bidi", post(handler_service(bidi)))
+            .at("/test.Service/Fail", post(handler_service(fail)))
+            .finish()
+            .build(())
+            .now_or_panic()
+            .unwrap()
+    }
+
+    #[test]
+    fn unary_call() {
+        let res = app()
+            .call(request("/test.Service/Unary", &[21]))
+            .now_or_panic()
+            .unwrap();
+
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), GRPC);
+        assert!(!res.headers().contains_key(GRPC_STATUS));
+
+        let trailers = res.extensions().get::<Trailers>().unwrap().clone();
+
+        let body = collect_body(res.into_body()).now_or_panic().unwrap();
+        assert_eq!(decode_body(body), [42]);
+        assert_eq!(trailers.take().get(GRPC_STATUS).unwrap(), "0");
+    }
+
+    #[test]
+    fn status() {
+        let res = app().call(request("/test.Service/Unary", &[0])).now_or_panic().unwrap();
+
+        assert_eq!(res.headers().get(GRPC_STATUS).unwrap(), "3");
+        assert_eq!(res.headers().get(GRPC_MESSAGE).unwrap(), "id can't be 0");
+        assert!(res.extensions().get::<Trailers>().is_none());
+
+        // unary call with multiple messages.
+        let res = app()
+            .call(request("/test.Service/Unary", &[1, 2]))
+            .now_or_panic()
+            .unwrap();
+        assert_eq!(res.headers().get(GRPC_STATUS).unwrap(), "13");
+
+        // incomplete message.
+        let mut req = request("/test.Service/Unary", &[]);
+        *req.body_mut() = Once::new(Bytes::from_static(&[0, 0, 0, 0, 2, 8]));
+        let res = app().call(req).now_or_panic().unwrap();
+        assert_eq!(res.headers().get(GRPC_STATUS).unwrap(), "13");
+
+        // unknown message encoding.
+        let mut req = request("/test.Service/Unary", &[1]);
+        req.headers_mut()
+            .insert(GRPC_ENCODING, HeaderValue::from_static("snappy"));
+        let res = app().call(req).now_or_panic().unwrap();
+        assert_eq!(res.headers().get(GRPC_STATUS).unwrap(), "12");
+    }
+
+    #[test]
+    fn content_type() {
+        let mut req = request("/test.Service/Unary", &[1]);
+        req.headers_mut().remove(CONTENT_TYPE);
+        let res = app().call(req).now_or_panic().unwrap();
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(res.headers().get(GRPC_STATUS).unwrap(), "3");
+
+        let mut req = request("/test.Service/Bidi", &[1]);
+        req.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc-web"));
+        let res = app().call(req).now_or_panic().unwrap();
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(res.headers().get(GRPC_STATUS).unwrap(), "3");
+
+        let mut req = request("/test.Service/Unary", &[1]);
+        req.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc+proto"));
+        let res = app().call(req).now_or_panic().unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn streaming_call() {
+        let res = app()
+            .call(request("/test.Service/Bidi", &[1, 2, 3]))
+            .now_or_panic()
+            .unwrap();
+
+        let trailers = res.extensions().get::<Trailers>().unwrap().clone();
+        assert!(trailers.take().is_empty());
+
+        let body = collect_body(res.into_body()).now_or_panic().unwrap();
+        assert_eq!(decode_body(body), [2, 4, 6]);
+        assert_eq!(trailers.take().get(GRPC_STATUS).unwrap(), "0");
+    }
+
+    #[test]
+    fn streaming_status() {
+        let res = app().call(request("/test.Service/Fail", &[1])).now_or_panic().unwrap();
+
+        let trailers = res.extensions().get::<Trailers>().unwrap().clone();
+
+        let body = collect_body(res.into_body()).now_or_panic().unwrap();
+        assert_eq!(decode_body(body), [1]);
+
+        let trailers = trailers.take();
+        assert_eq!(trailers.get(GRPC_STATUS).unwrap(), "10");
+        assert_eq!(trailers.get(GRPC_MESSAGE).unwrap(), "aborted");
+    }
+
+    #[tokio::test]
+    async fn deadline() {
+        let service = App::new()
+            .at("/test.Service/Slow", post(handler_service(slow)))
+            .enclosed(EnforceDeadline::new())
+            .finish()
+            .build(())
+            .await
+            .unwrap();
+
+        let mut req = request("/test.Service/Slow", &[1]);
+        req.headers_mut().insert(GRPC_TIMEOUT, HeaderValue::from_static("10m"));
+
+        let res = service.call(req).await.unwrap();
+        assert_eq!(res.headers().get(GRPC_STATUS).unwrap(), "4");
+    }
+}
@@ -0,0 +1,187 @@
+use std::{error, fmt, future::Future};
+
+use crate::{
+    dev::bytes::{BufMut, Bytes, BytesMut},
+    handler::Responder,
+    http::{const_header_value::GRPC, header::CONTENT_TYPE, HeaderMap, HeaderValue, StatusCode},
+    request::WebRequest,
+    response::WebResponse,
+};
+
+use super::{Trailers, GRPC_MESSAGE, GRPC_STATUS};
+
+/// Status code of gRPC call.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Code {
+    Ok = 0,
+    Cancelled = 1,
+    Unknown = 2,
+    InvalidArgument = 3,
+    DeadlineExceeded = 4,
+    NotFound = 5,
+    AlreadyExists = 6,
+    PermissionDenied = 7,
+    ResourceExhausted = 8,
+    FailedPrecondition = 9,
+    Aborted = 10,
+    OutOfRange = 11,
+    Unimplemented = 12,
+    Internal = 13,
+    Unavailable = 14,
+    DataLoss = 15,
+    Unauthenticated = 16,
+}
+
+impl Code {
+    fn header_value(self) -> HeaderValue {
+        HeaderValue::from(self as i32)
+    }
+}
+
+/// Outcome of gRPC call. Returning it from handler as error would produce a response with status
+/// code and message in it's `grpc-status` and `grpc-message` headers.
+///
+/// # Examples
+/// ```rust
+/// # use xitca_web::grpc::{Code, Grpc, Status};
+/// # #[derive(Clone, PartialEq, prost::Message)]
+/// # struct Request {}
+/// # #[derive(Clone, PartialEq, prost::Message)]
+/// # struct Reply {}
+/// async fn handler(_: Grpc<Request>) -> Result<Grpc<Reply>, Status> {
+///     Err(Status::new(Code::Unimplemented, "not implemented yet"))
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Status {
+    code: Code,
+    message: String,
+    // http status code of Trailers-Only response.
+    http: StatusCode,
+}
+
+impl Status {
+    /// Construct a new status with given code and message.
+    pub fn new(code: Code, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            http: StatusCode::OK,
+        }
+    }
+
+    /// Construct a [Code::Ok] status with empty message.
+    pub fn ok() -> Self {
+        Self::new(Code::Ok, String::new())
+    }
+
+    /// Status code of this status.
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    /// Message of this status.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    // request not using gRPC content type. responded with 415 Unsupported Media Type.
+    pub(super) fn unsupported_media_type() -> Self {
+        Self {
+            http: StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ..Self::new(Code::InvalidArgument, "content-type is not application/grpc")
+        }
+    }
+
+    // Trailers-Only response. status is sent in headers and there is no body.
+    pub(crate) fn into_response<C, B>(self, req: WebRequest<'_, C, B>) -> WebResponse {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = self.http;
+        res.headers_mut().insert(CONTENT_TYPE, GRPC);
+        self.insert_headers(res.headers_mut());
+        res
+    }
+
+    // add status to trailers sent after response body.
+    pub(super) fn insert_trailers(&self, trailers: &Trailers) {
+        let mut headers = HeaderMap::new();
+        self.insert_headers(&mut headers);
+        trailers.extend(headers);
+    }
+
+    // insert status as headers.
+    fn insert_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(GRPC_STATUS, self.code.header_value());
+        if !self.message.is_empty() {
+            // percent encoded message is always a valid header value.
+            let value = HeaderValue::from_maybe_shared(percent_encode(&self.message)).unwrap();
+            headers.insert(GRPC_MESSAGE, value);
+        }
+    }
+}
+
+impl fmt::Debug for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Status")
+            .field("code", &self.code)
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "gRPC status: {:?}, message: {}", self.code, self.message)
+    }
+}
+
+impl error::Error for Status {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for Status {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let res = self.into_response(req);
+        async { res }
+    }
+}
+
+// percent encode grpc-message value. everything outside of printable ascii and the '%' char itself
+// is encoded.
+fn percent_encode(msg: &str) -> Bytes {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut buf = BytesMut::with_capacity(msg.len());
+
+    for b in msg.bytes() {
+        match b {
+            b' '..=b'~' if b != b'%' => buf.put_u8(b),
+            _ => {
+                buf.put_u8(b'%');
+                buf.put_u8(HEX[(b >> 4) as usize]);
+                buf.put_u8(HEX[(b & 0xf) as usize]);
+            }
+        }
+    }
+
+    buf.freeze()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn percent_encode_message() {
+        assert_eq!(percent_encode("hello world"), "hello world");
+        assert_eq!(percent_encode("100%"), "100%25");
+        assert_eq!(percent_encode("a\nb"), "a%0Ab");
+        assert_eq!(percent_encode("ü"), "%C3%BC");
+    }
+
+    #[test]
+    fn code() {
+        assert_eq!(Code::Unauthenticated.header_value(), "16");
+    }
+}
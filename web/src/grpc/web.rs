@@ -0,0 +1,727 @@
+use std::{
+    cell::RefCell,
+    convert::Infallible,
+    error, fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::stream::Stream;
+use pin_project_lite::pin_project;
+use xitca_http::{body::none_body_hint, response::Trailers};
+
+use crate::{
+    dev::{
+        bytes::{Buf, BufMut, Bytes, BytesMut},
+        service::{ready::ReadyService, BuildService, Service},
+    },
+    http::{
+        const_header_value::GRPC,
+        header::{
+            HeaderMap, HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+            ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE,
+            ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, CONTENT_LENGTH, CONTENT_TYPE, ORIGIN, VARY,
+        },
+        Method, StatusCode,
+    },
+    request::{RequestBody, WebRequest},
+    response::WebResponse,
+    stream::WebStream,
+};
+
+use super::{Code, Status};
+
+// flag byte of the frame carrying trailers at the end of gRPC-Web response body.
+const TRAILERS_FLAG: u8 = 0x80;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EXPOSE_HEADERS: HeaderValue = HeaderValue::from_static("grpc-status,grpc-message");
+#[allow(clippy::declare_interior_mutable_const)]
+const MAX_AGE: HeaderValue = HeaderValue::from_static("86400");
+
+/// A middleware translate gRPC-Web calls from browser clients to gRPC calls so they can be served by
+/// the same handlers of native gRPC clients.
+///
+/// Request with `application/grpc-web(+proto)` or `application/grpc-web-text(+proto)` content type
+/// is handled as following:
+/// - Request is passed to handlers as `application/grpc`. Base64 encoded body of `grpc-web-text`
+///   is decoded.
+/// - Status of call with response body is encoded into a trailers frame at the end of response body
+///   instead of sending it as HTTP trailers. Response body of `grpc-web-text` is base64 encoded.
+/// - CORS preflight request with `x-grpc-web` in `Access-Control-Request-Headers` is responded by
+///   the middleware. Responses to allowed origin expose `grpc-status` and `grpc-message` headers.
+///
+/// Other requests including native gRPC calls are passed through untouched.
+///
+/// By default every origin is allowed. [GrpcWeb::allow_origin] can be used to restrict it.
+///
+/// The request body type is [GrpcWebBody]. Handlers extract [GrpcStreaming](super::GrpcStreaming)
+/// should name it as body type. e.g. `GrpcStreaming<M, GrpcWebBody>`.
+///
+/// # Examples
+/// ```rust
+/// # use xitca_web::{
+/// #     grpc::{Grpc, GrpcWeb, Status},
+/// #     handler::handler_service,
+/// #     route::post,
+/// #     App, HttpServer,
+/// # };
+/// # #[derive(Clone, PartialEq, prost::Message)]
+/// # struct HelloRequest {}
+/// # #[derive(Clone, PartialEq, prost::Message)]
+/// # struct HelloReply {}
+/// # async fn say_hello(_: Grpc<HelloRequest>) -> Result<Grpc<HelloReply>, Status> {
+/// #     Ok(Grpc(HelloReply {}))
+/// # }
+/// # fn main() -> std::io::Result<()> {
+/// HttpServer::new(|| {
+///     App::new()
+///         .at("/helloworld.Greeter/SayHello", post(handler_service(say_hello)))
+///         .enclosed(GrpcWeb::new().allow_origin("https://example.com"))
+///         .finish()
+/// })
+/// .bind("127.0.0.1:8080")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct GrpcWeb {
+    origins: Vec<String>,
+}
+
+impl GrpcWeb {
+    /// Construct a middleware allow every origin.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only allow given origin. Can be called multiple times to allow more origins.
+    ///
+    /// Origin is matched against `Origin` header of request case-insensitively. e.g.
+    /// `https://example.com`.
+    pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origins.push(origin.into());
+        self
+    }
+}
+
+impl<S> BuildService<S> for GrpcWeb {
+    type Service = GrpcWebService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        let origins = self.origins.clone();
+        async { Ok(GrpcWebService { service, origins }) }
+    }
+}
+
+pub struct GrpcWebService<S> {
+    service: S,
+    origins: Vec<String>,
+}
+
+impl<S> GrpcWebService<S> {
+    // resolve Origin header of request when it's allowed.
+    fn allowed_origin(&self, headers: &HeaderMap) -> Option<HeaderValue> {
+        let origin = headers.get(ORIGIN)?;
+        let allowed = self.origins.is_empty()
+            || origin
+                .to_str()
+                .map(|o| self.origins.iter().any(|allow| allow.eq_ignore_ascii_case(o)))
+                .unwrap_or(false);
+        allowed.then(|| origin.clone())
+    }
+}
+
+impl<'r, S, C, B, ResB, Err> Service<WebRequest<'r, C, B>> for GrpcWebService<S>
+where
+    C: 'static,
+    B: WebStream + Default + 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, GrpcWebBody<B>>, Response = WebResponse<ResB>, Error = Err>,
+    ResB: WebStream,
+{
+    type Response = WebResponse<GrpcWebResponseBody<ResB>>;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, mut req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let origin = self.allowed_origin(req.req().headers());
+
+            if is_preflight(req.req().method(), req.req().headers()) {
+                return Ok(preflight(req.req().headers(), origin));
+            }
+
+            let (mut http_req, body) = req.take_request().replace_body(());
+
+            let content_type = http_req
+                .headers()
+                .get(CONTENT_TYPE)
+                .filter(|ct| ct.as_bytes().starts_with(b"application/grpc-web"))
+                .cloned();
+
+            let text = match content_type {
+                Some(ref ct) => {
+                    let text = ct.as_bytes().starts_with(b"application/grpc-web-text");
+                    let headers = http_req.headers_mut();
+                    headers.insert(CONTENT_TYPE, GRPC);
+                    if text {
+                        // length of base64 encoded body does not match the decoded one.
+                        headers.remove(CONTENT_LENGTH);
+                    }
+                    text
+                }
+                None => false,
+            };
+
+            let mut body = RefCell::new(GrpcWebBody {
+                body,
+                buf: text.then(BytesMut::new),
+                eof: false,
+            });
+
+            let req = WebRequest::new(&mut http_req, &mut body, req.ctx);
+
+            let mut res = self.service.call(req).await?;
+
+            let content_type = match content_type {
+                Some(ct) => ct,
+                None => return Ok(res.map(|body| GrpcWebResponseBody::Plain { body })),
+            };
+
+            let headers = res.headers_mut();
+
+            if let Some(origin) = origin {
+                headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+                headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, EXPOSE_HEADERS);
+                headers.append(VARY, HeaderValue::from_static("origin"));
+            }
+
+            // response not produced by gRPC handlers. e.g. 404 Not Found of unknown method.
+            if headers.get(CONTENT_TYPE) != Some(&GRPC) {
+                return Ok(res.map(|body| GrpcWebResponseBody::Plain { body }));
+            }
+
+            headers.insert(CONTENT_TYPE, content_type);
+            headers.remove(CONTENT_LENGTH);
+
+            // Trailers-Only response has no trailers and it's status is left in headers.
+            let trailers = res.extensions_mut().remove::<Trailers>();
+
+            Ok(res.map(|body| GrpcWebResponseBody::Web {
+                body,
+                trailers,
+                text,
+                eof: false,
+            }))
+        }
+    }
+}
+
+impl<'r, S, C, B, ResB, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for GrpcWebService<S>
+where
+    C: 'static,
+    B: WebStream + Default + 'static,
+    S: for<'rs> ReadyService<
+        WebRequest<'rs, C, GrpcWebBody<B>>,
+        Response = WebResponse<ResB>,
+        Error = Err,
+        Ready = Rdy,
+    >,
+    ResB: WebStream,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+fn is_preflight(method: &Method, headers: &HeaderMap) -> bool {
+    method == Method::OPTIONS
+        && headers.contains_key(ORIGIN)
+        && headers.contains_key(ACCESS_CONTROL_REQUEST_METHOD)
+        && headers
+            .get_all(ACCESS_CONTROL_REQUEST_HEADERS)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|h| h.trim().eq_ignore_ascii_case("x-grpc-web"))
+}
+
+fn preflight<B>(headers: &HeaderMap, origin: Option<HeaderValue>) -> WebResponse<GrpcWebResponseBody<B>> {
+    let mut res = WebResponse::new(GrpcWebResponseBody::None);
+
+    match origin {
+        Some(origin) => {
+            *res.status_mut() = StatusCode::NO_CONTENT;
+            let res_headers = res.headers_mut();
+            res_headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+            res_headers.insert(ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("POST"));
+            if let Some(allow) = headers.get(ACCESS_CONTROL_REQUEST_HEADERS) {
+                res_headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, allow.clone());
+            }
+            res_headers.insert(ACCESS_CONTROL_MAX_AGE, MAX_AGE);
+        }
+        None => *res.status_mut() = StatusCode::FORBIDDEN,
+    }
+
+    res.headers_mut().insert(VARY, HeaderValue::from_static("origin"));
+
+    res
+}
+
+// encode trailers into a trailers frame. the frame is a length prefixed HTTP/1 header block.
+fn trailers_frame(trailers: HeaderMap) -> Bytes {
+    let mut buf = BytesMut::new();
+    buf.put_u8(TRAILERS_FLAG);
+    buf.put_u32(0);
+
+    for (name, value) in trailers.iter() {
+        buf.extend_from_slice(name.as_str().as_bytes());
+        buf.put_u8(b':');
+        buf.extend_from_slice(value.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+    }
+
+    let len = (buf.len() - 5) as u32;
+    (&mut buf[1..5]).put_u32(len);
+
+    buf.freeze()
+}
+
+pin_project! {
+    /// Request body type of [GrpcWebService]. Body of `grpc-web-text` request is base64 decoded.
+    pub struct GrpcWebBody<B = RequestBody> {
+        #[pin]
+        body: B,
+        // buffer of base64 chars not decoded yet. None when body is not base64 encoded.
+        buf: Option<BytesMut>,
+        eof: bool,
+    }
+}
+
+impl<B: Default> Default for GrpcWebBody<B> {
+    fn default() -> Self {
+        Self {
+            body: B::default(),
+            buf: None,
+            eof: false,
+        }
+    }
+}
+
+impl<B> Stream for GrpcWebBody<B>
+where
+    B: WebStream,
+    B::Chunk: Into<Bytes>,
+{
+    type Item = Result<Bytes, GrpcWebBodyError<B::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        while !*this.eof {
+            match futures_core::ready!(this.body.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => match this.buf {
+                    Some(ref mut buf) => {
+                        buf.extend_from_slice(&chunk.into());
+                        match decode_text(buf) {
+                            Ok(bytes) if bytes.is_empty() => {}
+                            Ok(bytes) => return Poll::Ready(Some(Ok(bytes))),
+                            Err(e) => {
+                                *this.eof = true;
+                                return Poll::Ready(Some(Err(e)));
+                            }
+                        }
+                    }
+                    None => return Poll::Ready(Some(Ok(chunk.into()))),
+                },
+                Some(Err(e)) => {
+                    *this.eof = true;
+                    return Poll::Ready(Some(Err(GrpcWebBodyError::Stream(e))));
+                }
+                None => {
+                    *this.eof = true;
+                    if matches!(this.buf, Some(ref buf) if !buf.is_empty()) {
+                        return Poll::Ready(Some(Err(GrpcWebBodyError::Base64)));
+                    }
+                }
+            }
+        }
+
+        Poll::Ready(None)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.buf {
+            Some(_) => (0, None),
+            None => self.body.size_hint(),
+        }
+    }
+}
+
+// decode complete base64 quanta of buffer. a padded quantum ends a segment and following chars are
+// decoded as a new segment.
+fn decode_text<E>(buf: &mut BytesMut) -> Result<Bytes, GrpcWebBodyError<E>> {
+    let end = buf.len() - buf.len() % 4;
+
+    let mut out = Vec::new();
+    let mut start = 0;
+
+    for pos in (4..=end).step_by(4) {
+        if buf[pos - 1] == b'=' || pos == end {
+            base64::decode_config_buf(&buf[start..pos], base64::STANDARD, &mut out)
+                .map_err(|_| GrpcWebBodyError::Base64)?;
+            start = pos;
+        }
+    }
+
+    buf.advance(end);
+
+    Ok(out.into())
+}
+
+/// Error type of [GrpcWebBody] stream.
+pub enum GrpcWebBodyError<E> {
+    /// Body of `grpc-web-text` request is not valid base64.
+    Base64,
+    /// Error from request body stream.
+    Stream(E),
+}
+
+impl<E> fmt::Debug for GrpcWebBodyError<E>
+where
+    E: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Base64 => f.write_str("Base64"),
+            Self::Stream(ref e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+impl<E> fmt::Display for GrpcWebBodyError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Base64 => f.write_str("gRPC-Web text body is not valid base64"),
+            Self::Stream(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E> error::Error for GrpcWebBodyError<E> where E: fmt::Debug + fmt::Display {}
+
+pin_project! {
+    /// Response body type of [GrpcWebService].
+    #[project = GrpcWebResponseBodyProj]
+    pub enum GrpcWebResponseBody<B> {
+        // response of CORS preflight request.
+        None,
+        // response of request not using gRPC-Web.
+        Plain {
+            #[pin]
+            body: B,
+        },
+        // response of gRPC-Web call. trailers frame is sent after body.
+        Web {
+            #[pin]
+            body: B,
+            trailers: Option<Trailers>,
+            text: bool,
+            eof: bool,
+        },
+    }
+}
+
+impl<B> Stream for GrpcWebResponseBody<B>
+where
+    B: WebStream,
+    B::Chunk: Into<Bytes>,
+{
+    type Item = Result<Bytes, B::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.project() {
+            GrpcWebResponseBodyProj::None => Poll::Ready(None),
+            GrpcWebResponseBodyProj::Plain { body } => {
+                body.poll_next(cx).map(|item| item.map(|res| res.map(Into::into)))
+            }
+            GrpcWebResponseBodyProj::Web {
+                mut body,
+                trailers,
+                text,
+                eof,
+            } => loop {
+                if *eof {
+                    return Poll::Ready(None);
+                }
+
+                let chunk = match futures_core::ready!(body.as_mut().poll_next(cx)) {
+                    Some(Ok(chunk)) => chunk.into(),
+                    // body error ends the call. it's status is sent with trailers frame.
+                    Some(Err(e)) => {
+                        *eof = true;
+                        let trailers = trailers.take().unwrap_or_default();
+                        Status::new(Code::Internal, e.to_string()).insert_trailers(&trailers);
+                        trailers_frame(trailers.take())
+                    }
+                    // trailers are complete when body ends.
+                    None => {
+                        *eof = true;
+                        match trailers.take() {
+                            Some(trailers) => trailers_frame(trailers.take()),
+                            None => return Poll::Ready(None),
+                        }
+                    }
+                };
+
+                if chunk.is_empty() {
+                    continue;
+                }
+
+                // every chunk is encoded with padding. clients decode them as concatenated segments.
+                let chunk = if *text {
+                    Bytes::from(base64::encode_config(&chunk, base64::STANDARD))
+                } else {
+                    chunk
+                };
+
+                return Poll::Ready(Some(Ok(chunk)));
+            },
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            Self::None => none_body_hint(),
+            Self::Plain { ref body } => body.size_hint(),
+            Self::Web { .. } => (0, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::handler_service,
+        http::Method,
+        route::post,
+        test::{TestBody, TestClient},
+        App,
+    };
+
+    use super::{
+        super::{codec, Grpc, GrpcStream, GrpcStreaming},
+        *,
+    };
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Msg {
+        #[prost(uint32, tag = "1")]
+        id: u32,
+    }
+
+    async fn unary(Grpc(msg): Grpc<Msg>) -> Result<Grpc<Msg>, Status> {
+        match msg.id {
+            0 => Err(Status::new(Code::InvalidArgument, "id can't be 0")),
+            id => Ok(Grpc(Msg { id: id * 2 })),
+        }
+    }
+
+    // echo one message and then fail.
+    async fn fail(
+        req: GrpcStreaming<Msg, GrpcWebBody<TestBody>>,
+    ) -> Result<GrpcStream<impl Stream<Item = Result<Msg, Status>>>, Status> {
+        struct Fail<S>(S, bool);
+
+        impl<S: Stream<Item = Result<Msg, Status>> + Unpin> Stream for Fail<S> {
+            type Item = Result<Msg, Status>;
+
+            fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                if self.1 {
+                    return Poll::Ready(Some(Err(Status::new(Code::Aborted, "aborted"))));
+                }
+                self.1 = true;
+                Pin::new(&mut self.0).poll_next(cx)
+            }
+        }
+
+        Ok(GrpcStream(Fail(req, false)))
+    }
+
+    fn frame(id: u32) -> Bytes {
+        codec::encode(&Msg { id }, http_encoding::ContentEncoding::NoOp).unwrap()
+    }
+
+    fn client(
+        web: GrpcWeb,
+    ) -> TestClient<
+        impl Service<
+            xitca_http::Request<TestBody>,
+            Response = WebResponse<impl Stream<Item = Result<Bytes, impl fmt::Debug>>>,
+        >,
+    > {
+        TestClient::new(
+            App::new()
+                .at("/test.Service/Unary", post(handler_service(unary)))
+                .at("/test.Service/Fail", post(handler_service(fail)))
+                .enclosed(web)
+                .finish(),
+        )
+        .now_or_panic()
+    }
+
+    #[test]
+    fn binary() {
+        let res = client(GrpcWeb::new())
+            .post("/test.Service/Unary")
+            .header("content-type", "application/grpc-web+proto")
+            .header("origin", "https://example.com")
+            .body(frame(21))
+            .send()
+            .now_or_panic();
+
+        res.assert_header("content-type", "application/grpc-web+proto")
+            .assert_header("access-control-allow-origin", "https://example.com")
+            .assert_header("access-control-expose-headers", "grpc-status,grpc-message");
+        assert!(!res.headers().contains_key("grpc-status"));
+
+        let mut expected = frame(42).to_vec();
+        expected.extend_from_slice(b"\x80\0\0\0\x0fgrpc-status:0\r\n");
+        assert_eq!(res.body().now_or_panic(), expected);
+    }
+
+    #[test]
+    fn text() {
+        // two padded segments each carry part of message frame.
+        let msg = frame(21);
+        let body = base64::encode(&msg[..4]) + &base64::encode(&msg[4..]);
+
+        let res = client(GrpcWeb::new())
+            .post("/test.Service/Unary")
+            .header("content-type", "application/grpc-web-text")
+            .body(body)
+            .send()
+            .now_or_panic();
+
+        res.assert_header("content-type", "application/grpc-web-text");
+        assert!(!res.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        let body = String::from_utf8(res.body().now_or_panic()).unwrap();
+        let mut buf = BytesMut::from(body.as_bytes());
+        let body = decode_text::<Infallible>(&mut buf).unwrap();
+        assert!(buf.is_empty());
+
+        let mut expected = frame(42).to_vec();
+        expected.extend_from_slice(b"\x80\0\0\0\x0fgrpc-status:0\r\n");
+        assert_eq!(body, expected);
+    }
+
+    #[test]
+    fn status() {
+        let res = client(GrpcWeb::new())
+            .post("/test.Service/Unary")
+            .header("content-type", "application/grpc-web")
+            .body(frame(0))
+            .send()
+            .now_or_panic();
+
+        // Trailers-Only response.
+        res.assert_header("content-type", "application/grpc-web")
+            .assert_header("grpc-status", "3");
+        assert!(res.body().now_or_panic().is_empty());
+
+        // native gRPC call is not affected.
+        let res = client(GrpcWeb::new())
+            .post("/test.Service/Unary")
+            .header("content-type", "application/grpc")
+            .body(frame(21))
+            .send()
+            .now_or_panic();
+
+        res.assert_header("content-type", "application/grpc");
+        assert!(res.extensions().get::<Trailers>().is_some());
+    }
+
+    #[test]
+    fn streaming_status() {
+        let res = client(GrpcWeb::new())
+            .post("/test.Service/Fail")
+            .header("content-type", "application/grpc-web")
+            .body(frame(21))
+            .send()
+            .now_or_panic();
+
+        let mut expected = frame(21).to_vec();
+        expected.extend_from_slice(b"\x80\0\0\0\x26grpc-status:10\r\ngrpc-message:aborted\r\n");
+        assert_eq!(res.body().now_or_panic(), expected);
+    }
+
+    #[test]
+    fn preflight() {
+        let client = client(GrpcWeb::new().allow_origin("https://example.com"));
+
+        let res = client
+            .request(Method::OPTIONS, "/test.Service/Unary")
+            .header("origin", "https://example.com")
+            .header("access-control-request-method", "POST")
+            .header("access-control-request-headers", "content-type,x-grpc-web")
+            .send()
+            .now_or_panic();
+
+        res.assert_status(StatusCode::NO_CONTENT)
+            .assert_header("access-control-allow-origin", "https://example.com")
+            .assert_header("access-control-allow-methods", "POST")
+            .assert_header("access-control-allow-headers", "content-type,x-grpc-web");
+
+        let res = client
+            .request(Method::OPTIONS, "/test.Service/Unary")
+            .header("origin", "https://evil.com")
+            .header("access-control-request-method", "POST")
+            .header("access-control-request-headers", "x-grpc-web")
+            .send()
+            .now_or_panic();
+
+        res.assert_status(StatusCode::FORBIDDEN);
+        assert!(!res.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        let res = client
+            .post("/test.Service/Unary")
+            .header("content-type", "application/grpc-web")
+            .header("origin", "https://evil.com")
+            .body(frame(21))
+            .send()
+            .now_or_panic();
+
+        // call from origin not allowed is still served. browser rejects the response.
+        res.assert_header("content-type", "application/grpc-web");
+        assert!(!res.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[test]
+    fn base64_segments() {
+        let mut buf = BytesMut::from(&b"aGk=aGVsbG8"[..]);
+        assert_eq!(decode_text::<Infallible>(&mut buf).unwrap(), "hihel");
+        assert_eq!(&buf[..], b"bG8");
+
+        buf.extend_from_slice(b"=");
+        assert_eq!(decode_text::<Infallible>(&mut buf).unwrap(), "lo");
+        assert!(buf.is_empty());
+
+        let mut buf = BytesMut::from(&b"a*k="[..]);
+        assert!(decode_text::<Infallible>(&mut buf).is_err());
+    }
+}
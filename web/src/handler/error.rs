@@ -22,6 +22,9 @@ pub enum ExtractError<E = BodyError> {
     HeaderNotFound(HeaderName),
     /// Error of parsing bytes to Rust types.
     Parse(ParseError),
+    /// Error of gRPC request. Responded as gRPC status.
+    #[cfg(feature = "grpc")]
+    Grpc(crate::grpc::Status),
 }
 
 impl<E: fmt::Display> fmt::Display for ExtractError<E> {
@@ -31,6 +34,8 @@ impl<E: fmt::Display> fmt::Display for ExtractError<E> {
             Self::ExtensionNotFound => write!(f, "Extension can not be found"),
             Self::HeaderNotFound(ref name) => write!(f, "HeaderName: {name} not found."),
             Self::Parse(ref e) => fmt::Display::fmt(e, f),
+            #[cfg(feature = "grpc")]
+            Self::Grpc(ref status) => fmt::Display::fmt(status, f),
         }
     }
 }
@@ -48,8 +53,15 @@ impl<'r, C, B, E> Responder<WebRequest<'r, C, B>> for ExtractError<E> {
     type Future = impl Future<Output = Self::Output>;
 
     fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
-        let mut res = req.into_response(Bytes::new());
-        *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        let res = match self {
+            #[cfg(feature = "grpc")]
+            Self::Grpc(status) => status.into_response(req),
+            _ => {
+                let mut res = req.into_response(Bytes::new());
+                *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                res
+            }
+        };
         async { res }
     }
 }
@@ -65,6 +77,10 @@ impl fmt::Display for ParseError {
             _ParseError::JsonString(ref e) => fmt::Display::fmt(e, f),
             #[cfg(feature = "urlencoded")]
             _ParseError::UrlEncoded(ref e) => fmt::Display::fmt(e, f),
+            #[cfg(feature = "msgpack")]
+            _ParseError::MsgPack(ref e) => fmt::Display::fmt(e, f),
+            #[cfg(feature = "cbor")]
+            _ParseError::Cbor(ref e) => fmt::Display::fmt(e, f),
         }
     }
 }
@@ -77,6 +93,16 @@ pub(super) enum _ParseError {
     JsonString(serde_json::Error),
     #[cfg(feature = "urlencoded")]
     UrlEncoded(serde_urlencoded::de::Error),
+    #[cfg(feature = "msgpack")]
+    MsgPack(rmp_serde::decode::Error),
+    #[cfg(feature = "cbor")]
+    Cbor(ciborium::de::Error<std::io::Error>),
+}
+
+impl From<_ParseError> for ParseError {
+    fn from(e: _ParseError) -> Self {
+        Self(e)
+    }
 }
 
 impl<E> From<_ParseError> for ExtractError<E> {
@@ -0,0 +1,79 @@
+use std::{future::Future, net::IpAddr};
+
+use crate::{
+    handler::{error::ExtractError, FromRequest},
+    http::header::HOST,
+    request::WebRequest,
+    stream::WebStream,
+};
+
+use xitca_http::request::RemoteAddr;
+
+/// Information of the connection a request comes from.
+///
+/// When [ProxyHeaders](crate::middleware::proxy_headers::ProxyHeaders) middleware is in use the information
+/// is resolved from the headers set by trusted proxies. Otherwise it's taken from request's remote address,
+/// uri and `Host` header.
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionInfo {
+    pub(crate) client_ip: Option<IpAddr>,
+    pub(crate) scheme: Option<String>,
+    pub(crate) host: Option<String>,
+}
+
+impl ConnectionInfo {
+    pub(crate) fn from_request<C, B>(req: &WebRequest<'_, C, B>) -> Self {
+        let req = req.req();
+        Self {
+            client_ip: remote_ip(req.remote_addr()),
+            scheme: req.uri().scheme_str().map(str::to_owned),
+            host: req
+                .headers()
+                .get(HOST)
+                .and_then(|v| v.to_str().ok())
+                .or_else(|| req.uri().authority().map(|a| a.as_str()))
+                .map(str::to_owned),
+        }
+    }
+
+    /// IP address of client.
+    pub fn client_ip(&self) -> Option<IpAddr> {
+        self.client_ip
+    }
+
+    /// Scheme client used to connect. e.g. `https`.
+    pub fn scheme(&self) -> Option<&str> {
+        self.scheme.as_deref()
+    }
+
+    /// Host client requested. Port is included when presents.
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+}
+
+pub(crate) fn remote_ip(addr: &RemoteAddr) -> Option<IpAddr> {
+    match *addr {
+        RemoteAddr::V4(ip, _) => Some(IpAddr::V4(ip)),
+        RemoteAddr::V6(ip, _) => Some(IpAddr::V6(ip)),
+        RemoteAddr::None => None,
+    }
+}
+
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for ConnectionInfo
+where
+    B: WebStream,
+{
+    type Type<'b> = ConnectionInfo;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let info = match req.req().extensions().get::<ConnectionInfo>() {
+            Some(info) => info.clone(),
+            None => ConnectionInfo::from_request(req),
+        };
+        async { Ok(info) }
+    }
+}
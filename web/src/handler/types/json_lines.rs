@@ -0,0 +1,368 @@
+use std::{
+    error, fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::stream::Stream;
+use pin_project_lite::pin_project;
+use serde::{de::DeserializeOwned, ser::Serialize};
+use xitca_http::{body::StreamBody, error::BodyError};
+
+use crate::{
+    dev::bytes::{BufMut, BufMutWriter, Bytes, BytesMut},
+    handler::{
+        error::{_ParseError, ExtractError, ParseError},
+        FromRequest, Responder,
+    },
+    http::{header::CONTENT_TYPE, HeaderValue},
+    request::{RequestBody, WebRequest},
+    response::{ResponseBody, WebResponse},
+    stream::WebStream,
+};
+
+use super::header::{self, HeaderRef};
+
+const DEFAULT_LINE_LIMIT: usize = 64 * 1024;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NDJSON: HeaderValue = HeaderValue::from_static("application/x-ndjson");
+
+/// Responder type for newline delimited json (NDJSON). Each item of the stream is serialized as one
+/// line of json and sent as a chunk of response body as soon as it's produced.
+pub struct JsonLines<S>(pub S);
+
+impl<'r, C, B, S, T> Responder<WebRequest<'r, C, B>> for JsonLines<S>
+where
+    S: Stream<Item = T> + 'static,
+    T: Serialize,
+{
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let body: StreamBody = Box::pin(JsonLinesEncoder { stream: self.0 });
+        let mut res = req.into_response(ResponseBody::stream(body));
+        res.headers_mut().insert(CONTENT_TYPE, NDJSON);
+        async { res }
+    }
+}
+
+pin_project! {
+    struct JsonLinesEncoder<S> {
+        #[pin]
+        stream: S,
+    }
+}
+
+impl<S, T> Stream for JsonLinesEncoder<S>
+where
+    S: Stream<Item = T>,
+    T: Serialize,
+{
+    type Item = Result<Bytes, BodyError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = match futures_core::ready!(self.project().stream.poll_next(cx)) {
+            Some(item) => item,
+            None => return Poll::Ready(None),
+        };
+
+        let mut bytes = BytesMut::new();
+        let res = match serde_json::to_writer(BufMutWriter(&mut bytes), &item) {
+            Ok(_) => {
+                bytes.put_u8(b'\n');
+                Ok(bytes.freeze())
+            }
+            Err(e) => Err(BodyError::from(Box::new(e) as Box<dyn error::Error + Send + Sync>)),
+        };
+
+        Poll::Ready(Some(res))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+/// Extract type for newline delimited json (NDJSON) request body. Body is decoded lazily as a [Stream]
+/// of `T` one line at a time. Empty lines are skipped.
+///
+/// const generic param LIMIT is for max size of a single line in bytes. Line larger than limit would
+/// yield [JsonLinesError::Overflow] and end the stream.
+///
+/// Default limit is 64KiB.
+pub struct JsonLinesBody<T, B = RequestBody, const LIMIT: usize = DEFAULT_LINE_LIMIT> {
+    body: B,
+    buf: BytesMut,
+    // offset of buf that has been searched for newline.
+    searched: usize,
+    eof: bool,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<'a, 'r, C, B, T, const LIMIT: usize> FromRequest<'a, WebRequest<'r, C, B>> for JsonLinesBody<T, B, LIMIT>
+where
+    B: WebStream + Default,
+    T: DeserializeOwned,
+{
+    type Type<'b> = JsonLinesBody<T, B, LIMIT>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        async move {
+            HeaderRef::<'a, { header::CONTENT_TYPE }>::from_request(req).await?;
+
+            Ok(JsonLinesBody {
+                body: req.take_body_ref(),
+                buf: BytesMut::new(),
+                searched: 0,
+                eof: false,
+                _item: PhantomData,
+            })
+        }
+    }
+}
+
+/// Error type of [JsonLinesBody] stream.
+pub enum JsonLinesError<E> {
+    /// A line is larger than limit.
+    Overflow,
+    /// Error of parsing a line to Rust type.
+    Parse(ParseError),
+    /// Error from request body stream.
+    Stream(E),
+}
+
+impl<E> fmt::Debug for JsonLinesError<E>
+where
+    E: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Overflow => f.write_str("Overflow"),
+            Self::Parse(ref e) => write!(f, "{:?}", e),
+            Self::Stream(ref e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+impl<E> fmt::Display for JsonLinesError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Overflow => f.write_str("Json line is larger than limit"),
+            Self::Parse(ref e) => write!(f, "{}", e),
+            Self::Stream(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E> error::Error for JsonLinesError<E> where E: fmt::Debug + fmt::Display {}
+
+impl<T, B, const LIMIT: usize> JsonLinesBody<T, B, LIMIT>
+where
+    T: DeserializeOwned,
+{
+    // try to split a complete line from buffer and parse it.
+    fn next_line<E>(&mut self) -> Option<Result<T, JsonLinesError<E>>> {
+        loop {
+            let pos = self.buf[self.searched..].iter().position(|b| *b == b'\n');
+
+            let line = match pos {
+                Some(pos) => {
+                    let line = self.buf.split_to(self.searched + pos + 1);
+                    self.searched = 0;
+                    line
+                }
+                None if self.eof && !self.buf.is_empty() => {
+                    self.searched = 0;
+                    self.buf.split()
+                }
+                None => {
+                    self.searched = self.buf.len();
+                    return (self.buf.len() > LIMIT).then(|| self.overflow());
+                }
+            };
+
+            let line = trim(&line);
+
+            if line.len() > LIMIT {
+                return Some(self.overflow());
+            }
+
+            if !line.is_empty() {
+                let res =
+                    serde_json::from_slice(line).map_err(|e| JsonLinesError::Parse(_ParseError::JsonString(e).into()));
+                return Some(res);
+            }
+        }
+    }
+
+    fn overflow<E>(&mut self) -> Result<T, JsonLinesError<E>> {
+        self.eof = true;
+        self.buf.clear();
+        self.searched = 0;
+        Err(JsonLinesError::Overflow)
+    }
+}
+
+// strip leading and trailing ascii whitespace of a line.
+fn trim(mut line: &[u8]) -> &[u8] {
+    while let [first, rest @ ..] = line {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        line = rest;
+    }
+
+    while let [rest @ .., last] = line {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+        line = rest;
+    }
+
+    line
+}
+
+impl<T, B, const LIMIT: usize> Stream for JsonLinesBody<T, B, LIMIT>
+where
+    B: WebStream + Unpin,
+    T: DeserializeOwned,
+{
+    type Item = Result<T, JsonLinesError<B::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(res) = this.next_line() {
+                return Poll::Ready(Some(res));
+            }
+
+            if this.eof {
+                return Poll::Ready(None);
+            }
+
+            match futures_core::ready!(Pin::new(&mut this.body).poll_next(cx)) {
+                Some(Ok(chunk)) => this.buf.extend_from_slice(chunk.as_ref()),
+                Some(Err(e)) => {
+                    this.eof = true;
+                    this.buf.clear();
+                    return Poll::Ready(Some(Err(JsonLinesError::Stream(e))));
+                }
+                None => this.eof = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::future::poll_fn;
+
+    use serde::Deserialize;
+    use xitca_http::{body::Once, request::Request};
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        dev::service::{BuildService, Service},
+        handler::handler_service,
+        http::Method,
+        route::post,
+        test::collect_body,
+        App,
+    };
+
+    use super::*;
+
+    #[derive(Deserialize, serde::Serialize)]
+    struct Item {
+        id: u32,
+    }
+
+    async fn handler(body: JsonLinesBody<Item, Once<Bytes>, 16>) -> JsonLines<impl Stream<Item = Item>> {
+        let mut body = body;
+        let mut items = Vec::new();
+        while let Some(item) = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await {
+            match item {
+                Ok(item) => items.push(Item { id: item.id * 2 }),
+                Err(JsonLinesError::Overflow) => items.push(Item { id: 0 }),
+                Err(_) => items.push(Item { id: 1 }),
+            }
+        }
+        JsonLines(Iter(items.into_iter()))
+    }
+
+    struct Iter<I>(I);
+
+    impl<I: Iterator + Unpin> Stream for Iter<I> {
+        type Item = I::Item;
+
+        fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.0.next())
+        }
+    }
+
+    #[test]
+    fn json_lines() {
+        let body = b"{\"id\":1}\n\r\n{\"id\": 2}\r\n{\"id\": \"3\"}\n{\"id\":                  4}\n{\"id\":5}";
+
+        let mut req = Request::new(Once::new(Bytes::from_static(body)));
+        *req.method_mut() = Method::POST;
+        req.headers_mut().insert(CONTENT_TYPE, NDJSON);
+
+        let res = App::new()
+            .at("/", post(handler_service(handler)))
+            .finish()
+            .build(())
+            .now_or_panic()
+            .unwrap()
+            .call(req)
+            .now_or_panic()
+            .unwrap();
+
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), NDJSON);
+
+        let body = collect_body(res.into_body()).now_or_panic().unwrap();
+
+        assert_eq!(body, b"{\"id\":2}\n{\"id\":4}\n{\"id\":1}\n{\"id\":0}\n");
+    }
+
+    #[test]
+    fn split_lines() {
+        let chunks = ["{\"i", "d\":1}\n{\"id\"", ":2}\r", "\n", "", "  {\"id\":3}"]
+            .into_iter()
+            .map(|chunk| Ok::<_, std::convert::Infallible>(Bytes::from_static(chunk.as_bytes())))
+            .collect::<Vec<_>>();
+
+        let mut body = JsonLinesBody::<Item, _, 16> {
+            body: Iter(chunks.into_iter()),
+            buf: BytesMut::new(),
+            searched: 0,
+            eof: false,
+            _item: PhantomData,
+        };
+
+        let mut ids = Vec::new();
+        while let Some(item) = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).now_or_panic() {
+            ids.push(item.ok().unwrap().id);
+        }
+
+        assert_eq!(ids, [1, 2, 3]);
+    }
+
+    #[test]
+    fn trim_line() {
+        assert_eq!(trim(b" \t{}\r\n"), b"{}");
+        assert_eq!(trim(b"\r\n"), b"");
+        assert_eq!(trim(b""), b"");
+    }
+}
@@ -1,4 +1,5 @@
 pub mod body;
+pub mod connection_info;
 pub mod extension;
 pub mod header;
 pub mod html;
@@ -14,6 +15,14 @@ pub mod query;
 
 #[cfg(feature = "json")]
 pub mod json;
+#[cfg(feature = "json")]
+pub mod json_lines;
+
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+
+#[cfg(feature = "cbor")]
+pub mod cbor;
 
 #[cfg(feature = "multipart")]
 pub mod multipart;
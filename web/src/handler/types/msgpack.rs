@@ -0,0 +1,163 @@
+use std::{
+    fmt,
+    future::{poll_fn, Future},
+    ops::{Deref, DerefMut},
+};
+
+use serde::{de::DeserializeOwned, ser::Serialize};
+use xitca_unsafe_collection::pin;
+
+use crate::{
+    dev::bytes::{BufMutWriter, BytesMut},
+    handler::{
+        error::{_ParseError, ExtractError},
+        FromRequest, Responder,
+    },
+    http::{const_header_value::MSGPACK, header::CONTENT_TYPE},
+    request::WebRequest,
+    response::WebResponse,
+    stream::WebStream,
+};
+
+use super::{
+    body::Body,
+    header::{self, HeaderRef},
+};
+
+const DEFAULT_LIMIT: usize = 1024 * 1024;
+
+/// Extract type for MessagePack object. const generic param LIMIT is for max size of the object in bytes.
+/// Object larger than limit would be treated as error.
+///
+/// Default limit is [DEFAULT_LIMIT] in bytes.
+pub struct MsgPack<T, const LIMIT: usize = DEFAULT_LIMIT>(pub T);
+
+impl<T, const LIMIT: usize> fmt::Debug for MsgPack<T, LIMIT>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MsgPack")
+            .field("value", &self.0)
+            .field("limit", &LIMIT)
+            .finish()
+    }
+}
+
+impl<T, const LIMIT: usize> Deref for MsgPack<T, LIMIT> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, const LIMIT: usize> DerefMut for MsgPack<T, LIMIT> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<'a, 'r, C, B, T, const LIMIT: usize> FromRequest<'a, WebRequest<'r, C, B>> for MsgPack<T, LIMIT>
+where
+    B: WebStream + Default,
+    T: DeserializeOwned,
+{
+    type Type<'b> = MsgPack<T, LIMIT>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        async move {
+            HeaderRef::<'a, { header::CONTENT_TYPE }>::from_request(req).await?;
+
+            let limit = HeaderRef::<'a, { header::CONTENT_LENGTH }>::from_request(req)
+                .await
+                .ok()
+                .and_then(|header| header.to_str().ok().and_then(|s| s.parse().ok()))
+                .map(|len| std::cmp::min(len, LIMIT))
+                .unwrap_or_else(|| LIMIT);
+
+            let Body(body) = Body::from_request(req).await?;
+
+            pin!(body);
+
+            let mut buf = BytesMut::new();
+
+            while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                let chunk = chunk.map_err(ExtractError::Body)?;
+                buf.extend_from_slice(chunk.as_ref());
+                if buf.len() > limit {
+                    break;
+                }
+            }
+
+            let value = rmp_serde::from_slice(&buf).map_err(_ParseError::MsgPack)?;
+
+            Ok(MsgPack(value))
+        }
+    }
+}
+
+impl<'r, C, B, T, const LIMIT: usize> Responder<WebRequest<'r, C, B>> for MsgPack<T, LIMIT>
+where
+    T: Serialize,
+{
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    #[inline]
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut bytes = BytesMut::new();
+        rmp_serde::encode::write_named(&mut BufMutWriter(&mut bytes), &self.0).unwrap();
+        let mut res = req.into_response(bytes.freeze());
+        res.headers_mut().insert(CONTENT_TYPE, MSGPACK);
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, http::StatusCode, route::post, test::TestClient, App};
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq, serde::Serialize)]
+    struct Object {
+        name: String,
+        id: u64,
+    }
+
+    async fn handler(MsgPack(mut object): MsgPack<Object>) -> MsgPack<Object> {
+        object.id += 1;
+        MsgPack(object)
+    }
+
+    #[test]
+    fn round_trip() {
+        let client = TestClient::new(App::new().at("/", post(handler_service(handler))).finish()).now_or_panic();
+
+        let object = Object {
+            name: String::from("xitca"),
+            id: 1,
+        };
+        let body = rmp_serde::to_vec_named(&object).unwrap();
+
+        let res = client
+            .post("/")
+            .header("content-type", "application/msgpack")
+            .body(body)
+            .send()
+            .now_or_panic();
+        res.assert_status(StatusCode::OK);
+        res.assert_header("content-type", "application/msgpack");
+
+        let body = res.body().now_or_panic();
+        let object: Object = rmp_serde::from_slice(&body).unwrap();
+        assert_eq!(object.id, 2);
+        assert_eq!(object.name, "xitca");
+    }
+}
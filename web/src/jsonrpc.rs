@@ -0,0 +1,675 @@
+//! JSON-RPC 2.0 server support on top of [App](crate::App).
+//!
+//! [JsonRpc] is a service dispatching calls to methods registered by name. It's mounted on a path
+//! of App with [post] route.
+//!
+//! Methods are async functions receiving extractors as arguments, the same as functions passed to
+//! [handler_service]. Params of call are passed to method as json request body so they are extracted
+//! with [Json] type. Other extractors like [StateRef](crate::handler::state::StateRef) work the same as
+//! in handlers. Result of call is returned from method as [Json], [Value] or `Result<T, E>` where `T`
+//! can be serialized and `E` can be converted into [RpcError].
+//!
+//! Single and batch requests are supported. Calls of a batch are dispatched one after another in
+//! order. Every call is dispatched with it's own copy of request head and the extensions of request.
+//! Notification is dispatched without producing response and request only contains notifications is
+//! responded with `204 No Content`.
+//!
+//! # Examples
+//! ```rust
+//! use xitca_web::{
+//!     handler::{json::Json, state::StateRef},
+//!     jsonrpc::{JsonRpc, RpcError},
+//!     route::post,
+//!     App, HttpServer,
+//! };
+//!
+//! // params can be extracted from by-position array as tuple.
+//! async fn add(Json((a, b)): Json<(i64, i64)>) -> Result<i64, RpcError> {
+//!     a.checked_add(b).ok_or_else(|| RpcError::new(-32000, "overflow"))
+//! }
+//!
+//! // method without params.
+//! async fn name(StateRef(name): StateRef<'_, String>) -> Json<String> {
+//!     Json(name.clone())
+//! }
+//!
+//! # fn main() -> std::io::Result<()> {
+//! HttpServer::new(|| {
+//!     App::with_current_thread_state(String::from("xitca"))
+//!         .at(
+//!             "/rpc",
+//!             post(
+//!                 JsonRpc::new()
+//!                     .method("add", add)
+//!                     .method("name", name),
+//!             ),
+//!         )
+//!         .finish()
+//! })
+//! .bind("127.0.0.1:8080")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [post]: crate::route::post
+//! [handler_service]: crate::handler::handler_service
+
+use std::{
+    cell::RefCell, collections::HashMap, convert::Infallible, error, fmt, future::Future, marker::PhantomData, mem,
+};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use xitca_http::{body::Once, request::Request, util::service::handler::AsyncFn};
+
+use crate::{
+    app::object::{WebFactoryObject, WebObjectConstructor, WebServiceObject},
+    dev::{
+        bytes::Bytes,
+        service::{object::ObjectConstructor, BuildService, Service},
+    },
+    handler::{json::Json, ExtractError, FromRequest},
+    http::{
+        const_header_value::JSON,
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+        StatusCode,
+    },
+    request::WebRequest,
+    response::WebResponse,
+    stream::WebStream,
+};
+
+/// Request body type of method. It contains json encoded params of call.
+pub type ParamsBody = Once<Bytes>;
+
+type MethodObject<C> = WebFactoryObject<(), C, ParamsBody, Infallible, Result<Value, RpcError>, Infallible>;
+
+/// A service dispatch JSON-RPC 2.0 calls to registered methods.
+///
+/// See [module](self) level document for detail.
+pub struct JsonRpc<C> {
+    methods: Vec<(String, MethodObject<C>)>,
+}
+
+impl<C> Default for JsonRpc<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> JsonRpc<C> {
+    /// Construct a dispatcher without any method.
+    pub fn new() -> Self {
+        Self { methods: Vec::new() }
+    }
+
+    /// Register async function as method with given name.
+    ///
+    /// Registering the same name multiple times would make the last one override the others.
+    pub fn method<F, T, O>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        WebObjectConstructor<C, ParamsBody>: ObjectConstructor<MethodFactory<F, T, O>, Object = MethodObject<C>>,
+    {
+        let object = WebObjectConstructor::into_object(MethodFactory::new(func));
+        self.methods.push((name.into(), object));
+        self
+    }
+}
+
+impl<C> BuildService for JsonRpc<C>
+where
+    C: 'static,
+{
+    type Service = JsonRpcService<C>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, _: ()) -> Self::Future {
+        let futs = self
+            .methods
+            .iter()
+            .map(|(name, factory)| (name.clone(), factory.build(())))
+            .collect::<Vec<_>>();
+
+        async move {
+            let mut methods = HashMap::with_capacity(futs.len());
+            for (name, fut) in futs {
+                methods.insert(name, fut.await?);
+            }
+            Ok(JsonRpcService { methods })
+        }
+    }
+}
+
+pub struct JsonRpcService<C> {
+    methods: HashMap<String, WebServiceObject<C, ParamsBody, Result<Value, RpcError>, Infallible>>,
+}
+
+impl<'r, C, B> Service<WebRequest<'r, C, B>> for JsonRpcService<C>
+where
+    C: 'static,
+    B: WebStream + Default + 'static,
+{
+    type Response = WebResponse;
+    type Error = ExtractError<B::Error>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, mut req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let value = match Json::<Value>::from_request(&req).await {
+                Ok(Json(value)) => value,
+                Err(ExtractError::Parse(_)) => {
+                    let output = error_output(RpcError::parse_error(), Value::Null);
+                    return Ok(respond(req, Some(output)));
+                }
+                Err(e) => return Err(e),
+            };
+
+            // request body is consumed and replaced by params of every call.
+            let headers = req.req_mut().headers_mut();
+            headers.insert(CONTENT_TYPE, JSON);
+            headers.remove(CONTENT_LENGTH);
+
+            let output = match value {
+                Value::Array(calls) if !calls.is_empty() => {
+                    let mut outputs = Vec::with_capacity(calls.len());
+                    for call in calls {
+                        if let Some(output) = self.dispatch(&mut req, call).await {
+                            outputs.push(output);
+                        }
+                    }
+                    (!outputs.is_empty()).then_some(Value::Array(outputs))
+                }
+                value => self.dispatch(&mut req, value).await,
+            };
+
+            Ok(respond(req, output))
+        }
+    }
+}
+
+impl<C> JsonRpcService<C>
+where
+    C: 'static,
+{
+    // dispatch a single call. return None when call is a notification.
+    async fn dispatch<B>(&self, req: &mut WebRequest<'_, C, B>, call: Value) -> Option<Value> {
+        let mut call = match call {
+            Value::Object(call) => call,
+            _ => return Some(error_output(RpcError::invalid_request(), Value::Null)),
+        };
+
+        let id = match call.remove("id") {
+            None => None,
+            Some(id @ (Value::Null | Value::Number(_) | Value::String(_))) => Some(id),
+            Some(_) => return Some(error_output(RpcError::invalid_request(), Value::Null)),
+        };
+
+        let invalid = || {
+            Some(error_output(
+                RpcError::invalid_request(),
+                id.clone().unwrap_or(Value::Null),
+            ))
+        };
+
+        if call.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+            return invalid();
+        }
+
+        let method = match call.remove("method") {
+            Some(Value::String(method)) => method,
+            _ => return invalid(),
+        };
+
+        let params = match call.remove("params") {
+            None => Value::Null,
+            Some(params @ (Value::Array(_) | Value::Object(_))) => params,
+            Some(_) => return invalid(),
+        };
+
+        let res = match self.methods.get(&method) {
+            Some(service) => {
+                // serializing Value to Vec is infallible.
+                let params = serde_json::to_vec(&params).unwrap();
+                let mut body = RefCell::new(Once::new(Bytes::from(params)));
+                // extensions are moved to head of call and restored afterwards.
+                let mut head = clone_head(req.req);
+                mem::swap(head.extensions_mut(), req.req_mut().extensions_mut());
+                let res = service.call(WebRequest::new(&mut head, &mut body, req.ctx)).await;
+                mem::swap(head.extensions_mut(), req.req_mut().extensions_mut());
+                match res {
+                    Ok(res) => res,
+                    Err(e) => match e {},
+                }
+            }
+            None => Err(RpcError::method_not_found()),
+        };
+
+        let id = id?;
+
+        Some(match res {
+            Ok(result) => {
+                let mut output = Map::with_capacity(3);
+                output.insert("jsonrpc".into(), Value::from("2.0"));
+                output.insert("result".into(), result);
+                output.insert("id".into(), id);
+                Value::Object(output)
+            }
+            Err(e) => error_output(e, id),
+        })
+    }
+}
+
+fn clone_head(req: &Request<()>) -> Request<()> {
+    let mut head = Request::with_remote_addr((), *req.remote_addr());
+    *head.method_mut() = req.method().clone();
+    *head.uri_mut() = req.uri().clone();
+    *head.version_mut() = req.version();
+    *head.headers_mut() = req.headers().clone();
+    head
+}
+
+fn error_output(e: RpcError, id: Value) -> Value {
+    let mut error = Map::with_capacity(3);
+    error.insert("code".into(), Value::from(e.code));
+    error.insert("message".into(), Value::from(e.message));
+    if let Some(data) = e.data {
+        error.insert("data".into(), data);
+    }
+
+    let mut output = Map::with_capacity(3);
+    output.insert("jsonrpc".into(), Value::from("2.0"));
+    output.insert("error".into(), Value::Object(error));
+    output.insert("id".into(), id);
+    Value::Object(output)
+}
+
+fn respond<C, B>(req: WebRequest<'_, C, B>, output: Option<Value>) -> WebResponse {
+    match output {
+        Some(output) => {
+            // serializing Value to Vec is infallible.
+            let mut res = req.into_response(serde_json::to_vec(&output).unwrap());
+            res.headers_mut().insert(CONTENT_TYPE, JSON);
+            res
+        }
+        None => {
+            let mut res = req.into_response(Bytes::new());
+            *res.status_mut() = StatusCode::NO_CONTENT;
+            res
+        }
+    }
+}
+
+#[doc(hidden)]
+/// Factory type wrapping async function to produce uniform output of [JsonRpc] methods.
+pub struct MethodFactory<F, T, O> {
+    func: F,
+    _p: PhantomData<(T, O)>,
+}
+
+impl<F, T, O> MethodFactory<F, T, O> {
+    const fn new(func: F) -> Self {
+        Self { func, _p: PhantomData }
+    }
+}
+
+impl<F, T, O> BuildService for MethodFactory<F, T, O>
+where
+    F: Clone,
+{
+    type Service = MethodService<F, T, O>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, _: ()) -> Self::Future {
+        let func = self.func.clone();
+        async { Ok(MethodService { func, _p: PhantomData }) }
+    }
+}
+
+#[doc(hidden)]
+pub struct MethodService<F, T, O> {
+    func: F,
+    _p: PhantomData<(T, O)>,
+}
+
+impl<'r, F, C, T, O> Service<WebRequest<'r, C, ParamsBody>> for MethodService<F, T, O>
+where
+    C: 'static,
+    // for borrowed extrctors, `T` is the `'static` version of the extractors
+    T: FromRequest<'static, WebRequest<'r, C, ParamsBody>>,
+    T::Error: Into<RpcError>,
+    F: AsyncFn<T>, // just to assist type inference to pinpoint `T`
+
+    F: for<'a> AsyncFn<T::Type<'a>, Output = O> + Clone + 'static,
+    O: MethodResponse,
+{
+    type Response = Result<Value, RpcError>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, req: WebRequest<'r, C, ParamsBody>) -> Self::Future<'_> {
+        async move {
+            match T::Type::<'_>::from_request(&req).await {
+                Ok(extract) => Ok(self.func.call(extract).await.into_result()),
+                Err(e) => Ok(Err(e.into())),
+            }
+        }
+    }
+}
+
+/// Output type of [JsonRpc] method. Implemented for [Json], [Value] and `Result<T, E>` where `T` can
+/// be serialized and `E` can be converted into [RpcError].
+pub trait MethodResponse {
+    /// Convert to result of successful call or error of failed call.
+    fn into_result(self) -> Result<Value, RpcError>;
+}
+
+impl MethodResponse for Value {
+    fn into_result(self) -> Result<Value, RpcError> {
+        Ok(self)
+    }
+}
+
+impl<T, const LIMIT: usize> MethodResponse for Json<T, LIMIT>
+where
+    T: Serialize,
+{
+    fn into_result(self) -> Result<Value, RpcError> {
+        to_value(self.0)
+    }
+}
+
+impl<T, E> MethodResponse for Result<T, E>
+where
+    T: Serialize,
+    E: Into<RpcError>,
+{
+    fn into_result(self) -> Result<Value, RpcError> {
+        self.map_err(Into::into).and_then(to_value)
+    }
+}
+
+fn to_value<T: Serialize>(result: T) -> Result<Value, RpcError> {
+    serde_json::to_value(result).map_err(|e| RpcError::internal_error(e.to_string()))
+}
+
+/// Error object of JSON-RPC call.
+///
+/// Error of extracting params from call is converted to [RpcError::INVALID_PARAMS] error. Other
+/// extract errors are converted to [RpcError::INTERNAL_ERROR] error.
+#[derive(Clone)]
+pub struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    /// Invalid json was received.
+    pub const PARSE_ERROR: i64 = -32700;
+    /// The json sent is not a valid request object.
+    pub const INVALID_REQUEST: i64 = -32600;
+    /// The method does not exist.
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    /// Invalid method parameters.
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// Internal JSON-RPC error.
+    pub const INTERNAL_ERROR: i64 = -32603;
+
+    /// Construct a new error with given code and message.
+    ///
+    /// Code from -32768 to -32000 is reserved for pre-defined errors. -32000 to -32099 is for
+    /// implementation defined server errors.
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Attach additional information about the error.
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Code of this error.
+    pub fn code(&self) -> i64 {
+        self.code
+    }
+
+    /// Message of this error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Additional information of this error.
+    pub fn data(&self) -> Option<&Value> {
+        self.data.as_ref()
+    }
+
+    pub fn parse_error() -> Self {
+        Self::new(Self::PARSE_ERROR, "Parse error")
+    }
+
+    pub fn invalid_request() -> Self {
+        Self::new(Self::INVALID_REQUEST, "Invalid Request")
+    }
+
+    pub fn method_not_found() -> Self {
+        Self::new(Self::METHOD_NOT_FOUND, "Method not found")
+    }
+
+    pub fn invalid_params(data: impl Into<String>) -> Self {
+        Self::new(Self::INVALID_PARAMS, "Invalid params").with_data(Value::String(data.into()))
+    }
+
+    pub fn internal_error(data: impl Into<String>) -> Self {
+        Self::new(Self::INTERNAL_ERROR, "Internal error").with_data(Value::String(data.into()))
+    }
+}
+
+impl fmt::Debug for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcError")
+            .field("code", &self.code)
+            .field("message", &self.message)
+            .field("data", &self.data)
+            .finish()
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "JSON-RPC error: {}, message: {}", self.code, self.message)
+    }
+}
+
+impl error::Error for RpcError {}
+
+impl<E> From<ExtractError<E>> for RpcError
+where
+    E: fmt::Display,
+{
+    fn from(e: ExtractError<E>) -> Self {
+        match e {
+            ExtractError::Parse(e) => Self::invalid_params(e.to_string()),
+            e => Self::internal_error(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::state::StateRef,
+        response::ResponseBody,
+        route::post,
+        test::{TestBody, TestClient},
+        App,
+    };
+
+    use super::*;
+
+    async fn add(Json((a, b)): Json<(i64, i64)>) -> Result<i64, RpcError> {
+        a.checked_add(b)
+            .ok_or_else(|| RpcError::new(-32000, "overflow").with_data(Value::from(a)))
+    }
+
+    async fn name(StateRef(name): StateRef<'_, String>) -> Value {
+        Value::from(name.as_str())
+    }
+
+    #[derive(Serialize)]
+    struct Info {
+        name: String,
+        extension: Option<u8>,
+    }
+
+    // extensions of request are observed by every call of a batch.
+    async fn info(req: &WebRequest<'_, String, ParamsBody>) -> Json<Info> {
+        Json(Info {
+            name: req.state().clone(),
+            extension: req.req().extensions().get::<u8>().copied(),
+        })
+    }
+
+    async fn extension<S, C, B, Res, Err>(service: &S, mut req: WebRequest<'_, C, B>) -> Result<Res, Err>
+    where
+        S: for<'r> Service<WebRequest<'r, C, B>, Response = Res, Error = Err>,
+    {
+        req.req_mut().extensions_mut().insert(7u8);
+        service.call(req).await
+    }
+
+    fn client(
+    ) -> TestClient<impl Service<xitca_http::Request<TestBody>, Response = WebResponse<ResponseBody<ResponseBody>>>>
+    {
+        TestClient::new(
+            App::with_current_thread_state(String::from("xitca"))
+                .at(
+                    "/rpc",
+                    post(
+                        JsonRpc::new()
+                            .method("add", add)
+                            .method("name", name)
+                            .method("info", info),
+                    ),
+                )
+                .enclosed_fn(extension)
+                .finish(),
+        )
+        .now_or_panic()
+    }
+
+    fn call(body: &'static str) -> Value {
+        let res = client()
+            .post("/rpc")
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .now_or_panic();
+        res.assert_status(StatusCode::OK)
+            .assert_header("content-type", "application/json");
+        serde_json::from_slice(&res.body().now_or_panic()).unwrap()
+    }
+
+    #[test]
+    fn single() {
+        let res = call(r#"{"jsonrpc":"2.0","method":"add","params":[1,2],"id":1}"#);
+        assert_eq!(res, serde_json::json!({"jsonrpc":"2.0","result":3,"id":1}));
+
+        let res = call(r#"{"jsonrpc":"2.0","method":"name","id":"a"}"#);
+        assert_eq!(res, serde_json::json!({"jsonrpc":"2.0","result":"xitca","id":"a"}));
+
+        let res = call(r#"{"jsonrpc":"2.0","method":"add","params":[9223372036854775807,1],"id":2}"#);
+        assert_eq!(
+            res,
+            serde_json::json!({"jsonrpc":"2.0","error":{"code":-32000,"message":"overflow","data":9223372036854775807_i64},"id":2})
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let code = |res: Value| res["error"]["code"].as_i64().unwrap();
+
+        let res = call(r#"{"jsonrpc":"2.0","method":"add","params":[1,2"#);
+        assert_eq!(code(res.clone()), RpcError::PARSE_ERROR);
+        assert_eq!(res["id"], Value::Null);
+
+        let res = call(r#"{"jsonrpc":"1.0","method":"add","id":1}"#);
+        assert_eq!(code(res.clone()), RpcError::INVALID_REQUEST);
+        assert_eq!(res["id"], 1);
+
+        assert_eq!(code(call(r#"[]"#)), RpcError::INVALID_REQUEST);
+        assert_eq!(code(call(r#""call""#)), RpcError::INVALID_REQUEST);
+
+        let res = call(r#"{"jsonrpc":"2.0","method":"sub","id":1}"#);
+        assert_eq!(code(res), RpcError::METHOD_NOT_FOUND);
+
+        let res = call(r#"{"jsonrpc":"2.0","method":"add","params":{"a":1},"id":1}"#);
+        assert_eq!(code(res), RpcError::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn batch() {
+        let res = call(
+            r#"[
+                {"jsonrpc":"2.0","method":"add","params":[1,2],"id":1},
+                {"jsonrpc":"2.0","method":"add","params":[3,4]},
+                {"jsonrpc":"2.0","method":"sub","id":2},
+                1,
+                {"jsonrpc":"2.0","method":"name","id":3}
+            ]"#,
+        );
+        assert_eq!(
+            res,
+            serde_json::json!([
+                {"jsonrpc":"2.0","result":3,"id":1},
+                {"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":2},
+                {"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid Request"},"id":null},
+                {"jsonrpc":"2.0","result":"xitca","id":3}
+            ])
+        );
+    }
+
+    #[test]
+    fn batch_extensions() {
+        let res = call(
+            r#"[
+                {"jsonrpc":"2.0","method":"info","id":1},
+                {"jsonrpc":"2.0","method":"info","id":2}
+            ]"#,
+        );
+        assert_eq!(
+            res,
+            serde_json::json!([
+                {"jsonrpc":"2.0","result":{"name":"xitca","extension":7},"id":1},
+                {"jsonrpc":"2.0","result":{"name":"xitca","extension":7},"id":2}
+            ])
+        );
+    }
+
+    #[test]
+    fn notification() {
+        let client = client();
+
+        for body in [
+            r#"{"jsonrpc":"2.0","method":"add","params":[1,2]}"#,
+            r#"[{"jsonrpc":"2.0","method":"add","params":[1,2]},{"jsonrpc":"2.0","method":"sub"}]"#,
+        ] {
+            let res = client
+                .post("/rpc")
+                .header("content-type", "application/json")
+                .body(body)
+                .send()
+                .now_or_panic();
+            res.assert_status(StatusCode::NO_CONTENT);
+            assert!(res.body().now_or_panic().is_empty());
+        }
+    }
+}
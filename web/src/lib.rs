@@ -12,6 +12,15 @@ pub mod response;
 pub mod stream;
 pub mod test;
 
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+#[cfg(feature = "jsonrpc")]
+pub mod jsonrpc;
+
+#[cfg(feature = "proxy")]
+pub mod proxy;
+
 #[cfg(feature = "codegen")]
 pub mod codegen {
     /// Derive macro for individual struct field extractable through [StateRef](crate::handler::state::StateRef)
@@ -46,7 +55,11 @@ pub mod codegen {
 }
 
 pub mod route {
-    pub use xitca_http::util::service::route::{connect, delete, get, head, options, patch, post, put, trace, Route};
+    pub use xitca_http::util::service::{
+        guard,
+        host::HostParams,
+        route::{connect, delete, get, head, options, patch, post, put, trace, Route},
+    };
 }
 
 pub mod dev {
@@ -0,0 +1,304 @@
+use std::{
+    cell::{Cell, RefCell},
+    convert::Infallible,
+    error, fmt,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use futures_core::stream::Stream;
+use pin_project_lite::pin_project;
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, BuildService, Service},
+    },
+    handler::Responder,
+    http::{header::CONTENT_LENGTH, StatusCode},
+    request::WebRequest,
+    response::WebResponse,
+    stream::WebStream,
+};
+
+/// A middleware limit the size of request body.
+///
+/// Request with `Content-Length` header larger than limit is rejected at once with `413 Payload Too Large`.
+/// Otherwise request body is wrapped in [LimitBody] which counts the bytes streamed and yields an error
+/// when the limit is passed. In that case when the service passed to BodyLimit returns an error the
+/// request is responded with `413 Payload Too Large` too.
+///
+/// The limit applies to every extractor consuming request body. e.g. `Body`, `Multipart`, `Json` etc.
+/// BodyLimit can be applied to [App](crate::App) or to single route with [BuildServiceExt::enclosed].
+///
+/// [BuildServiceExt::enclosed]: crate::dev::service::BuildServiceExt::enclosed
+#[derive(Clone, Copy)]
+pub struct BodyLimit {
+    limit: usize,
+}
+
+impl BodyLimit {
+    /// Construct a new middleware limit request body to given bytes.
+    pub const fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+impl<S> BuildService<S> for BodyLimit {
+    type Service = BodyLimitService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        let limit = self.limit;
+        async move { Ok(BodyLimitService { service, limit }) }
+    }
+}
+
+pub struct BodyLimitService<S> {
+    service: S,
+    limit: usize,
+}
+
+/// Error type of [BodyLimitService].
+/// `First` variant contains [PayloadTooLarge] when request body is larger than limit.
+/// `Second` variant contains error returned by the service passed to BodyLimit.
+pub type BodyLimitError<E> = PipelineE<PayloadTooLarge, E>;
+
+/// Request body is larger than limit. Responds with `413 Payload Too Large`.
+pub struct PayloadTooLarge {
+    limit: usize,
+}
+
+impl PayloadTooLarge {
+    /// Limit of request body in bytes.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Debug for PayloadTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PayloadTooLarge").field("limit", &self.limit).finish()
+    }
+}
+
+impl fmt::Display for PayloadTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Request body is larger than limit: {} bytes", self.limit)
+    }
+}
+
+impl error::Error for PayloadTooLarge {}
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for BodyLimitService<S>
+where
+    C: 'static,
+    B: WebStream + Default + 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, LimitBody<B>>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = BodyLimitError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, mut req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let limit = self.limit;
+
+            let content_length = req
+                .req()
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok());
+
+            if matches!(content_length, Some(len) if len > limit as u64) {
+                return Err(BodyLimitError::First(PayloadTooLarge { limit }));
+            }
+
+            let (mut http_req, body) = req.take_request().replace_body(());
+
+            let overflow = Rc::new(Cell::new(false));
+
+            let mut body = RefCell::new(LimitBody {
+                body,
+                limit,
+                read: 0,
+                overflow: overflow.clone(),
+            });
+
+            let req = WebRequest::new(&mut http_req, &mut body, req.ctx);
+
+            self.service.call(req).await.map_err(|e| {
+                if overflow.get() {
+                    BodyLimitError::First(PayloadTooLarge { limit })
+                } else {
+                    BodyLimitError::Second(e)
+                }
+            })
+        }
+    }
+}
+
+impl<'r, S, C, B, Res, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for BodyLimitService<S>
+where
+    C: 'static,
+    B: WebStream + Default + 'static,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, LimitBody<B>>, Response = Res, Error = Err, Ready = Rdy>,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for PayloadTooLarge {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = StatusCode::PAYLOAD_TOO_LARGE;
+        async { res }
+    }
+}
+
+pin_project! {
+    /// Request body type of [BodyLimitService].
+    pub struct LimitBody<B> {
+        #[pin]
+        body: B,
+        limit: usize,
+        read: usize,
+        overflow: Rc<Cell<bool>>,
+    }
+}
+
+impl<B: Default> Default for LimitBody<B> {
+    fn default() -> Self {
+        Self {
+            body: B::default(),
+            limit: 0,
+            read: 0,
+            overflow: Rc::new(Cell::new(false)),
+        }
+    }
+}
+
+/// Error type of [LimitBody].
+pub enum LimitBodyError<E> {
+    /// Body stream passed the limit.
+    Overflow,
+    /// Error from body stream.
+    Stream(E),
+}
+
+impl<E> fmt::Debug for LimitBodyError<E>
+where
+    E: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Overflow => f.write_str("Overflow"),
+            Self::Stream(ref e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+impl<E> fmt::Display for LimitBodyError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Overflow => f.write_str("Request body is larger than limit"),
+            Self::Stream(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E> error::Error for LimitBodyError<E> where E: fmt::Debug + fmt::Display {}
+
+impl<B, T, E> Stream for LimitBody<B>
+where
+    B: Stream<Item = Result<T, E>>,
+    T: AsRef<[u8]>,
+{
+    type Item = Result<T, LimitBodyError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if this.overflow.get() {
+            return Poll::Ready(None);
+        }
+
+        match futures_core::ready!(this.body.poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                *this.read += chunk.as_ref().len();
+                if *this.read > *this.limit {
+                    this.overflow.set(true);
+                    Poll::Ready(Some(Err(LimitBodyError::Overflow)))
+                } else {
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+            }
+            Some(Err(e)) => Poll::Ready(Some(Err(LimitBodyError::Stream(e)))),
+            None => Poll::Ready(None),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, test::TestClient, App};
+
+    use super::*;
+
+    async fn handler(body: Vec<u8>) -> Vec<u8> {
+        body
+    }
+
+    #[test]
+    fn limit() {
+        let client = TestClient::new(
+            App::new()
+                .at("/", handler_service(handler))
+                .enclosed(BodyLimit::new(8))
+                .finish(),
+        )
+        .now_or_panic();
+
+        let res = client.post("/").body("12345678").send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string().now_or_panic(), "12345678");
+
+        client
+            .post("/")
+            .header("content-length", "9")
+            .body("123456789")
+            .send()
+            .now_or_panic()
+            .assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+
+        // streaming body without content-length.
+        client
+            .post("/")
+            .body("123456789")
+            .send()
+            .now_or_panic()
+            .assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,765 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    future::Future,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::Notify;
+use xitca_http::request::Request;
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{ready::ReadyService, BuildService, Service},
+    },
+    http::{
+        header::{HeaderName, AGE, AUTHORIZATION, CACHE_CONTROL, SET_COOKIE, VARY},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    request::WebRequest,
+    response::{ResponseBody, WebResponse},
+};
+
+/// An in-memory response cache middleware.
+///
+/// Response to `GET` and `HEAD` request is cached by request's method, uri and the value of request
+/// headers listed in response's `Vary` header or registered with [ResponseCache::vary]. Only successful
+/// response with buffered body (`ResponseBody::Bytes`) is cached and response's `Cache-Control` header
+/// is honored:
+/// - `no-store`, `no-cache` and `private` prevent response from being cached.
+/// - `s-maxage` or `max-age` (when `s-maxage` is absent) is used as freshness lifetime. Response without
+///   them is not cached.
+/// - `stale-while-revalidate` lets stale response be served at once while it's revalidated in the
+///   background by calling the service passed to ResponseCache.
+///
+/// Response with `Set-Cookie` header or `Vary: *` is not cached. Response to request with `Authorization`
+/// header is only cached when it's marked `public` or has `s-maxage`.
+///
+/// Concurrent requests for the same absent entry are coalesced and only one of them calls the service.
+/// Total size of cached responses is bounded and least recently used entries are evicted first.
+///
+/// Cache is not shared between worker threads.
+///
+/// # Panics
+/// Background revalidation is spawned with [tokio::task::spawn_local] and it panics when service is not
+/// running inside a [tokio::task::LocalSet]. xitca server runs services of it's workers in one.
+#[derive(Clone)]
+pub struct ResponseCache {
+    max_size: usize,
+    vary: Vec<HeaderName>,
+}
+
+impl ResponseCache {
+    /// Construct a new middleware with maximum size of cached responses in bytes.
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            vary: Vec::new(),
+        }
+    }
+
+    /// Add request header to cache key. Requests with different value of given header would be cached
+    /// separately.
+    pub fn vary(mut self, name: HeaderName) -> Self {
+        self.vary.push(name);
+        self
+    }
+}
+
+impl<S> BuildService<S> for ResponseCache {
+    type Service = ResponseCacheService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        let vary = self.vary.clone();
+        let cache = Rc::new(RefCell::new(Cache::new(self.max_size)));
+        async move {
+            Ok(ResponseCacheService {
+                service: Rc::new(service),
+                vary,
+                cache,
+            })
+        }
+    }
+}
+
+pub struct ResponseCacheService<S> {
+    // service is shared with background revalidation task.
+    service: Rc<S>,
+    vary: Vec<HeaderName>,
+    cache: Rc<RefCell<Cache>>,
+}
+
+impl<'r, S, C, B, ResB, Err> Service<WebRequest<'r, C, B>> for ResponseCacheService<S>
+where
+    C: Clone + 'static,
+    B: Default + 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = WebResponse<ResponseBody<ResB>>, Error = Err> + 'static,
+    ResB: 'static,
+    Err: 'static,
+{
+    type Response = WebResponse<ResponseBody<ResB>>;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let method = req.req().method();
+            if method != Method::GET && method != Method::HEAD {
+                return self.service.call(req).await;
+            }
+
+            let mut waited = false;
+
+            let key = loop {
+                // key is derived again after waiting on in-flight request. it's response can make request
+                // headers listed in it's Vary header part of key.
+                let key = self.cache.borrow().key(req.req(), &self.vary);
+                let lookup = self.cache.borrow_mut().lookup(&key, Instant::now());
+
+                match lookup {
+                    Lookup::Fresh(res) => return Ok(res.into_response()),
+                    Lookup::Stale(res, in_flight) => {
+                        // serve stale entry at once and revalidate it in background when no other
+                        // request is doing it.
+                        if in_flight.is_none() {
+                            self.revalidate(&req, key);
+                        }
+                        return Ok(res.into_response());
+                    }
+                    Lookup::Miss(Some(notify)) if !waited => {
+                        notify.notified().await;
+                        waited = true;
+                    }
+                    Lookup::Miss(_) => break key,
+                }
+            };
+
+            // only register in-flight request when not waited on another one. when response is not
+            // cacheable the following requests would not be queued behind each other.
+            let _guard = (!waited).then(|| InFlight::new(&self.cache, key));
+
+            let authorized = req.req().headers().contains_key(AUTHORIZATION);
+            let head = clone_head(req.req());
+
+            let res = self.service.call(req).await?;
+
+            store(&self.cache, &self.vary, &head, authorized, &res);
+
+            Ok(res)
+        }
+    }
+}
+
+impl<S> ResponseCacheService<S> {
+    // call service with a copy of request in a spawned task and store the response.
+    fn revalidate<'r, C, B, ResB, Err>(&self, req: &WebRequest<'r, C, B>, key: Key)
+    where
+        C: Clone + 'static,
+        B: Default + 'static,
+        S: for<'rs> Service<WebRequest<'rs, C, B>, Response = WebResponse<ResponseBody<ResB>>, Error = Err> + 'static,
+        ResB: 'static,
+        Err: 'static,
+    {
+        let guard = InFlight::new(&self.cache, key);
+        let service = self.service.clone();
+        let cache = self.cache.clone();
+        let vary = self.vary.clone();
+        let ctx = req.state().clone();
+        let authorized = req.req().headers().contains_key(AUTHORIZATION);
+        let mut head = clone_head(req.req());
+
+        tokio::task::spawn_local(async move {
+            let mut body = RefCell::new(B::default());
+            let req = WebRequest::new(&mut head, &mut body, &ctx);
+            if let Ok(res) = service.call(req).await {
+                store(&cache, &vary, &head, authorized, &res);
+            }
+            drop(guard);
+        });
+    }
+}
+
+impl<'r, S, C, B, ResB, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for ResponseCacheService<S>
+where
+    C: Clone + 'static,
+    B: Default + 'static,
+    S: for<'rs> ReadyService<
+            WebRequest<'rs, C, B>,
+            Response = WebResponse<ResponseBody<ResB>>,
+            Error = Err,
+            Ready = Rdy,
+        > + 'static,
+    ResB: 'static,
+    Err: 'static,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+// copy request head without extensions for storing response and revalidating in background.
+fn clone_head(req: &Request<()>) -> Request<()> {
+    let mut head = Request::with_remote_addr((), *req.remote_addr());
+    *head.method_mut() = req.method().clone();
+    *head.uri_mut() = req.uri().clone();
+    *head.version_mut() = req.version();
+    *head.headers_mut() = req.headers().clone();
+    head
+}
+
+// store response when it's cacheable. request headers listed in response's Vary header become part of
+// the key for following requests.
+fn store<B>(
+    cache: &RefCell<Cache>,
+    vary: &[HeaderName],
+    req: &Request<()>,
+    authorized: bool,
+    res: &WebResponse<ResponseBody<B>>,
+) {
+    if let Some((entry, ttl, swr, res_vary)) = Entry::try_from_response(res, authorized) {
+        let mut names = vary.to_vec();
+        names.extend(res_vary.into_iter().filter(|name| !vary.contains(name)));
+        let key = Key::new(req, &names);
+        cache.borrow_mut().insert(key, names, entry, ttl, swr, Instant::now());
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+struct Key {
+    method: Method,
+    uri: String,
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+}
+
+impl Key {
+    fn new(req: &Request<()>, vary: &[HeaderName]) -> Self {
+        Self {
+            method: req.method().clone(),
+            uri: req.uri().to_string(),
+            vary: vary
+                .iter()
+                .map(|name| (name.clone(), req.headers().get(name).cloned()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Entry {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Entry {
+    // check if response is cacheable and clone it as cache entry with freshness lifetime,
+    // stale-while-revalidate window and request headers it varies on.
+    fn try_from_response<B>(
+        res: &WebResponse<ResponseBody<B>>,
+        authorized: bool,
+    ) -> Option<(Self, Duration, Duration, Vec<HeaderName>)> {
+        if res.status() != StatusCode::OK || res.headers().contains_key(SET_COOKIE) {
+            return None;
+        }
+
+        let body = match *res.body() {
+            ResponseBody::Bytes { ref bytes } => bytes.clone(),
+            _ => return None,
+        };
+
+        let directives = CacheControl::from_headers(res.headers())?;
+
+        // shared cache must not store response to authorized request unless explicitly allowed.
+        if authorized && !directives.public && directives.s_maxage.is_none() {
+            return None;
+        }
+
+        let vary = vary(res.headers())?;
+
+        let ttl = directives.s_maxage.or(directives.max_age)?;
+        let swr = directives.stale_while_revalidate.unwrap_or(0);
+
+        if ttl == 0 && swr == 0 {
+            return None;
+        }
+
+        let entry = Self {
+            status: res.status(),
+            headers: res.headers().clone(),
+            body,
+        };
+
+        Some((entry, Duration::from_secs(ttl), Duration::from_secs(swr), vary))
+    }
+
+    fn size(&self) -> usize {
+        self.body.len()
+            + self
+                .headers
+                .iter()
+                .map(|(k, v)| k.as_str().len() + v.len())
+                .sum::<usize>()
+    }
+}
+
+// cache entry with its age.
+struct Cached {
+    entry: Entry,
+    age: Duration,
+}
+
+impl Cached {
+    fn into_response<B>(self) -> WebResponse<ResponseBody<B>> {
+        let mut res = WebResponse::new(ResponseBody::from(self.entry.body));
+        *res.status_mut() = self.entry.status;
+        *res.headers_mut() = self.entry.headers;
+        res.headers_mut().insert(AGE, HeaderValue::from(self.age.as_secs()));
+        res
+    }
+}
+
+enum Lookup {
+    Fresh(Cached),
+    // stale entry and in-flight revalidation.
+    Stale(Cached, Option<Rc<Notify>>),
+    // in-flight request for absent entry.
+    Miss(Option<Rc<Notify>>),
+}
+
+struct Slot {
+    entry: Entry,
+    size: usize,
+    stored: Instant,
+    expires: Instant,
+    stale_until: Instant,
+    last_used: u64,
+}
+
+struct Cache {
+    max_size: usize,
+    size: usize,
+    tick: u64,
+    entries: HashMap<Key, Slot>,
+    lru: BTreeMap<u64, Key>,
+    in_flight: HashMap<Key, Rc<Notify>>,
+    // request headers the last stored response of uri varies on and count of stored entries of uri.
+    // removed together with the last entry of uri.
+    vary: HashMap<String, (Vec<HeaderName>, usize)>,
+}
+
+impl Cache {
+    fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            size: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            lru: BTreeMap::new(),
+            in_flight: HashMap::new(),
+            vary: HashMap::new(),
+        }
+    }
+
+    fn key(&self, req: &Request<()>, vary: &[HeaderName]) -> Key {
+        let names = match self.vary.get(&req.uri().to_string()) {
+            Some((names, _)) => names,
+            None => vary,
+        };
+        Key::new(req, names)
+    }
+
+    fn lookup(&mut self, key: &Key, now: Instant) -> Lookup {
+        let in_flight = self.in_flight.get(key).cloned();
+
+        let (fresh, stale) = match self.entries.get(key) {
+            Some(slot) => (now < slot.expires, now < slot.stale_until),
+            None => return Lookup::Miss(in_flight),
+        };
+
+        if !stale {
+            self.remove(key);
+            return Lookup::Miss(in_flight);
+        }
+
+        self.tick += 1;
+        let tick = self.tick;
+        let slot = self.entries.get_mut(key).unwrap();
+        self.lru.remove(&slot.last_used);
+        self.lru.insert(tick, key.clone());
+        slot.last_used = tick;
+
+        let cached = Cached {
+            entry: slot.entry.clone(),
+            age: now.saturating_duration_since(slot.stored),
+        };
+
+        if fresh {
+            Lookup::Fresh(cached)
+        } else {
+            Lookup::Stale(cached, in_flight)
+        }
+    }
+
+    fn insert(&mut self, key: Key, vary: Vec<HeaderName>, entry: Entry, ttl: Duration, swr: Duration, now: Instant) {
+        self.remove(&key);
+
+        let size = entry.size();
+        if size > self.max_size {
+            return;
+        }
+
+        while self.size + size > self.max_size {
+            match self.lru.keys().next().copied() {
+                Some(tick) => {
+                    let key = self.lru.remove(&tick).unwrap();
+                    self.remove(&key);
+                }
+                None => break,
+            }
+        }
+
+        let (names, count) = self.vary.entry(key.uri.clone()).or_default();
+        *names = vary;
+        *count += 1;
+
+        self.tick += 1;
+        self.size += size;
+        self.lru.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            Slot {
+                entry,
+                size,
+                stored: now,
+                expires: now + ttl,
+                stale_until: now + ttl + swr,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(slot) = self.entries.remove(key) {
+            self.size -= slot.size;
+            self.lru.remove(&slot.last_used);
+
+            if let Some((_, count)) = self.vary.get_mut(&key.uri) {
+                *count -= 1;
+                if *count == 0 {
+                    self.vary.remove(&key.uri);
+                }
+            }
+        }
+    }
+}
+
+// guard of in-flight request. notify waiting requests on drop.
+struct InFlight {
+    cache: Rc<RefCell<Cache>>,
+    key: Key,
+}
+
+impl InFlight {
+    fn new(cache: &Rc<RefCell<Cache>>, key: Key) -> Self {
+        cache.borrow_mut().in_flight.insert(key.clone(), Rc::new(Notify::new()));
+        Self {
+            cache: cache.clone(),
+            key,
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Some(notify) = self.cache.borrow_mut().in_flight.remove(&self.key) {
+            notify.notify_waiters();
+        }
+    }
+}
+
+// parse Vary header into request header names. return None when response varies on `*`.
+fn vary(headers: &HeaderMap) -> Option<Vec<HeaderName>> {
+    let mut names = Vec::new();
+
+    for value in headers.get_all(VARY).iter() {
+        let value = value.to_str().ok()?;
+        for name in value.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            if name == "*" {
+                return None;
+            }
+            let name = HeaderName::from_bytes(name.as_bytes()).ok()?;
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+
+    Some(names)
+}
+
+#[derive(Default)]
+struct CacheControl {
+    public: bool,
+    max_age: Option<u64>,
+    s_maxage: Option<u64>,
+    stale_while_revalidate: Option<u64>,
+}
+
+impl CacheControl {
+    // parse Cache-Control header. return None when response must not be stored.
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let mut this = Self::default();
+
+        for value in headers.get_all(CACHE_CONTROL).iter() {
+            let value = value.to_str().ok()?;
+            for directive in value.split(',') {
+                let (name, arg) = match directive.split_once('=') {
+                    Some((name, arg)) => (name.trim(), Some(arg.trim().trim_matches('"'))),
+                    None => (directive.trim(), None),
+                };
+
+                let secs = || arg.and_then(|arg| arg.parse().ok());
+
+                if name.eq_ignore_ascii_case("no-store")
+                    || name.eq_ignore_ascii_case("no-cache")
+                    || name.eq_ignore_ascii_case("private")
+                {
+                    return None;
+                } else if name.eq_ignore_ascii_case("public") {
+                    this.public = true;
+                } else if name.eq_ignore_ascii_case("max-age") {
+                    this.max_age = secs();
+                } else if name.eq_ignore_ascii_case("s-maxage") {
+                    this.s_maxage = secs();
+                } else if name.eq_ignore_ascii_case("stale-while-revalidate") {
+                    this.stale_while_revalidate = secs();
+                }
+            }
+        }
+
+        Some(this)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use tokio::{task::LocalSet, time::sleep};
+
+    use crate::{
+        handler::handler_service,
+        test::{TestBody, TestClient},
+        App,
+    };
+
+    use super::*;
+
+    async fn handler(req: &WebRequest<'_, Rc<Cell<usize>>, TestBody>) -> WebResponse {
+        let count = req.state();
+        count.set(count.get() + 1);
+        let n = count.get();
+
+        sleep(Duration::from_millis(20)).await;
+
+        let path = req.req().uri().path();
+
+        let cache_control = match path {
+            "/private" => "private, max-age=60",
+            "/stale" => "max-age=0, stale-while-revalidate=60",
+            "/none" => "no-cache, max-age=60",
+            "/auth" | "/vary" | "/vary-all" => "max-age=60",
+            _ => "public, max-age=60",
+        };
+
+        let mut res = WebResponse::new(ResponseBody::from(n.to_string()));
+        let headers = res.headers_mut();
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+
+        match path {
+            "/cookie" => {
+                headers.insert(SET_COOKIE, HeaderValue::from_static("id=1"));
+            }
+            "/vary" => {
+                headers.insert(VARY, HeaderValue::from_static("Accept-Encoding"));
+            }
+            "/vary-all" => {
+                headers.insert(VARY, HeaderValue::from_static("*"));
+            }
+            _ => {}
+        }
+
+        res
+    }
+
+    fn cache_control(headers: &str) -> Option<(Option<u64>, Option<u64>, Option<u64>)> {
+        let mut map = HeaderMap::new();
+        map.insert(CACHE_CONTROL, HeaderValue::from_str(headers).unwrap());
+        CacheControl::from_headers(&map).map(|c| (c.max_age, c.s_maxage, c.stale_while_revalidate))
+    }
+
+    #[test]
+    fn directives() {
+        assert_eq!(cache_control("max-age=60"), Some((Some(60), None, None)));
+        assert_eq!(
+            cache_control("Max-Age=\"60\", s-maxage=10, stale-while-revalidate=5"),
+            Some((Some(60), Some(10), Some(5)))
+        );
+        assert_eq!(cache_control("public, max-age=60, no-store"), None);
+        assert_eq!(cache_control("private"), None);
+        assert_eq!(cache_control("no-cache, max-age=60"), None);
+
+        let mut headers = HeaderMap::new();
+        headers.append(VARY, HeaderValue::from_static("Accept-Encoding, accept-language"));
+        headers.append(VARY, HeaderValue::from_static("accept-encoding"));
+        assert_eq!(
+            vary(&headers),
+            Some(vec![
+                HeaderName::from_static("accept-encoding"),
+                HeaderName::from_static("accept-language")
+            ])
+        );
+        headers.append(VARY, HeaderValue::from_static("*"));
+        assert_eq!(vary(&headers), None);
+    }
+
+    #[test]
+    fn lru() {
+        let entry = |body: &'static str| Entry {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(body.as_bytes()),
+        };
+        let key = |uri: &str| Key {
+            method: Method::GET,
+            uri: uri.to_string(),
+            vary: Vec::new(),
+        };
+        let ttl = Duration::from_secs(60);
+        let now = Instant::now();
+
+        let mut cache = Cache::new(8);
+        cache.insert(key("/a"), Vec::new(), entry("aaa"), ttl, Duration::ZERO, now);
+        cache.insert(key("/b"), Vec::new(), entry("bbb"), ttl, Duration::ZERO, now);
+        assert!(matches!(cache.lookup(&key("/a"), now), Lookup::Fresh(_)));
+
+        // "/b" is least recently used.
+        cache.insert(key("/c"), Vec::new(), entry("ccc"), ttl, Duration::ZERO, now);
+        assert!(matches!(cache.lookup(&key("/a"), now), Lookup::Fresh(_)));
+        assert!(matches!(cache.lookup(&key("/b"), now), Lookup::Miss(None)));
+        assert!(matches!(cache.lookup(&key("/c"), now), Lookup::Fresh(_)));
+        assert_eq!(cache.size, 6);
+
+        // entry larger than max size is not stored.
+        cache.insert(key("/d"), Vec::new(), entry("ddddddddd"), ttl, Duration::ZERO, now);
+        assert!(matches!(cache.lookup(&key("/d"), now), Lookup::Miss(None)));
+
+        // expired entry is removed.
+        assert!(matches!(cache.lookup(&key("/a"), now + ttl), Lookup::Miss(None)));
+        assert_eq!(cache.size, 3);
+
+        // vary names of uri are removed with it's last entry.
+        assert_eq!(cache.vary.len(), 1);
+        assert!(cache.vary.contains_key("/c"));
+    }
+
+    #[tokio::test]
+    async fn cache() {
+        let count = Rc::new(Cell::new(0));
+
+        let client = TestClient::new(
+            App::with_current_thread_state(count.clone())
+                .at("/*path", handler_service(handler))
+                .enclosed(ResponseCache::new(1024).vary(HeaderName::from_static("accept-language")))
+                .finish(),
+        )
+        .await;
+
+        // concurrent misses are coalesced.
+        let (res1, res2, res3) = tokio::join!(
+            client.get("/a").send(),
+            client.get("/a").send(),
+            client.get("/a").send()
+        );
+        assert_eq!(res1.string().await, "1");
+        assert_eq!(res2.string().await, "1");
+        assert_eq!(res3.string().await, "1");
+
+        let res = client.get("/a").send().await;
+        assert!(res.headers().contains_key(AGE));
+        assert_eq!(res.string().await, "1");
+
+        // vary header is part of key.
+        let res = client.get("/a").header("accept-language", "en").send().await;
+        assert_eq!(res.string().await, "2");
+
+        // not cacheable responses.
+        assert_eq!(client.get("/private").send().await.string().await, "3");
+        assert_eq!(client.get("/private").send().await.string().await, "4");
+        assert_eq!(client.get("/none").send().await.string().await, "5");
+        assert_eq!(client.get("/none").send().await.string().await, "6");
+        assert_eq!(client.post("/a").send().await.string().await, "7");
+        assert_eq!(client.get("/cookie").send().await.string().await, "8");
+        assert_eq!(client.get("/cookie").send().await.string().await, "9");
+        assert_eq!(client.get("/vary-all").send().await.string().await, "10");
+        assert_eq!(client.get("/vary-all").send().await.string().await, "11");
+
+        // authorized request is only cached when response allows it explicitly.
+        let auth = |uri| client.get(uri).header("authorization", "Basic Zm9vOmJhcg==").send();
+        assert_eq!(auth("/auth").await.string().await, "12");
+        assert_eq!(auth("/auth").await.string().await, "13");
+        assert_eq!(auth("/b").await.string().await, "14");
+        assert_eq!(auth("/b").await.string().await, "14");
+
+        // request headers listed in response's Vary header are part of key.
+        // key is derived again after waiting on coalesced request.
+        let vary = |encoding| client.get("/vary").header("accept-encoding", encoding).send();
+        let (res1, res2, res3) = tokio::join!(vary("gzip"), vary("gzip"), vary("gzip"));
+        assert_eq!(res1.string().await, "15");
+        assert_eq!(res2.string().await, "15");
+        assert_eq!(res3.string().await, "15");
+        assert_eq!(vary("br").await.string().await, "16");
+        assert_eq!(vary("br").await.string().await, "16");
+        assert_eq!(vary("gzip").await.string().await, "15");
+    }
+
+    #[tokio::test]
+    async fn stale_while_revalidate() {
+        // revalidation is spawned as local task.
+        LocalSet::new()
+            .run_until(async {
+                let count = Rc::new(Cell::new(0));
+
+                let client = TestClient::new(
+                    App::with_current_thread_state(count.clone())
+                        .at("/*path", handler_service(handler))
+                        .enclosed(ResponseCache::new(1024))
+                        .finish(),
+                )
+                .await;
+
+                assert_eq!(client.get("/stale").send().await.string().await, "1");
+
+                // stale response is served at once and only one revalidation happens in background.
+                let (res1, res2) = tokio::join!(client.get("/stale").send(), client.get("/stale").send());
+                assert_eq!(res1.string().await, "1");
+                assert_eq!(res2.string().await, "1");
+                assert_eq!(count.get(), 1);
+
+                sleep(Duration::from_millis(100)).await;
+                assert_eq!(count.get(), 2);
+
+                assert_eq!(client.get("/stale").send().await.string().await, "2");
+            })
+            .await
+    }
+}
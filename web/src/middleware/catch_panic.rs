@@ -0,0 +1,249 @@
+use std::{
+    any::Any,
+    convert::Infallible,
+    fmt,
+    future::{poll_fn, Future},
+    panic::{catch_unwind, AssertUnwindSafe},
+    task::Poll,
+};
+
+use tracing::error;
+use xitca_unsafe_collection::pin;
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, BuildService, Service},
+    },
+    handler::Responder,
+    http::{header::CONTENT_TYPE, HeaderValue, StatusCode},
+    request::WebRequest,
+    response::WebResponse,
+};
+
+/// A middleware catch panic from the service it wraps and respond with `500 Internal Server Error`.
+///
+/// Panic is caught per request. The connection and other requests (h2 streams) on it are not affected.
+/// Panic payload is logged with request's method and uri at error level.
+///
+/// # Example:
+/// ```rust
+/// # use xitca_web::{handler::handler_service, middleware::catch_panic::CatchPanic, App, HttpServer};
+/// async fn index() -> &'static str {
+///     panic!("oops")
+/// }
+///
+/// # fn main() -> std::io::Result<()> {
+/// HttpServer::new(|| {
+///     App::new()
+///         .at("/", handler_service(index))
+///         .enclosed(CatchPanic::new().body("text/plain; charset=utf-8", "something went wrong"))
+///         .finish()
+/// })
+/// .bind("127.0.0.1:8080")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct CatchPanic {
+    body: Option<(HeaderValue, Bytes)>,
+}
+
+impl CatchPanic {
+    /// Construct a new middleware respond with empty body on panic.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set content type and body of the response on panic.
+    ///
+    /// # Panic:
+    ///
+    /// When content type is not a valid header value.
+    pub fn body(mut self, content_type: &'static str, body: impl Into<Bytes>) -> Self {
+        self.body = Some((HeaderValue::from_static(content_type), body.into()));
+        self
+    }
+}
+
+impl<S> BuildService<S> for CatchPanic {
+    type Service = CatchPanicService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        let body = self.body.clone();
+        async { Ok(CatchPanicService { service, body }) }
+    }
+}
+
+pub struct CatchPanicService<S> {
+    service: S,
+    body: Option<(HeaderValue, Bytes)>,
+}
+
+/// Error type of [CatchPanicService].
+/// `First` variant contains [Panicked] when the service panicked.
+/// `Second` variant contains error returned by the service passed to CatchPanic.
+pub type CatchPanicError<E> = PipelineE<Panicked, E>;
+
+/// Service panicked when handling request. Responds with `500 Internal Server Error`.
+pub struct Panicked {
+    message: String,
+    body: Option<(HeaderValue, Bytes)>,
+}
+
+impl Panicked {
+    /// Message of panic payload.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Debug for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Panicked").field("message", &self.message).finish()
+    }
+}
+
+impl fmt::Display for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Service panicked: {}", self.message)
+    }
+}
+
+impl std::error::Error for Panicked {}
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for CatchPanicService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = CatchPanicError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let method = req.req().method().clone();
+            let uri = req.req().uri().clone();
+
+            let res = match catch_unwind(AssertUnwindSafe(|| self.service.call(req))) {
+                Ok(fut) => {
+                    pin!(fut);
+                    poll_fn(|cx| match catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(cx))) {
+                        Ok(poll) => poll.map(Ok),
+                        Err(payload) => Poll::Ready(Err(payload)),
+                    })
+                    .await
+                }
+                Err(payload) => Err(payload),
+            };
+
+            match res {
+                Ok(res) => res.map_err(CatchPanicError::Second),
+                Err(payload) => {
+                    let message = panic_message(&*payload);
+                    error!("panic when handling request {} {}: {}", method, uri, message);
+                    Err(CatchPanicError::First(Panicked {
+                        message,
+                        body: self.body.clone(),
+                    }))
+                }
+            }
+        }
+    }
+}
+
+impl<'r, S, C, B, Res, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for CatchPanicService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, B>, Response = Res, Error = Err, Ready = Rdy>,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for Panicked {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = match self.body {
+            Some((content_type, body)) => {
+                let mut res = req.into_response(body);
+                res.headers_mut().insert(CONTENT_TYPE, content_type);
+                res
+            }
+            None => req.into_response(Bytes::new()),
+        };
+        *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        async { res }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&'static str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        String::from("Box<dyn Any>")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::{handler_service, request::RequestRef},
+        http::Method,
+        test::TestClient,
+        App,
+    };
+
+    use super::*;
+
+    async fn handler(RequestRef(req): RequestRef<'_>) -> &'static str {
+        if req.method() == Method::POST {
+            panic!("post is not allowed");
+        }
+        "hello"
+    }
+
+    #[test]
+    fn catch() {
+        let client = TestClient::new(
+            App::new()
+                .at("/", handler_service(handler))
+                .enclosed(CatchPanic::new().body("text/plain; charset=utf-8", "oops"))
+                .finish(),
+        )
+        .now_or_panic();
+
+        let res = client.post("/").send().now_or_panic();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        res.assert_header(CONTENT_TYPE, "text/plain; charset=utf-8");
+        assert_eq!(res.string().now_or_panic(), "oops");
+
+        // service is still usable after panic.
+        let res = client.get("/").send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string().now_or_panic(), "hello");
+    }
+
+    #[test]
+    fn message() {
+        assert_eq!(panic_message(&"static"), "static");
+        assert_eq!(panic_message(&String::from("owned")), "owned");
+        assert_eq!(panic_message(&1usize), "Box<dyn Any>");
+    }
+}
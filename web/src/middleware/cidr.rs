@@ -0,0 +1,288 @@
+use std::{
+    error, fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+/// An IP network in CIDR notation. e.g. `10.0.0.0/8`, `fd00::/8`.
+///
+/// A single address without prefix length is treated as network of itself.
+/// IPv4-mapped IPv6 addresses are matched as IPv4 addresses and IPv4-mapped network e.g. `::ffff:0:0/96`
+/// is treated as IPv4 network.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Construct a new network from address and prefix length. Host bits of address are zeroed.
+    pub fn new(addr: IpAddr, mut prefix: u8) -> Result<Self, CidrParseError> {
+        // IPv4-mapped network is stored as IPv4 network when it's inside the mapped range.
+        let addr = match canonical(addr) {
+            IpAddr::V4(ip) if addr.is_ipv6() && prefix >= 96 => {
+                prefix -= 96;
+                IpAddr::V4(ip)
+            }
+            IpAddr::V4(_) if addr.is_ipv6() => addr,
+            ip => ip,
+        };
+        let width = width(&addr);
+
+        if prefix > width {
+            return Err(CidrParseError(()));
+        }
+
+        let addr = match addr {
+            IpAddr::V4(ip) => IpAddr::V4(Ipv4Addr::from(u32::from(ip) & (mask(prefix, width) as u32))),
+            IpAddr::V6(ip) => IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask(prefix, width))),
+        };
+
+        Ok(Self { addr, prefix })
+    }
+
+    /// Network address.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Prefix length of network.
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// Check if given address is inside network.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, canonical(ip)) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => (u32::from(ip) & mask(self.prefix, 32) as u32) == u32::from(net),
+            (IpAddr::V4(_), _) => false,
+            (IpAddr::V6(net), _) => (u128::from(ipv6(ip)) & mask(self.prefix, 128)) == u128::from(net),
+        }
+    }
+}
+
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => match v6.octets() {
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, a, b, c, d] => IpAddr::V4(Ipv4Addr::new(a, b, c, d)),
+            _ => IpAddr::V6(v6),
+        },
+        ip => ip,
+    }
+}
+
+fn ipv6(ip: IpAddr) -> Ipv6Addr {
+    match ip {
+        IpAddr::V4(v4) => v4.to_ipv6_mapped(),
+        IpAddr::V6(v6) => v6,
+    }
+}
+
+fn width(ip: &IpAddr) -> u8 {
+    match ip {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn mask(prefix: u8, width: u8) -> u128 {
+    match prefix {
+        0 => 0,
+        prefix => (u128::MAX << (128 - prefix)) >> (128 - width),
+    }
+}
+
+// a binary prefix trie of networks. ipv4 and ipv6 networks are stored in separate trees.
+#[derive(Debug, Default)]
+pub(crate) struct CidrTrie {
+    v4: Node,
+    v6: Node,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    terminal: bool,
+    children: [Option<Box<Node>>; 2],
+}
+
+impl CidrTrie {
+    pub(crate) fn insert(&mut self, cidr: Cidr) {
+        let (mut node, bits) = match cidr.addr {
+            IpAddr::V4(ip) => (&mut self.v4, u128::from(u32::from(ip)) << 96),
+            IpAddr::V6(ip) => (&mut self.v6, u128::from(ip)),
+        };
+
+        for i in 0..cidr.prefix {
+            if node.terminal {
+                // a wider network already covers this one.
+                return;
+            }
+            node = node.children[bit(bits, i)].get_or_insert_with(Default::default);
+        }
+
+        node.terminal = true;
+        node.children = [None, None];
+    }
+
+    pub(crate) fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 address can be inside IPv6 network covering the mapped range.
+        let v4 = match canonical(ip) {
+            IpAddr::V4(v4) => Node::contains(&self.v4, u128::from(u32::from(v4)) << 96, 32),
+            IpAddr::V6(_) => false,
+        };
+        v4 || Node::contains(&self.v6, u128::from(ipv6(ip)), 128)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        let empty = |node: &Node| !node.terminal && node.children.iter().all(Option::is_none);
+        empty(&self.v4) && empty(&self.v6)
+    }
+}
+
+impl Node {
+    fn contains(mut node: &Node, bits: u128, width: u8) -> bool {
+        for i in 0..width {
+            if node.terminal {
+                return true;
+            }
+            match node.children[bit(bits, i)] {
+                Some(ref child) => node = child,
+                None => return false,
+            }
+        }
+
+        node.terminal
+    }
+}
+
+impl FromIterator<Cidr> for CidrTrie {
+    fn from_iter<I: IntoIterator<Item = Cidr>>(iter: I) -> Self {
+        let mut trie = Self::default();
+        for cidr in iter {
+            trie.insert(cidr);
+        }
+        trie
+    }
+}
+
+fn bit(bits: u128, i: u8) -> usize {
+    ((bits >> (127 - i)) & 1) as usize
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = CidrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((addr, prefix)) => {
+                let addr = addr.parse().map_err(|_| CidrParseError(()))?;
+                let prefix = prefix.parse().map_err(|_| CidrParseError(()))?;
+                Self::new(addr, prefix)
+            }
+            None => {
+                let addr = canonical(s.parse().map_err(|_| CidrParseError(()))?);
+                Self::new(addr, width(&addr))
+            }
+        }
+    }
+}
+
+/// Error for invalid CIDR notation.
+#[derive(Debug)]
+pub struct CidrParseError(());
+
+impl fmt::Display for CidrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid CIDR notation")
+    }
+}
+
+impl error::Error for CidrParseError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_contains() {
+        let cidr = "10.1.2.3/8".parse::<Cidr>().unwrap();
+        assert_eq!(cidr.to_string(), "10.0.0.0/8");
+        assert!(cidr.contains("10.255.0.1".parse().unwrap()));
+        assert!(cidr.contains("::ffff:10.0.0.1".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+        assert!(!cidr.contains("::1".parse().unwrap()));
+
+        let cidr = "fd00::/8".parse::<Cidr>().unwrap();
+        assert!(cidr.contains("fd12:3456::1".parse().unwrap()));
+        assert!(!cidr.contains("fe80::1".parse().unwrap()));
+
+        let cidr = "127.0.0.1".parse::<Cidr>().unwrap();
+        assert_eq!(cidr.prefix(), 32);
+        assert!(cidr.contains("127.0.0.1".parse().unwrap()));
+        assert!(!cidr.contains("127.0.0.2".parse().unwrap()));
+
+        let cidr = "0.0.0.0/0".parse::<Cidr>().unwrap();
+        assert!(cidr.contains("1.2.3.4".parse().unwrap()));
+
+        let cidr = "::ffff:0:0/96".parse::<Cidr>().unwrap();
+        assert_eq!(cidr.to_string(), "0.0.0.0/0");
+        assert!(cidr.contains("1.2.3.4".parse().unwrap()));
+        assert!(!cidr.contains("::1".parse().unwrap()));
+
+        let cidr = "::ffff:10.1.2.3/104".parse::<Cidr>().unwrap();
+        assert_eq!(cidr.to_string(), "10.0.0.0/8");
+        assert!(cidr.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+        // ipv6 network covering the mapped range.
+        let cidr = "::/80".parse::<Cidr>().unwrap();
+        assert!(cidr.contains("1.2.3.4".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("::ffff:0:0/129".parse::<Cidr>().is_err());
+        assert!("10.0.0/8".parse::<Cidr>().is_err());
+        assert!("::/129".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn trie() {
+        let mut trie = CidrTrie::default();
+        assert!(trie.is_empty());
+        assert!(!trie.contains("10.0.0.1".parse().unwrap()));
+
+        trie.insert("10.1.0.0/16".parse().unwrap());
+        trie.insert("192.168.1.7".parse().unwrap());
+        trie.insert("fd00::/8".parse().unwrap());
+        assert!(!trie.is_empty());
+
+        assert!(trie.contains("10.1.255.255".parse().unwrap()));
+        assert!(!trie.contains("10.2.0.0".parse().unwrap()));
+        assert!(trie.contains("192.168.1.7".parse().unwrap()));
+        assert!(trie.contains("::ffff:192.168.1.7".parse().unwrap()));
+        assert!(!trie.contains("192.168.1.8".parse().unwrap()));
+        assert!(trie.contains("fdff::1".parse().unwrap()));
+        assert!(!trie.contains("fe00::1".parse().unwrap()));
+
+        // wider network replaces narrower ones.
+        trie.insert("10.0.0.0/8".parse().unwrap());
+        assert!(trie.contains("10.2.0.0".parse().unwrap()));
+
+        let trie = ["0.0.0.0/0".parse().unwrap()].into_iter().collect::<CidrTrie>();
+        assert!(trie.contains("1.2.3.4".parse().unwrap()));
+        assert!(!trie.contains("::1".parse().unwrap()));
+
+        let trie = ["::ffff:0:0/96".parse().unwrap()].into_iter().collect::<CidrTrie>();
+        assert!(trie.contains("1.2.3.4".parse().unwrap()));
+        assert!(trie.contains("::ffff:1.2.3.4".parse().unwrap()));
+        assert!(!trie.contains("::1".parse().unwrap()));
+
+        let trie = ["::/80".parse().unwrap()].into_iter().collect::<CidrTrie>();
+        assert!(trie.contains("1.2.3.4".parse().unwrap()));
+        assert!(trie.contains("::1".parse().unwrap()));
+    }
+}
@@ -1,19 +1,209 @@
 use std::{convert::Infallible, future::Future};
 
-use http_encoding::{encoder, Coder, ContentEncoding};
+use http_encoding::{encoder_with_level, Coder};
+use xitca_http::body::BodySize;
 
 use crate::{
     dev::service::{ready::ReadyService, BuildService, Service},
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderMap,
+    },
     request::WebRequest,
     response::WebResponse,
     stream::WebStream,
 };
 
+pub use http_encoding::ContentEncoding;
+
 /// A compress middleware look into [WebRequest]'s `Accept-Encoding` header and
 /// apply according compression to [WebResponse]'s body according to enabled compress feature.
 /// `compress-x` feature must be enabled for this middleware to function correctly.
-#[derive(Clone)]
-pub struct Compress;
+///
+/// By default every response is compressed. [Compress::with_config] can be used to apply a
+/// [CompressConfig] policy.
+#[derive(Clone, Default)]
+pub struct Compress {
+    config: CompressConfig,
+}
+
+/// Compress middleware with default config.
+///
+/// Compress used to be a unit struct and this constant keeps `.enclosed(Compress)` working.
+#[allow(non_upper_case_globals)]
+pub const Compress: Compress = Compress::new();
+
+impl Compress {
+    /// Construct a compress middleware with default config.
+    pub const fn new() -> Self {
+        Self {
+            config: CompressConfig::new(),
+        }
+    }
+
+    /// Construct a compress middleware with given config.
+    pub fn with_config(config: CompressConfig) -> Self {
+        Self { config }
+    }
+}
+
+/// Policy of [Compress] middleware.
+///
+/// # Examples
+/// ```rust
+/// use xitca_web::{
+///     handler::handler_service,
+///     middleware::compress::{Compress, CompressConfig, ContentEncoding},
+///     App, HttpServer,
+/// };
+///
+/// # fn main() -> std::io::Result<()> {
+/// HttpServer::new(|| {
+///     let config = CompressConfig::new()
+///         .min_size(1024)
+///         .deny_content_type("image/*")
+///         .deny_content_type("application/zip")
+///         .level(ContentEncoding::Gzip, 6)
+///         .preference([ContentEncoding::Br, ContentEncoding::Gzip]);
+///
+///     App::new()
+///         .at("/", handler_service(|| async { "hello,world!" }))
+///         .enclosed(Compress::with_config(config))
+///         .finish()
+/// })
+/// .bind("127.0.0.1:8080")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct CompressConfig {
+    min_size: usize,
+    allow: Vec<String>,
+    deny: Vec<String>,
+    levels: Vec<(ContentEncoding, u32)>,
+    preference: Vec<ContentEncoding>,
+    sync_flush: bool,
+}
+
+impl CompressConfig {
+    /// Construct a new config that compress every response with default level of algorithms.
+    pub const fn new() -> Self {
+        Self {
+            min_size: 0,
+            allow: Vec::new(),
+            deny: Vec::new(),
+            levels: Vec::new(),
+            preference: Vec::new(),
+            sync_flush: false,
+        }
+    }
+
+    /// Skip compression for response body smaller than given bytes.
+    ///
+    /// Body size is determined by `Content-Length` header or size hint of response body. Response
+    /// body with unknown size is always compressed.
+    pub fn min_size(mut self, size: usize) -> Self {
+        self.min_size = size;
+        self
+    }
+
+    /// Only compress response with given content type. Can be called multiple times to allow
+    /// more content types.
+    ///
+    /// Content type is matched against media type of `Content-Type` header case-insensitively.
+    /// A type ends with `/*` matches all sub types. e.g. `text/*`.
+    ///
+    /// When allow list is not empty response without `Content-Type` header is not compressed.
+    pub fn allow_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.allow.push(content_type.into());
+        self
+    }
+
+    /// Skip compression for response with given content type. Deny list has precedence over allow
+    /// list. See [CompressConfig::allow_content_type] for matching rule.
+    pub fn deny_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.deny.push(content_type.into());
+        self
+    }
+
+    /// Set compression level of given encoding. Level is clamped to valid range of algorithm:
+    /// - `Br`: 0-11. Default to 3.
+    /// - `Deflate` and `Gzip`: 0-9. Default to 1.
+    /// - `Zstd`: 1-22. Default to 3.
+    pub fn level(mut self, encoding: ContentEncoding, level: u32) -> Self {
+        self.levels.retain(|(e, _)| *e != encoding);
+        self.levels.push((encoding, level));
+        self
+    }
+
+    /// Set server preference order of encodings. It's used to select encoding when multiple ones
+    /// share the highest q-value in `Accept-Encoding` header.
+    pub fn preference(mut self, encodings: impl IntoIterator<Item = ContentEncoding>) -> Self {
+        self.preference = encodings.into_iter().collect();
+        self
+    }
+
+    /// Flush compressed output after every chunk of response body.
+    ///
+    /// By default compressor buffers output until enough input is collected. For streaming response
+    /// like Server-Sent Events it can delay chunks indefinitely. With sync flush every chunk is sent
+    /// as soon as it's compressed at the cost of compression ratio.
+    ///
+    /// Regardless of this option an empty chunk of response body always flushes compressor.
+    pub fn sync_flush(mut self) -> Self {
+        self.sync_flush = true;
+        self
+    }
+
+    fn level_of(&self, encoding: ContentEncoding) -> Option<u32> {
+        self.levels
+            .iter()
+            .find(|(e, _)| *e == encoding)
+            .map(|(_, level)| *level)
+    }
+
+    fn should_compress<B: WebStream>(&self, headers: &HeaderMap, body: &B) -> bool {
+        let size = headers
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .map(BodySize::Sized)
+            .unwrap_or_else(|| BodySize::from_stream(body));
+
+        match size {
+            BodySize::None => return false,
+            BodySize::Sized(size) if size < self.min_size => return false,
+            _ => {}
+        }
+
+        if self.allow.is_empty() && self.deny.is_empty() {
+            return true;
+        }
+
+        let media_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.split(';').next().unwrap().trim());
+
+        match media_type {
+            Some(media_type) => {
+                !self.deny.iter().any(|t| content_type_match(t, media_type))
+                    && (self.allow.is_empty() || self.allow.iter().any(|t| content_type_match(t, media_type)))
+            }
+            None => self.allow.is_empty(),
+        }
+    }
+}
+
+fn content_type_match(pattern: &str, media_type: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(ty) => media_type
+            .split_once('/')
+            .map(|(t, _)| t.eq_ignore_ascii_case(ty))
+            .unwrap_or(false),
+        None => pattern.eq_ignore_ascii_case(media_type),
+    }
+}
 
 impl<S> BuildService<S> for Compress {
     type Service = CompressService<S>;
@@ -21,12 +211,14 @@ impl<S> BuildService<S> for Compress {
     type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
 
     fn build(&self, service: S) -> Self::Future {
-        async { Ok(CompressService { service }) }
+        let config = self.config.clone();
+        async { Ok(CompressService { service, config }) }
     }
 }
 
 pub struct CompressService<S> {
     service: S,
+    config: CompressConfig,
 }
 
 impl<'r, S, C, ReqB, ResB, Err> Service<WebRequest<'r, C, ReqB>> for CompressService<S>
@@ -42,9 +234,19 @@ where
 
     fn call(&self, req: WebRequest<'r, C, ReqB>) -> Self::Future<'_> {
         async move {
-            let encoding = ContentEncoding::from_headers(req.req().headers());
+            let mut encoding =
+                ContentEncoding::from_headers_with_preference(req.req().headers(), &self.config.preference);
             let res = self.service.call(req).await?;
-            Ok(encoder(res, encoding))
+            if !self.config.should_compress(res.headers(), res.body()) {
+                encoding = ContentEncoding::NoOp;
+            }
+            let level = self.config.level_of(encoding);
+            let res = encoder_with_level(res, encoding, level);
+            if self.config.sync_flush {
+                Ok(res.map(Coder::sync_flush))
+            } else {
+                Ok(res)
+            }
         }
     }
 }
@@ -64,3 +266,86 @@ where
         async move { self.service.ready().await }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, http::StatusCode, test::TestClient, App};
+
+    #[cfg(feature = "compress-gz")]
+    use crate::{
+        dev::bytes::Bytes,
+        handler::request::RequestRef,
+        http::{header::CONTENT_ENCODING, HeaderValue},
+        response::ResponseBody,
+    };
+
+    use super::*;
+
+    #[cfg(feature = "compress-gz")]
+    async fn handler(RequestRef(req): RequestRef<'_>) -> WebResponse {
+        let (body, content_type) = match req.uri().path() {
+            "/small" => (Bytes::from_static(b"small"), "text/plain"),
+            "/image" => (Bytes::from(vec![0; 1024]), "image/png"),
+            _ => (Bytes::from(vec![b'a'; 1024]), "text/plain; charset=utf-8"),
+        };
+        let mut res = WebResponse::new(ResponseBody::from(body));
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        res
+    }
+
+    #[test]
+    fn unit() {
+        let client = TestClient::new(
+            App::new()
+                .at("/", handler_service(|| async { "hello" }))
+                .enclosed(Compress)
+                .finish(),
+        )
+        .now_or_panic();
+
+        let res = client.get("/").send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string().now_or_panic(), "hello");
+    }
+
+    #[test]
+    fn content_type() {
+        assert!(content_type_match("image/*", "image/png"));
+        assert!(content_type_match("IMAGE/*", "image/png"));
+        assert!(!content_type_match("image/*", "text/plain"));
+        assert!(content_type_match("application/zip", "application/zip"));
+        assert!(!content_type_match("application/zip", "application/json"));
+    }
+
+    #[cfg(feature = "compress-gz")]
+    #[test]
+    fn policy() {
+        let config = CompressConfig::new()
+            .min_size(64)
+            .deny_content_type("image/*")
+            .preference([ContentEncoding::Gzip]);
+
+        let client = TestClient::new(
+            App::new()
+                .at("/*path", handler_service(handler))
+                .enclosed(Compress::with_config(config))
+                .finish(),
+        )
+        .now_or_panic();
+
+        let res = client
+            .get("/text")
+            .header("accept-encoding", "br, gzip")
+            .send()
+            .now_or_panic();
+        res.assert_header("content-encoding", "gzip");
+
+        for path in ["/small", "/image"] {
+            let res = client.get(path).header("accept-encoding", "gzip").send().now_or_panic();
+            assert!(!res.headers().contains_key(CONTENT_ENCODING));
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use std::{convert::Infallible, future::Future, time::SystemTime};
+
+use crate::{
+    dev::service::{ready::ReadyService, BuildService, Service},
+    http::{
+        header::{
+            CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_UNMODIFIED_SINCE,
+            LAST_MODIFIED,
+        },
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    request::WebRequest,
+    response::{ResponseBody, WebResponse},
+};
+
+/// A middleware evaluate conditional request headers against response's validators.
+///
+/// For successful response to `GET` and `HEAD` request:
+/// - A strong `ETag` is computed from buffered response body when handler does not provide one.
+///   Streaming response body is not buffered and only handler provided `ETag` is used.
+/// - `If-Match`, `If-Unmodified-Since`, `If-None-Match` and `If-Modified-Since` headers are evaluated
+///   against `ETag` and `Last-Modified` headers of response in the order specified by RFC 7232.
+/// - Response body is dropped and status is set to `304 Not Modified` or `412 Precondition Failed`
+///   according to evaluation result.
+///
+/// Request with other method is passed to inner service untouched. Evaluating preconditions of unsafe
+/// method requires current state of resource which only the handler knows about.
+#[derive(Clone, Copy, Default)]
+pub struct ConditionalGet;
+
+impl<S> BuildService<S> for ConditionalGet {
+    type Service = ConditionalGetService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        async { Ok(ConditionalGetService { service }) }
+    }
+}
+
+pub struct ConditionalGetService<S> {
+    service: S,
+}
+
+impl<'r, S, C, B, ResB, Err> Service<WebRequest<'r, C, B>> for ConditionalGetService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = WebResponse<ResponseBody<ResB>>, Error = Err>,
+{
+    type Response = WebResponse<ResponseBody<ResB>>;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let method = req.req().method();
+            if method != Method::GET && method != Method::HEAD {
+                return self.service.call(req).await;
+            }
+
+            let preconditions = Preconditions::from_headers(req.req().headers());
+
+            let mut res = self.service.call(req).await?;
+
+            if !res.status().is_success() {
+                return Ok(res);
+            }
+
+            insert_etag(&mut res);
+
+            if let Some(status) = preconditions.evaluate(res.headers()) {
+                *res.status_mut() = status;
+                *res.body_mut() = ResponseBody::None;
+                let headers = res.headers_mut();
+                headers.remove(CONTENT_LENGTH);
+                headers.remove(CONTENT_TYPE);
+            }
+
+            Ok(res)
+        }
+    }
+}
+
+impl<'r, S, C, B, ResB, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for ConditionalGetService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<
+        WebRequest<'rs, C, B>,
+        Response = WebResponse<ResponseBody<ResB>>,
+        Error = Err,
+        Ready = Rdy,
+    >,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+// conditional headers of request.
+struct Preconditions {
+    if_match: Option<HeaderValue>,
+    if_none_match: Option<HeaderValue>,
+    if_modified_since: Option<SystemTime>,
+    if_unmodified_since: Option<SystemTime>,
+}
+
+impl Preconditions {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            if_match: headers.get(IF_MATCH).cloned(),
+            if_none_match: headers.get(IF_NONE_MATCH).cloned(),
+            if_modified_since: headers.get(IF_MODIFIED_SINCE).and_then(http_date),
+            if_unmodified_since: headers.get(IF_UNMODIFIED_SINCE).and_then(http_date),
+        }
+    }
+
+    // evaluate If-Match and If-Unmodified-Since against response headers. RFC 7232 section 6 step 1 and 2.
+    fn evaluate_state(&self, headers: &HeaderMap) -> bool {
+        let etag = headers.get(ETAG).and_then(|v| v.to_str().ok());
+
+        match (self.if_match.as_ref(), self.if_unmodified_since) {
+            (Some(if_match), _) => etag_match(if_match, etag, true),
+            // resource without modification date ignores If-Unmodified-Since.
+            (None, Some(since)) => headers
+                .get(LAST_MODIFIED)
+                .and_then(http_date)
+                .map(|modified| modified <= since)
+                .unwrap_or(true),
+            _ => true,
+        }
+    }
+
+    // evaluate preconditions against response headers. RFC 7232 section 6.
+    fn evaluate(&self, headers: &HeaderMap) -> Option<StatusCode> {
+        if !self.evaluate_state(headers) {
+            return Some(StatusCode::PRECONDITION_FAILED);
+        }
+
+        let etag = headers.get(ETAG).and_then(|v| v.to_str().ok());
+        let last_modified = headers.get(LAST_MODIFIED).and_then(http_date);
+
+        match (self.if_none_match.as_ref(), self.if_modified_since) {
+            (Some(if_none_match), _) => etag_match(if_none_match, etag, false).then_some(StatusCode::NOT_MODIFIED),
+            (None, Some(since)) => last_modified
+                .filter(|modified| *modified <= since)
+                .map(|_| StatusCode::NOT_MODIFIED),
+            _ => None,
+        }
+    }
+}
+
+fn http_date(value: &HeaderValue) -> Option<SystemTime> {
+    value.to_str().ok().and_then(|v| httpdate::parse_http_date(v).ok())
+}
+
+// match entity tag against the list of condition header. `*` matches any present entity tag.
+fn etag_match(condition: &HeaderValue, etag: Option<&str>, strong: bool) -> bool {
+    let etag = match etag {
+        Some(etag) => etag,
+        None => return false,
+    };
+
+    let condition = match condition.to_str() {
+        Ok(condition) => condition.trim(),
+        Err(_) => return false,
+    };
+
+    if condition == "*" {
+        return true;
+    }
+
+    let (weak, opaque) = split_weak(etag);
+
+    condition.split(',').map(str::trim).any(|tag| {
+        let (tag_weak, tag_opaque) = split_weak(tag);
+        tag_opaque == opaque && !(strong && (weak || tag_weak))
+    })
+}
+
+fn split_weak(tag: &str) -> (bool, &str) {
+    match tag.strip_prefix("W/") {
+        Some(tag) => (true, tag),
+        None => (false, tag),
+    }
+}
+
+// add strong entity tag from buffered response body when handler does not provide one.
+fn insert_etag<B>(res: &mut WebResponse<ResponseBody<B>>) {
+    if !res.headers().contains_key(ETAG) {
+        if let ResponseBody::Bytes { ref bytes } = *res.body() {
+            let etag = strong_etag(bytes);
+            res.headers_mut().insert(ETAG, etag);
+        }
+    }
+}
+
+// entity tag must stay the same across processes and builds. 64 bit FNV-1a hash is used for it.
+fn strong_etag(bytes: &[u8]) -> HeaderValue {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = bytes
+        .iter()
+        .fold(OFFSET, |hash, b| (hash ^ u64::from(*b)).wrapping_mul(PRIME));
+
+    let etag = format!("\"{:x}-{:x}\"", bytes.len(), hash);
+    HeaderValue::try_from(etag).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, rc::Rc, time::Duration};
+
+    use httpdate::fmt_http_date;
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, route::get, test::TestClient, App};
+
+    use super::*;
+
+    #[test]
+    fn etag() {
+        let etag = HeaderValue::from_static("\"a\", W/\"b\"");
+        assert!(etag_match(&etag, Some("\"a\""), true));
+        assert!(etag_match(&etag, Some("W/\"a\""), false));
+        assert!(!etag_match(&etag, Some("W/\"a\""), true));
+        assert!(etag_match(&etag, Some("\"b\""), false));
+        assert!(!etag_match(&etag, Some("\"b\""), true));
+        assert!(!etag_match(&etag, Some("\"c\""), false));
+        assert!(!etag_match(&etag, None, false));
+        assert!(etag_match(&HeaderValue::from_static("*"), Some("\"c\""), true));
+    }
+
+    #[test]
+    fn stable_etag() {
+        assert_eq!(strong_etag(b""), "\"0-cbf29ce484222325\"");
+        assert_eq!(strong_etag(b"hello"), "\"5-a430d84680aabd0b\"");
+    }
+
+    #[test]
+    fn conditional() {
+        let client = TestClient::new(
+            App::new()
+                .at("/", handler_service(|| async { "hello" }))
+                .enclosed(ConditionalGet)
+                .finish(),
+        )
+        .now_or_panic();
+
+        let res = client.get("/").send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+        let etag = res.headers().get(ETAG).unwrap().to_str().unwrap().to_owned();
+        assert_eq!(res.string().now_or_panic(), "hello");
+
+        let res = client
+            .get("/")
+            .header("if-none-match", format!("W/\"foo\", {etag}"))
+            .send()
+            .now_or_panic();
+        res.assert_status(StatusCode::NOT_MODIFIED);
+        res.assert_header("etag", &etag);
+        assert_eq!(res.string().now_or_panic(), "");
+
+        let res = client.get("/").header("if-match", "\"foo\"").send().now_or_panic();
+        res.assert_status(StatusCode::PRECONDITION_FAILED);
+        assert_eq!(res.string().now_or_panic(), "");
+
+        let res = client.get("/").header("if-match", etag).send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+
+    }
+
+    #[test]
+    fn unsafe_method() {
+        let puts = Rc::new(Cell::new(0));
+        let puts2 = puts.clone();
+
+        let client = TestClient::new(
+            App::new()
+                .at(
+                    "/",
+                    get(handler_service(|| async { "hello" })).put(handler_service(move || {
+                        let puts = puts2.clone();
+                        async move {
+                            puts.set(puts.get() + 1);
+                            "updated"
+                        }
+                    })),
+                )
+                .enclosed(ConditionalGet)
+                .finish(),
+        )
+        .now_or_panic();
+
+        let etag = client
+            .get("/")
+            .send()
+            .now_or_panic()
+            .headers()
+            .get(ETAG)
+            .unwrap()
+            .clone();
+
+        // unsafe method is passed through regardless of preconditions.
+        let res = client.put("/").header("if-match", "\"foo\"").send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+        assert!(res.headers().get(ETAG).is_none());
+        assert_eq!(res.string().now_or_panic(), "updated");
+        assert_eq!(puts.get(), 1);
+
+        let since = fmt_http_date(SystemTime::UNIX_EPOCH);
+        client
+            .put("/")
+            .header("if-unmodified-since", since.as_str())
+            .send()
+            .now_or_panic()
+            .assert_status(StatusCode::OK);
+        assert_eq!(puts.get(), 2);
+
+        client
+            .put("/")
+            .header("if-none-match", etag)
+            .send()
+            .now_or_panic()
+            .assert_status(StatusCode::OK);
+        assert_eq!(puts.get(), 3);
+    }
+
+    const MODIFIED: Duration = Duration::from_secs(1_000_000_000);
+
+    async fn modified() -> WebResponse {
+        let mut res = WebResponse::new(ResponseBody::from("hello"));
+        let modified = fmt_http_date(SystemTime::UNIX_EPOCH + MODIFIED);
+        res.headers_mut()
+            .insert(LAST_MODIFIED, HeaderValue::try_from(modified).unwrap());
+        res
+    }
+
+    #[test]
+    fn last_modified() {
+        let client = TestClient::new(
+            App::new()
+                .at("/", handler_service(modified))
+                .enclosed(ConditionalGet)
+                .finish(),
+        )
+        .now_or_panic();
+
+        let at = fmt_http_date(SystemTime::UNIX_EPOCH + MODIFIED);
+        let before = fmt_http_date(SystemTime::UNIX_EPOCH + MODIFIED - Duration::from_secs(1));
+
+        client
+            .get("/")
+            .header("if-modified-since", at.as_str())
+            .send()
+            .now_or_panic()
+            .assert_status(StatusCode::NOT_MODIFIED);
+
+        client
+            .get("/")
+            .header("if-modified-since", before.as_str())
+            .send()
+            .now_or_panic()
+            .assert_status(StatusCode::OK);
+
+        client
+            .get("/")
+            .header("if-unmodified-since", before.as_str())
+            .send()
+            .now_or_panic()
+            .assert_status(StatusCode::PRECONDITION_FAILED);
+
+        client
+            .get("/")
+            .header("if-unmodified-since", at.as_str())
+            .send()
+            .now_or_panic()
+            .assert_status(StatusCode::OK);
+
+        // If-None-Match takes precedence over If-Modified-Since.
+        client
+            .get("/")
+            .header("if-none-match", "\"foo\"")
+            .header("if-modified-since", at.as_str())
+            .send()
+            .now_or_panic()
+            .assert_status(StatusCode::OK);
+    }
+}
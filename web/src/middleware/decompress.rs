@@ -1,9 +1,24 @@
-use std::{cell::RefCell, convert::Infallible, future::Future};
+use std::{
+    cell::{Cell, RefCell},
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
 
-use http_encoding::{error::EncodingError, Coder};
+use futures_core::stream::Stream;
+use http_encoding::{
+    error::{CoderError, EncodingError},
+    Coder, DecodeLimit,
+};
+use pin_project_lite::pin_project;
 
 use crate::{
-    dev::service::{pipeline::PipelineE, ready::ReadyService, BuildService, Service},
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, BuildService, Service},
+    },
     handler::Responder,
     http::{const_header_value::TEXT_UTF8, header::CONTENT_TYPE, StatusCode},
     request::WebRequest,
@@ -14,8 +29,42 @@ use crate::{
 /// A decompress middleware look into [WebRequest]'s `Content-Encoding` header and
 /// apply according decompression to it according to enabled compress feature.
 /// `compress-x` feature must be enabled for this middleware to function correctly.
-#[derive(Clone)]
-pub struct Decompress;
+///
+/// By default decompressed request body is limited to 64MiB. [Decompress::max_size] and [Decompress::max_ratio]
+/// can be used to tighten the protection against decompression bomb. When decompressed body exceeds the limit the body
+/// stream yields [CoderError::LimitExceeded] error. In that case when the service passed to Decompress
+/// returns an error the request is responded with `413 Payload Too Large`.
+#[derive(Clone, Copy, Default)]
+pub struct Decompress {
+    limit: DecodeLimit,
+}
+
+/// Decompress middleware with default limit.
+///
+/// Keeps unit style `.enclosed(Decompress)` working.
+#[allow(non_upper_case_globals)]
+pub const Decompress: Decompress = Decompress::new();
+
+impl Decompress {
+    /// Construct a decompress middleware with default limit of 64MiB decompressed request body.
+    pub const fn new() -> Self {
+        Self {
+            limit: DecodeLimit::new(),
+        }
+    }
+
+    /// Set maximum size of decompressed request body in bytes.
+    pub const fn max_size(mut self, size: usize) -> Self {
+        self.limit = self.limit.max_size(size);
+        self
+    }
+
+    /// Set maximum ratio of decompressed request body size to compressed request body size.
+    pub const fn max_ratio(mut self, ratio: usize) -> Self {
+        self.limit = self.limit.max_ratio(ratio);
+        self
+    }
+}
 
 impl<S> BuildService<S> for Decompress {
     type Service = DecompressService<S>;
@@ -23,12 +72,14 @@ impl<S> BuildService<S> for Decompress {
     type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
 
     fn build(&self, service: S) -> Self::Future {
-        async { Ok(DecompressService { service }) }
+        let limit = self.limit;
+        async move { Ok(DecompressService { service, limit }) }
     }
 }
 
 pub struct DecompressService<S> {
     service: S,
+    limit: DecodeLimit,
 }
 
 pub type DecompressServiceError<E> = PipelineE<EncodingError, E>;
@@ -37,7 +88,7 @@ impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for DecompressService<
 where
     C: 'static,
     B: WebStream + Default + 'static,
-    S: for<'rs> Service<WebRequest<'rs, C, Coder<B>>, Response = Res, Error = Err>,
+    S: for<'rs> Service<WebRequest<'rs, C, DecompressBody<B>>, Response = Res, Error = Err>,
 {
     type Response = Res;
     type Error = DecompressServiceError<Err>;
@@ -47,13 +98,25 @@ where
         async move {
             let (mut http_req, body) = req.take_request().replace_body(());
 
-            let decoder = http_encoding::try_decoder(&*http_req, body).map_err(DecompressServiceError::First)?;
+            let decoder = http_encoding::try_decoder_with_limit(&*http_req, body, self.limit)
+                .map_err(DecompressServiceError::First)?;
+
+            let exceeded = Rc::new(Cell::new(false));
 
-            let mut body = RefCell::new(decoder);
+            let mut body = RefCell::new(DecompressBody {
+                body: decoder,
+                exceeded: exceeded.clone(),
+            });
 
             let req = WebRequest::new(&mut http_req, &mut body, req.ctx);
 
-            self.service.call(req).await.map_err(DecompressServiceError::Second)
+            self.service.call(req).await.map_err(|e| {
+                if exceeded.get() {
+                    DecompressServiceError::First(EncodingError::LimitExceeded)
+                } else {
+                    DecompressServiceError::Second(e)
+                }
+            })
         }
     }
 }
@@ -62,7 +125,7 @@ impl<'r, S, C, B, Res, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for Decompre
 where
     C: 'static,
     B: WebStream + Default + 'static,
-    S: for<'rs> ReadyService<WebRequest<'rs, C, Coder<B>>, Response = Res, Error = Err, Ready = Rdy>,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, DecompressBody<B>>, Response = Res, Error = Err, Ready = Rdy>,
 {
     type Ready = Rdy;
     type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
@@ -78,13 +141,56 @@ impl<'r, C, B> Responder<WebRequest<'r, C, B>> for EncodingError {
     type Future = impl Future<Output = Self::Output>;
 
     fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let status = match self {
+            Self::LimitExceeded => StatusCode::PAYLOAD_TOO_LARGE,
+            _ => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        };
         let mut res = req.into_response(format!("{self}"));
         res.headers_mut().insert(CONTENT_TYPE, TEXT_UTF8);
-        *res.status_mut() = StatusCode::UNSUPPORTED_MEDIA_TYPE;
+        *res.status_mut() = status;
         async { res }
     }
 }
 
+pin_project! {
+    /// Request body type of [DecompressService].
+    pub struct DecompressBody<B> {
+        #[pin]
+        body: Coder<B>,
+        exceeded: Rc<Cell<bool>>,
+    }
+}
+
+impl<B: Default> Default for DecompressBody<B> {
+    fn default() -> Self {
+        Self {
+            body: Coder::default(),
+            exceeded: Rc::new(Cell::new(false)),
+        }
+    }
+}
+
+impl<B> Stream for DecompressBody<B>
+where
+    B: WebStream,
+{
+    type Item = Result<Bytes, CoderError<B::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = futures_core::ready!(this.body.poll_next(cx));
+        if let Some(Err(CoderError::LimitExceeded)) = item {
+            this.exceeded.set(true);
+        }
+        Poll::Ready(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.body.size_hint()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use http_encoding::{encoder, ContentEncoding};
@@ -145,18 +251,22 @@ mod test {
             .unwrap();
     }
 
-    #[cfg(any(feature = "compress-br", feature = "compress-gz", feature = "compress-de"))]
-    #[test]
-    fn compressed() {
-        // a hack to generate a compressed client request from server response.
-        let res = WebResponse::<ResponseBody>::new(ResponseBody::bytes(Bytes::from_static(Q)));
+    // a hack to generate a compressed client request from server response.
+    #[cfg(any(
+        feature = "compress-br",
+        feature = "compress-gz",
+        feature = "compress-de",
+        feature = "compress-zs"
+    ))]
+    fn compressed_request(body: Bytes) -> Request<Once<Bytes>> {
+        let res = WebResponse::<ResponseBody>::new(ResponseBody::bytes(body));
 
         let encoding = {
-            #[cfg(all(feature = "compress-br", not(any(feature = "compress-gz", feature = "compress-de"))))]
+            #[cfg(feature = "compress-br")]
             {
                 ContentEncoding::Br
             }
-            #[cfg(all(feature = "compress-gz", not(any(feature = "compress-br", feature = "compress-de"))))]
+            #[cfg(all(feature = "compress-gz", not(feature = "compress-br")))]
             {
                 ContentEncoding::Gzip
             }
@@ -164,9 +274,12 @@ mod test {
             {
                 ContentEncoding::Deflate
             }
-            #[cfg(all(feature = "compress-de", feature = "compress-br", feature = "compress-gz"))]
+            #[cfg(all(
+                feature = "compress-zs",
+                not(any(feature = "compress-br", feature = "compress-gz", feature = "compress-de"))
+            ))]
             {
-                ContentEncoding::Br
+                ContentEncoding::Zstd
             }
         };
 
@@ -177,6 +290,18 @@ mod test {
         let mut req = Request::new(Once::new(Bytes::from(body)));
         req.headers_mut()
             .insert(CONTENT_ENCODING, parts.headers.remove(CONTENT_ENCODING).unwrap());
+        req
+    }
+
+    #[cfg(any(
+        feature = "compress-br",
+        feature = "compress-gz",
+        feature = "compress-de",
+        feature = "compress-zs"
+    ))]
+    #[test]
+    fn compressed() {
+        let req = compressed_request(Bytes::from_static(Q));
 
         App::new()
             .at("/", handler_service(handler))
@@ -190,4 +315,33 @@ mod test {
             .ok()
             .unwrap();
     }
+
+    #[cfg(any(
+        feature = "compress-br",
+        feature = "compress-gz",
+        feature = "compress-de",
+        feature = "compress-zs"
+    ))]
+    #[test]
+    fn limit() {
+        async fn len(vec: Vec<u8>) -> String {
+            vec.len().to_string()
+        }
+
+        let service = App::new()
+            .at("/", handler_service(len))
+            .enclosed(Decompress::new().max_size(1024 * 1024).max_ratio(100))
+            .finish()
+            .build(())
+            .now_or_panic()
+            .unwrap();
+
+        let req = compressed_request(Bytes::from(vec![b'a'; 1024]));
+        let res = service.call(req).now_or_panic().ok().unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = compressed_request(Bytes::from(vec![0; 4 * 1024 * 1024]));
+        let res = service.call(req).now_or_panic().ok().unwrap();
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
 }
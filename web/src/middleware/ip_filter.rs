@@ -0,0 +1,312 @@
+use std::{
+    convert::Infallible,
+    fmt,
+    future::Future,
+    net::IpAddr,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, BuildService, Service},
+    },
+    handler::{connection_info::remote_ip, Responder},
+    http::StatusCode,
+    request::WebRequest,
+    response::WebResponse,
+};
+
+use super::cidr::{Cidr, CidrTrie};
+
+/// A middleware filter requests by their remote address with allow and deny lists of networks.
+///
+/// - Request from address inside deny list is rejected.
+/// - When allow list is not empty request from address outside of it is rejected.
+/// - When allow list is not empty request without remote address is rejected.
+///
+/// Rejected request is responded with `403 Forbidden`.
+///
+/// Lists can be updated at runtime through [IpFilterHandle] and the change is observed by all
+/// services built from the same middleware.
+///
+/// # Example:
+/// ```rust
+/// # use xitca_web::{handler::handler_service, middleware::ip_filter::IpFilter, App, HttpServer};
+/// # fn main() -> std::io::Result<()> {
+/// let filter = IpFilter::new().allow("10.0.0.0/8").allow("fd00::/8").deny("10.0.13.0/24");
+///
+/// // handle can be sent to other thread and update lists without restarting server.
+/// let handle = filter.handle();
+/// std::thread::spawn(move || handle.deny("10.0.14.0/24".parse().unwrap()));
+///
+/// HttpServer::new(move || {
+///     App::new()
+///         .at("/admin", handler_service(|| async { "admin" }))
+///         .enclosed(filter.clone())
+///         .finish()
+/// })
+/// .bind("127.0.0.1:8080")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct IpFilter {
+    handle: IpFilterHandle,
+}
+
+impl IpFilter {
+    /// Construct a new middleware with empty allow and deny lists which accept all requests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add network in CIDR notation to allow list.
+    ///
+    /// # Panic:
+    ///
+    /// When given network is not valid CIDR notation.
+    pub fn allow(self, cidr: impl AsRef<str>) -> Self {
+        self.handle.allow(parse(cidr.as_ref()));
+        self
+    }
+
+    /// Add network in CIDR notation to deny list.
+    ///
+    /// # Panic:
+    ///
+    /// When given network is not valid CIDR notation.
+    pub fn deny(self, cidr: impl AsRef<str>) -> Self {
+        self.handle.deny(parse(cidr.as_ref()));
+        self
+    }
+
+    /// Get a handle for updating lists of middleware at runtime.
+    pub fn handle(&self) -> IpFilterHandle {
+        self.handle.clone()
+    }
+}
+
+fn parse(cidr: &str) -> Cidr {
+    cidr.parse()
+        .unwrap_or_else(|_| panic!("{cidr} is not valid CIDR notation"))
+}
+
+/// Handle for updating lists of [IpFilter] at runtime.
+#[derive(Clone, Default)]
+pub struct IpFilterHandle {
+    lists: Arc<RwLock<Lists>>,
+}
+
+#[derive(Default)]
+struct Lists {
+    allow: CidrTrie,
+    deny: CidrTrie,
+}
+
+impl Lists {
+    fn check(&self, ip: Option<IpAddr>) -> bool {
+        match ip {
+            Some(ip) => !self.deny.contains(ip) && (self.allow.is_empty() || self.allow.contains(ip)),
+            None => self.allow.is_empty(),
+        }
+    }
+}
+
+impl IpFilterHandle {
+    /// Add network to allow list.
+    pub fn allow(&self, cidr: Cidr) {
+        self.lists.write().unwrap().allow.insert(cidr);
+    }
+
+    /// Add network to deny list.
+    pub fn deny(&self, cidr: Cidr) {
+        self.lists.write().unwrap().deny.insert(cidr);
+    }
+
+    /// Replace allow list with given networks.
+    pub fn set_allow<I>(&self, cidrs: I)
+    where
+        I: IntoIterator<Item = Cidr>,
+    {
+        let allow = cidrs.into_iter().collect();
+        self.lists.write().unwrap().allow = allow;
+    }
+
+    /// Replace deny list with given networks.
+    pub fn set_deny<I>(&self, cidrs: I)
+    where
+        I: IntoIterator<Item = Cidr>,
+    {
+        let deny = cidrs.into_iter().collect();
+        self.lists.write().unwrap().deny = deny;
+    }
+
+    fn check(&self, ip: Option<IpAddr>) -> bool {
+        self.lists.read().unwrap().check(ip)
+    }
+}
+
+impl<S> BuildService<S> for IpFilter {
+    type Service = IpFilterService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        let handle = self.handle();
+        async { Ok(IpFilterService { service, handle }) }
+    }
+}
+
+pub struct IpFilterService<S> {
+    service: S,
+    handle: IpFilterHandle,
+}
+
+/// Error type of [IpFilterService].
+/// `First` variant contains [IpForbidden] when request is rejected.
+/// `Second` variant contains error returned by the service passed to IpFilter.
+pub type IpFilterError<E> = PipelineE<IpForbidden, E>;
+
+/// Request is rejected by [IpFilter]. Responds with `403 Forbidden`.
+pub struct IpForbidden {
+    ip: Option<IpAddr>,
+}
+
+impl IpForbidden {
+    /// Remote address of rejected request.
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.ip
+    }
+}
+
+impl fmt::Debug for IpForbidden {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IpForbidden").field("ip", &self.ip).finish()
+    }
+}
+
+impl fmt::Display for IpForbidden {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ip {
+            Some(ip) => write!(f, "Remote address: {ip} is forbidden"),
+            None => f.write_str("Unknown remote address is forbidden"),
+        }
+    }
+}
+
+impl std::error::Error for IpForbidden {}
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for IpFilterService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = IpFilterError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let ip = remote_ip(req.req().remote_addr());
+
+            if !self.handle.check(ip) {
+                return Err(IpFilterError::First(IpForbidden { ip }));
+            }
+
+            self.service.call(req).await.map_err(IpFilterError::Second)
+        }
+    }
+}
+
+impl<'r, S, C, B, Res, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for IpFilterService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, B>, Response = Res, Error = Err, Ready = Rdy>,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for IpForbidden {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = StatusCode::FORBIDDEN;
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, test::TestClient, App};
+
+    use super::*;
+
+    #[test]
+    fn lists() {
+        let ip = |ip: &str| Some(ip.parse().unwrap());
+
+        let filter = IpFilter::new();
+        let handle = filter.handle();
+        assert!(handle.check(ip("1.2.3.4")));
+        assert!(handle.check(None));
+
+        handle.deny("1.2.3.0/24".parse().unwrap());
+        assert!(!handle.check(ip("1.2.3.4")));
+        assert!(handle.check(ip("1.2.4.4")));
+
+        handle.allow("1.2.0.0/16".parse().unwrap());
+        assert!(!handle.check(ip("1.2.3.4")));
+        assert!(handle.check(ip("1.2.4.4")));
+        assert!(!handle.check(ip("1.3.0.1")));
+        assert!(!handle.check(None));
+
+        handle.set_deny([]);
+        assert!(handle.check(ip("1.2.3.4")));
+
+        handle.set_allow(["::1".parse().unwrap()]);
+        assert!(!handle.check(ip("1.2.3.4")));
+        assert!(handle.check(ip("::1")));
+    }
+
+    #[test]
+    fn forbidden() {
+        let filter = IpFilter::new().allow("10.0.0.0/8").deny("10.0.13.0/24");
+        let handle = filter.handle();
+
+        let client = TestClient::new(
+            App::new()
+                .at("/", handler_service(|| async { "hello" }))
+                .enclosed(filter)
+                .finish(),
+        )
+        .now_or_panic();
+
+        let get = |addr: SocketAddr| client.get("/").remote_addr(addr).send().now_or_panic();
+
+        get(([10, 0, 0, 1], 80).into()).assert_status(StatusCode::OK);
+        get(([10, 0, 13, 1], 80).into()).assert_status(StatusCode::FORBIDDEN);
+        get(([192, 168, 0, 1], 80).into()).assert_status(StatusCode::FORBIDDEN);
+
+        // update lists at runtime.
+        handle.set_deny([]);
+        handle.allow("192.168.0.0/16".parse().unwrap());
+
+        get(([10, 0, 13, 1], 80).into()).assert_status(StatusCode::OK);
+        get(([192, 168, 0, 1], 80).into()).assert_status(StatusCode::OK);
+    }
+}
@@ -0,0 +1,322 @@
+use std::{convert::Infallible, fmt, future::Future, rc::Rc, sync::Arc, time::Duration};
+
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::timeout,
+};
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, BuildService, Service},
+    },
+    handler::Responder,
+    http::StatusCode,
+    request::WebRequest,
+    response::WebResponse,
+};
+
+/// A middleware cap the number of in-flight requests per worker thread.
+///
+/// [ReadyService::ready] of the service waits for a permit of the semaphore and hands it out with the
+/// ready state of inner service. The server holds the ready state until the connection accepted after
+/// it is closed so a saturated worker stops accepting new connections until a permit is released.
+///
+/// A connection counts as one in-flight request. Multiplexed requests of one http/2 connection share
+/// the permit. Calling the service without [ReadyService::ready] is not limited.
+///
+/// See [LoadShed] for rejecting excessive requests instead of waiting.
+#[derive(Clone, Copy)]
+pub struct ConcurrencyLimit {
+    limit: usize,
+}
+
+impl ConcurrencyLimit {
+    /// Construct a new middleware allow up to `limit` in-flight requests per worker thread.
+    pub const fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+impl<S> BuildService<S> for ConcurrencyLimit {
+    type Service = ConcurrencyLimitService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        // service is built on each worker thread and semaphore is not shared between them.
+        // Arc is for OwnedSemaphorePermit handed out by ready.
+        let semaphore = Arc::new(Semaphore::new(self.limit));
+        async { Ok(ConcurrencyLimitService { service, semaphore }) }
+    }
+}
+
+pub struct ConcurrencyLimitService<S> {
+    service: S,
+    semaphore: Arc<Semaphore>,
+}
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for ConcurrencyLimitService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    #[inline]
+    fn call(&self, req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move { self.service.call(req).await }
+    }
+}
+
+impl<'r, S, C, B, Res, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for ConcurrencyLimitService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, B>, Response = Res, Error = Err, Ready = Rdy>,
+{
+    type Ready = (Rdy, OwnedSemaphorePermit);
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move {
+            // semaphore is never closed.
+            let permit = self.semaphore.clone().acquire_owned().await.unwrap();
+            let ready = self.service.ready().await;
+            (ready, permit)
+        }
+    }
+}
+
+/// A middleware cap the number of in-flight requests per worker thread and reject excessive requests
+/// with `503 Service Unavailable`.
+///
+/// By default request is rejected at once when the limit is reached. [LoadShed::queue_timeout] can be
+/// used to let request wait for a limited time before rejecting it.
+#[derive(Clone, Copy)]
+pub struct LoadShed {
+    limit: usize,
+    queue_timeout: Option<Duration>,
+}
+
+impl LoadShed {
+    /// Construct a new middleware allow up to `limit` in-flight requests per worker thread.
+    pub const fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            queue_timeout: None,
+        }
+    }
+
+    /// Let request wait for in-flight ones to finish up to given duration before rejecting it.
+    pub const fn queue_timeout(mut self, dur: Duration) -> Self {
+        self.queue_timeout = Some(dur);
+        self
+    }
+}
+
+impl<S> BuildService<S> for LoadShed {
+    type Service = LoadShedService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        let semaphore = Rc::new(Semaphore::new(self.limit));
+        let queue_timeout = self.queue_timeout;
+        async move {
+            Ok(LoadShedService {
+                service,
+                semaphore,
+                queue_timeout,
+            })
+        }
+    }
+}
+
+pub struct LoadShedService<S> {
+    service: S,
+    semaphore: Rc<Semaphore>,
+    queue_timeout: Option<Duration>,
+}
+
+/// Error type of [LoadShedService].
+/// `First` variant contains [Overloaded] when request is rejected.
+/// `Second` variant contains error returned by the service passed to LoadShed.
+pub type LoadShedError<E> = PipelineE<Overloaded, E>;
+
+/// Request is rejected by [LoadShed]. Responds with `503 Service Unavailable`.
+pub struct Overloaded;
+
+impl fmt::Debug for Overloaded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Overloaded")
+    }
+}
+
+impl fmt::Display for Overloaded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Service is overloaded")
+    }
+}
+
+impl std::error::Error for Overloaded {}
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for LoadShedService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = LoadShedError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let permit = match self.semaphore.try_acquire() {
+                Ok(permit) => permit,
+                Err(_) => match self.queue_timeout {
+                    Some(dur) => match timeout(dur, self.semaphore.acquire()).await {
+                        Ok(Ok(permit)) => permit,
+                        _ => return Err(LoadShedError::First(Overloaded)),
+                    },
+                    None => return Err(LoadShedError::First(Overloaded)),
+                },
+            };
+
+            let res = self.service.call(req).await;
+            drop(permit);
+            res.map_err(LoadShedError::Second)
+        }
+    }
+}
+
+impl<'r, S, C, B, Res, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for LoadShedService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, B>, Response = Res, Error = Err, Ready = Rdy>,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for Overloaded {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use tokio::time::sleep;
+    use xitca_http::{body::RequestBody, Request};
+
+    use crate::{
+        handler::handler_service,
+        test::{TestBody, TestClient},
+        App,
+    };
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Counter {
+        current: Rc<Cell<usize>>,
+        max: Rc<Cell<usize>>,
+    }
+
+    async fn handler(req: &WebRequest<'_, Counter, TestBody>) -> &'static str {
+        let counter = req.state();
+        counter.current.set(counter.current.get() + 1);
+        counter.max.set(counter.max.get().max(counter.current.get()));
+        sleep(Duration::from_millis(50)).await;
+        counter.current.set(counter.current.get() - 1);
+        "hello"
+    }
+
+    #[tokio::test]
+    async fn concurrency_limit() {
+        let service = App::new()
+            .at("/", handler_service(|| async { "hello" }))
+            .enclosed(ConcurrencyLimit::new(2))
+            .finish()
+            .build(())
+            .await
+            .unwrap();
+
+        let ready = || ReadyService::<Request<RequestBody>>::ready(&service);
+
+        let ready1 = ready().await;
+        let _ready2 = ready().await;
+
+        // limit is reached and ready waits for a permit.
+        assert!(timeout(Duration::from_millis(10), ready()).await.is_err());
+
+        drop(ready1);
+        let _ready3 = timeout(Duration::from_millis(10), ready()).await.unwrap();
+
+        let res = service.call(Request::<RequestBody>::default()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn load_shed() {
+        let client = TestClient::new(
+            App::with_current_thread_state(Counter::default())
+                .at("/", handler_service(handler))
+                .enclosed(LoadShed::new(1))
+                .finish(),
+        )
+        .await;
+
+        let (res1, res2) = tokio::join!(client.get("/").send(), client.get("/").send());
+        res1.assert_status(StatusCode::OK);
+        res2.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+        // permit is released after request finished.
+        client.get("/").send().await.assert_status(StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn load_shed_queue_timeout() {
+        let client = TestClient::new(
+            App::with_current_thread_state(Counter::default())
+                .at("/", handler_service(handler))
+                .enclosed(LoadShed::new(1).queue_timeout(Duration::from_secs(5)))
+                .finish(),
+        )
+        .await;
+
+        let (res1, res2) = tokio::join!(client.get("/").send(), client.get("/").send());
+        res1.assert_status(StatusCode::OK);
+        res2.assert_status(StatusCode::OK);
+
+        let client = TestClient::new(
+            App::with_current_thread_state(Counter::default())
+                .at("/", handler_service(handler))
+                .enclosed(LoadShed::new(1).queue_timeout(Duration::from_millis(1)))
+                .finish(),
+        )
+        .await;
+
+        let (res1, res2) = tokio::join!(client.get("/").send(), client.get("/").send());
+        res1.assert_status(StatusCode::OK);
+        res2.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,241 @@
+use std::{convert::Infallible, future::Future};
+
+use xitca_http::{body::BodySize, util::service::route::RouteMethods};
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{ready::ReadyService, BuildService, Service},
+    },
+    error::RouteError,
+    http::{
+        header::{ALLOW, CONTENT_LENGTH},
+        HeaderValue, Method, StatusCode,
+    },
+    request::WebRequest,
+    response::{ResponseBody, WebResponse},
+};
+
+/// A middleware for [Route](crate::route::Route) answering requests no route handles explicitly.
+///
+/// - `HEAD` request is handled by route with `GET` method. Body of it's response is dropped and
+///   `Content-Length` header is kept when possible.
+/// - `OPTIONS` request is answered with `Allow` header of methods the routes accept.
+/// - Request with other method is answered with `405 Method Not Allowed` and `Allow` header.
+///   When no route's guard accepts the request it's answered with `404 Not Found`.
+///
+/// # Example:
+/// ```rust
+/// # use xitca_web::{
+/// #     dev::service::BuildServiceExt,
+/// #     handler::handler_service,
+/// #     middleware::method_fallback::MethodFallback,
+/// #     route::get,
+/// #     App, HttpServer,
+/// # };
+/// # fn main() -> std::io::Result<()> {
+/// HttpServer::new(|| {
+///     App::new()
+///         .at(
+///             "/",
+///             get(handler_service(|| async { "hello" }))
+///                 .post(handler_service(|| async { "posted" }))
+///                 .enclosed(MethodFallback),
+///         )
+///         .finish()
+/// })
+/// .bind("127.0.0.1:8080")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct MethodFallback;
+
+impl<S> BuildService<S> for MethodFallback {
+    type Service = MethodFallbackService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        async { Ok(MethodFallbackService { service }) }
+    }
+}
+
+pub struct MethodFallbackService<S> {
+    service: S,
+}
+
+impl<'r, S, C, B, E> Service<WebRequest<'r, C, B>> for MethodFallbackService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = WebResponse, Error = RouteError<E>>
+        + for<'rs> RouteMethods<WebRequest<'rs, C, B>>,
+{
+    type Response = WebResponse;
+    type Error = RouteError<E>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, mut req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            match self.service.call(req.reborrow()).await {
+                Err(RouteError::First(_)) => {}
+                res => return res,
+            }
+
+            let method = req.req().method().clone();
+
+            // HEAD request not handled explicitly goes through routes again as GET request so their
+            // guards are evaluated.
+            if method == Method::HEAD {
+                *req.req_mut().method_mut() = Method::GET;
+                let res = self.service.call(req.reborrow()).await;
+                *req.req_mut().method_mut() = Method::HEAD;
+
+                match res {
+                    Ok(res) => return Ok(into_head(res)),
+                    Err(RouteError::First(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let mut allowed = Vec::new();
+            self.service.allowed(&req, &mut allowed);
+
+            let mut res = req.into_response(Bytes::new());
+
+            if allowed.is_empty() {
+                // no route would accept the request.
+                *res.status_mut() = StatusCode::NOT_FOUND;
+            } else {
+                if method != Method::OPTIONS {
+                    *res.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+                }
+                res.headers_mut().insert(ALLOW, allow_header(&allowed));
+            }
+
+            Ok(res)
+        }
+    }
+}
+
+impl<'r, S, C, B, E, Rdy> ReadyService<WebRequest<'r, C, B>> for MethodFallbackService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, B>, Response = WebResponse, Error = RouteError<E>, Ready = Rdy>
+        + for<'rs> RouteMethods<WebRequest<'rs, C, B>>,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+fn into_head(mut res: WebResponse) -> WebResponse {
+    if let BodySize::Sized(len) = BodySize::from_stream(res.body()) {
+        if !res.headers().contains_key(CONTENT_LENGTH) {
+            res.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from(len));
+        }
+    }
+    *res.body_mut() = ResponseBody::None;
+    res
+}
+
+// OPTIONS method is always allowed. HEAD method is allowed with GET method.
+fn allow_header(allowed: &[Method]) -> HeaderValue {
+    let mut methods = vec![Method::OPTIONS.as_str()];
+
+    for method in allowed {
+        if *method == Method::GET && !allowed.contains(&Method::HEAD) {
+            methods.push(Method::HEAD.as_str());
+        }
+        if *method != Method::OPTIONS {
+            methods.push(method.as_str());
+        }
+    }
+
+    HeaderValue::try_from(methods.join(", ")).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        dev::service::BuildServiceExt,
+        handler::handler_service,
+        http::header::HeaderName,
+        route::{get, guard::header_eq, post},
+        test::TestClient,
+        App,
+    };
+
+    use super::*;
+
+    const VERSION: HeaderName = HeaderName::from_static("accept-version");
+
+    #[test]
+    fn fallback() {
+        let client = TestClient::new(
+            App::new()
+                .at(
+                    "/",
+                    get(handler_service(|| async { "hello" }))
+                        .post(handler_service(|| async { "posted" }))
+                        .enclosed(MethodFallback),
+                )
+                .at(
+                    "/v2",
+                    get(handler_service(|| async { "v2" }))
+                        .guard(header_eq(VERSION, "2"))
+                        .enclosed(MethodFallback),
+                )
+                .at(
+                    "/json",
+                    post(handler_service(|| async { "json" }))
+                        .guard(header_eq(VERSION, "2"))
+                        .next(get(handler_service(|| async { "get" })))
+                        .enclosed(MethodFallback),
+                )
+                .finish(),
+        )
+        .now_or_panic();
+
+        let res = client.head("/").send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+        res.assert_header(CONTENT_LENGTH, "5");
+        assert_eq!(res.string().now_or_panic(), "");
+
+        let res = client.options("/").send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+        res.assert_header(ALLOW, "OPTIONS, HEAD, GET, POST");
+
+        let res = client.delete("/").send().now_or_panic();
+        res.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+        res.assert_header(ALLOW, "OPTIONS, HEAD, GET, POST");
+
+        // guard of get route is evaluated for HEAD request.
+        let res = client.head("/v2").send().now_or_panic();
+        res.assert_status(StatusCode::NOT_FOUND);
+
+        let res = client.head("/v2").header(VERSION, "2").send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+        res.assert_header(CONTENT_LENGTH, "2");
+
+        // route rejected by guard is not allowed.
+        let res = client.delete("/json").send().now_or_panic();
+        res.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+        res.assert_header(ALLOW, "OPTIONS, HEAD, GET");
+    }
+
+    #[test]
+    fn allow() {
+        assert_eq!(allow_header(&[Method::GET]), "OPTIONS, HEAD, GET");
+        assert_eq!(allow_header(&[Method::HEAD, Method::GET]), "OPTIONS, HEAD, GET");
+        assert_eq!(allow_header(&[Method::POST, Method::OPTIONS]), "OPTIONS, POST");
+    }
+}
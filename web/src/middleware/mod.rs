@@ -1,8 +1,38 @@
-#[cfg(any(feature = "compress-br", feature = "compress-gz", feature = "compress-de"))]
+#[cfg(any(
+    feature = "compress-br",
+    feature = "compress-gz",
+    feature = "compress-de",
+    feature = "compress-zs"
+))]
 pub mod compress;
-#[cfg(any(feature = "compress-br", feature = "compress-gz", feature = "compress-de"))]
+#[cfg(any(
+    feature = "compress-br",
+    feature = "compress-gz",
+    feature = "compress-de",
+    feature = "compress-zs"
+))]
 pub mod decompress;
 
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "limit")]
+pub mod limit;
+#[cfg(feature = "timeout")]
+pub mod timeout;
+
+pub mod body_limit;
+pub mod catch_panic;
+pub mod conditional_get;
+pub mod ip_filter;
+pub mod method_fallback;
+pub mod normalize_path;
+pub mod proxy_headers;
+pub mod ranges;
+
+mod cidr;
+
+pub use cidr::{Cidr, CidrParseError};
+
 #[cfg(feature = "tower-http-compat")]
 pub mod tower_http_compat;
 
@@ -0,0 +1,347 @@
+use std::{convert::Infallible, fmt, future::Future};
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, BuildService, Service},
+    },
+    handler::Responder,
+    http::{header::LOCATION, uri::PathAndQuery, HeaderValue, StatusCode, Uri},
+    request::WebRequest,
+    response::WebResponse,
+};
+
+/// A middleware normalize request's path before it reaches the router.
+///
+/// Normalization includes:
+/// - merge duplicate slashes. `/a//b` -> `/a/b`
+/// - resolve `.` and `..` segments. `/a/./b/../c` -> `/a/c`
+/// - percent-decode unreserved characters and uppercase other percent-encodings. `/%7euser%2f` -> `/~user%2F`
+/// - add or remove trailing slash according to [TrailingSlash] policy.
+///
+/// By default request's uri is rewritten in place. [NormalizePath::redirect] can be used to
+/// respond with `308 Permanent Redirect` to the normalized path instead.
+#[derive(Clone)]
+pub struct NormalizePath {
+    trailing_slash: TrailingSlash,
+    redirect: bool,
+}
+
+/// Policy on trailing slash of request path for [NormalizePath] middleware.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TrailingSlash {
+    /// Remove trailing slash. `/a/` -> `/a`
+    Trim,
+    /// Add trailing slash. `/a` -> `/a/`
+    Always,
+    /// Keep trailing slash as is.
+    MergeOnly,
+}
+
+impl Default for NormalizePath {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NormalizePath {
+    /// Construct a new middleware that trim trailing slash and rewrite request path in place.
+    pub const fn new() -> Self {
+        Self {
+            trailing_slash: TrailingSlash::Trim,
+            redirect: false,
+        }
+    }
+
+    /// Set policy on trailing slash.
+    ///
+    /// Default to [TrailingSlash::Trim].
+    pub const fn trailing_slash(mut self, trailing_slash: TrailingSlash) -> Self {
+        self.trailing_slash = trailing_slash;
+        self
+    }
+
+    /// Respond with `308 Permanent Redirect` to normalized path instead of rewriting request path.
+    pub const fn redirect(mut self) -> Self {
+        self.redirect = true;
+        self
+    }
+}
+
+impl<S> BuildService<S> for NormalizePath {
+    type Service = NormalizePathService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        let trailing_slash = self.trailing_slash;
+        let redirect = self.redirect;
+        async move {
+            Ok(NormalizePathService {
+                service,
+                trailing_slash,
+                redirect,
+            })
+        }
+    }
+}
+
+pub struct NormalizePathService<S> {
+    service: S,
+    trailing_slash: TrailingSlash,
+    redirect: bool,
+}
+
+/// Error type of [NormalizePathService].
+/// `First` variant contains [PathRedirect] when redirect is enabled.
+/// `Second` variant contains error returned by the service passed to NormalizePath.
+pub type NormalizePathError<E> = PipelineE<PathRedirect, E>;
+
+/// Redirect to normalized path. Responds with `308 Permanent Redirect`.
+pub struct PathRedirect {
+    location: HeaderValue,
+}
+
+impl fmt::Debug for PathRedirect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PathRedirect").field("location", &self.location).finish()
+    }
+}
+
+impl fmt::Display for PathRedirect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Redirect to normalized path: {:?}", self.location)
+    }
+}
+
+impl std::error::Error for PathRedirect {}
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for NormalizePathService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = NormalizePathError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, mut req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let uri = req.req().uri();
+            if let Some(uri) = normalize_path(uri.path(), self.trailing_slash).and_then(|path| with_path(uri, &path)) {
+                if self.redirect {
+                    let location = uri.path_and_query().map(PathAndQuery::as_str).unwrap_or("/");
+                    if let Ok(location) = HeaderValue::try_from(location) {
+                        return Err(NormalizePathError::First(PathRedirect { location }));
+                    }
+                } else {
+                    *req.req_mut().uri_mut() = uri;
+                }
+            }
+
+            self.service.call(req).await.map_err(NormalizePathError::Second)
+        }
+    }
+}
+
+impl<'r, S, C, B, Res, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for NormalizePathService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, B>, Response = Res, Error = Err, Ready = Rdy>,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for PathRedirect {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = StatusCode::PERMANENT_REDIRECT;
+        res.headers_mut().insert(LOCATION, self.location);
+        async { res }
+    }
+}
+
+// replace path of uri and keep the rest part of it.
+fn with_path(uri: &Uri, path: &str) -> Option<Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => PathAndQuery::try_from(format!("{path}?{query}")),
+        None => PathAndQuery::try_from(path),
+    }
+    .ok()?;
+
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query);
+    Uri::from_parts(parts).ok()
+}
+
+// normalize given path. return None when path is already normalized.
+fn normalize_path(path: &str, trailing_slash: TrailingSlash) -> Option<String> {
+    let mut segments = Vec::new();
+
+    for segment in path.split('/') {
+        let segment = decode_unreserved(segment);
+        match &*segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut normalized = String::with_capacity(path.len());
+
+    for segment in segments.iter() {
+        normalized.push('/');
+        normalized.push_str(segment);
+    }
+
+    let trailing = match trailing_slash {
+        TrailingSlash::Trim => false,
+        TrailingSlash::Always => true,
+        TrailingSlash::MergeOnly => path.ends_with('/'),
+    };
+
+    if trailing || normalized.is_empty() {
+        normalized.push('/');
+    }
+
+    (normalized != path).then_some(normalized)
+}
+
+// percent-decode unreserved characters and uppercase hex digits of other percent-encodings.
+fn decode_unreserved(segment: &str) -> std::borrow::Cow<'_, str> {
+    if !segment.contains('%') {
+        return segment.into();
+    }
+
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(segment.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                let byte = hi << 4 | lo;
+                if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+                    decoded.push(byte);
+                } else {
+                    decoded.extend_from_slice(&[
+                        b'%',
+                        bytes[i + 1].to_ascii_uppercase(),
+                        bytes[i + 2].to_ascii_uppercase(),
+                    ]);
+                }
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    // only ascii bytes are decoded so the segment stays valid utf-8.
+    String::from_utf8(decoded)
+        .map(Into::into)
+        .unwrap_or_else(|_| segment.into())
+}
+
+fn hex(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_http::{body::RequestBody, Request};
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::{handler_service, uri::UriRef},
+        test::collect_string_body,
+        App,
+    };
+
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        let trim = |path| normalize_path(path, TrailingSlash::Trim);
+        assert_eq!(trim("/"), None);
+        assert_eq!(trim("/a/b"), None);
+        assert_eq!(trim("").as_deref(), Some("/"));
+        assert_eq!(trim("//").as_deref(), Some("/"));
+        assert_eq!(trim("/api//users/").as_deref(), Some("/api/users"));
+        assert_eq!(trim("/a/./b/../c").as_deref(), Some("/a/c"));
+        assert_eq!(trim("/../../a").as_deref(), Some("/a"));
+        assert_eq!(trim("/a/%2e%2E/b").as_deref(), Some("/b"));
+        assert_eq!(trim("/%7euser%2f%41").as_deref(), Some("/~user%2FA"));
+        assert_eq!(trim("/100%").as_deref(), None);
+        assert_eq!(trim("/café/%c3%a9%41").as_deref(), Some("/café/%C3%A9A"));
+
+        let always = |path| normalize_path(path, TrailingSlash::Always);
+        assert_eq!(always("/"), None);
+        assert_eq!(always("/a/b/"), None);
+        assert_eq!(always("/a//b").as_deref(), Some("/a/b/"));
+
+        let merge = |path| normalize_path(path, TrailingSlash::MergeOnly);
+        assert_eq!(merge("/a/"), None);
+        assert_eq!(merge("/a"), None);
+        assert_eq!(merge("//a//").as_deref(), Some("/a/"));
+    }
+
+    async fn handler(UriRef(uri): UriRef<'_>) -> String {
+        uri.to_string()
+    }
+
+    #[test]
+    fn rewrite() {
+        let mut req = Request::new(RequestBody::default());
+        *req.uri_mut() = Uri::from_static("/api//users/?id=1");
+
+        let res = App::new()
+            .at("/api/users", handler_service(handler))
+            .enclosed(NormalizePath::new())
+            .finish()
+            .build(())
+            .now_or_panic()
+            .unwrap()
+            .call(req)
+            .now_or_panic()
+            .unwrap();
+
+        assert_eq!(res.status().as_u16(), 200);
+        let body = collect_string_body(res.into_body()).now_or_panic().unwrap();
+        assert_eq!(body, "/api/users?id=1");
+    }
+
+    #[test]
+    fn redirect() {
+        let mut req = Request::new(RequestBody::default());
+        *req.uri_mut() = Uri::from_static("/api/./users?id=1");
+
+        let res = App::new()
+            .at("/api/users/", handler_service(handler))
+            .enclosed(NormalizePath::new().trailing_slash(TrailingSlash::Always).redirect())
+            .finish()
+            .build(())
+            .now_or_panic()
+            .unwrap()
+            .call(req)
+            .now_or_panic()
+            .unwrap();
+
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/api/users/?id=1");
+    }
+}
@@ -0,0 +1,490 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    rc::Rc,
+};
+
+use xitca_http::request::RemoteAddr;
+
+use crate::{
+    dev::service::{ready::ReadyService, BuildService, Service},
+    handler::connection_info::{remote_ip, ConnectionInfo},
+    http::{
+        header::{HeaderName, FORWARDED},
+        HeaderMap,
+    },
+    request::WebRequest,
+};
+
+use super::cidr::Cidr;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+#[allow(clippy::declare_interior_mutable_const)]
+const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+#[allow(clippy::declare_interior_mutable_const)]
+const X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
+
+/// A middleware resolve real client information from headers set by trusted proxies.
+///
+/// When request's remote address is inside trusted networks the trusted header is walked from the nearest
+/// hop and the first address not trusted is treated as client address.
+///
+/// Only one family of headers is trusted and the other one is ignored:
+/// - [ProxyHeaders::forwarded] trusts `Forwarded` header. This is the default.
+/// - [ProxyHeaders::x_forwarded_for] trusts `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host` headers.
+///
+/// The resolved client address overrides request's [RemoteAddr] and the resolved information can be
+/// extracted with [ConnectionInfo] type. When the client hop carries no port the port of request's remote
+/// address is kept.
+///
+/// # Example:
+/// ```rust
+/// # use xitca_web::{handler::{connection_info::ConnectionInfo, handler_service}, middleware::proxy_headers::ProxyHeaders, App, HttpServer};
+/// async fn index(info: ConnectionInfo) -> String {
+///     format!("{:?}", info.client_ip())
+/// }
+///
+/// # fn main() -> std::io::Result<()> {
+/// HttpServer::new(|| {
+///     App::new()
+///         .at("/", handler_service(index))
+///         .enclosed(ProxyHeaders::new(["10.0.0.0/8", "fd00::/8"]).x_forwarded_for())
+///         .finish()
+/// })
+/// .bind("127.0.0.1:8080")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ProxyHeaders {
+    trusted: Rc<[Cidr]>,
+    header: TrustedHeader,
+}
+
+#[derive(Clone, Copy)]
+enum TrustedHeader {
+    Forwarded,
+    XForwarded,
+}
+
+impl ProxyHeaders {
+    /// Construct middleware with trusted proxy networks in CIDR notation.
+    ///
+    /// # Panic:
+    ///
+    /// When given network is not valid CIDR notation.
+    pub fn new<I>(trusted: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let trusted = trusted
+            .into_iter()
+            .map(|cidr| {
+                let cidr = cidr.as_ref();
+                cidr.parse()
+                    .unwrap_or_else(|_| panic!("{cidr} is not valid CIDR notation"))
+            })
+            .collect();
+
+        Self {
+            trusted,
+            header: TrustedHeader::Forwarded,
+        }
+    }
+
+    /// Trust `Forwarded` header. RFC 7239.
+    ///
+    /// `X-Forwarded-*` headers are ignored.
+    pub fn forwarded(mut self) -> Self {
+        self.header = TrustedHeader::Forwarded;
+        self
+    }
+
+    /// Trust `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host` headers.
+    ///
+    /// `Forwarded` header is ignored.
+    pub fn x_forwarded_for(mut self) -> Self {
+        self.header = TrustedHeader::XForwarded;
+        self
+    }
+}
+
+impl<S> BuildService<S> for ProxyHeaders {
+    type Service = ProxyHeadersService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        let trusted = self.trusted.clone();
+        let header = self.header;
+        async move {
+            Ok(ProxyHeadersService {
+                service,
+                trusted,
+                header,
+            })
+        }
+    }
+}
+
+pub struct ProxyHeadersService<S> {
+    service: S,
+    trusted: Rc<[Cidr]>,
+    header: TrustedHeader,
+}
+
+impl<S> ProxyHeadersService<S> {
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    fn resolve<C, B>(&self, req: &WebRequest<'_, C, B>) -> Option<(ConnectionInfo, Option<u16>)> {
+        let peer = remote_ip(req.req().remote_addr())?;
+
+        if !self.is_trusted(peer) {
+            return None;
+        }
+
+        let headers = req.req().headers();
+
+        let hops = match self.header {
+            TrustedHeader::Forwarded => forwarded(headers),
+            TrustedHeader::XForwarded => x_forwarded_for(headers),
+        };
+
+        // walk from the nearest hop and stop at the first address not trusted.
+        let mut client = None;
+        for hop in hops.iter().rev() {
+            match hop.addr {
+                Some((ip, _)) => {
+                    client = Some(hop);
+                    if !self.is_trusted(ip) {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        let client = client?;
+
+        let mut info = ConnectionInfo::from_request(req);
+        let (ip, port) = client.addr?;
+        info.client_ip = Some(ip);
+
+        let (scheme, host) = match self.header {
+            TrustedHeader::Forwarded => (client.proto.clone(), client.host.clone()),
+            TrustedHeader::XForwarded => (
+                last_value(headers, &X_FORWARDED_PROTO),
+                last_value(headers, &X_FORWARDED_HOST),
+            ),
+        };
+
+        if let Some(scheme) = scheme {
+            info.scheme = Some(scheme);
+        }
+
+        if let Some(host) = host {
+            info.host = Some(host);
+        }
+
+        Some((info, port))
+    }
+}
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for ProxyHeadersService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, mut req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            if let Some((info, port)) = self.resolve(&req) {
+                if let Some(ip) = info.client_ip {
+                    let port = port.unwrap_or_else(|| remote_port(req.req().remote_addr()));
+                    *req.req_mut().remote_addr_mut() = RemoteAddr::from(SocketAddr::new(ip, port));
+                }
+                req.req_mut().extensions_mut().insert(info);
+            }
+
+            self.service.call(req).await
+        }
+    }
+}
+
+impl<'r, S, C, B, Res, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for ProxyHeadersService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, B>, Response = Res, Error = Err, Ready = Rdy>,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+#[derive(Default)]
+struct Hop {
+    addr: Option<(IpAddr, Option<u16>)>,
+    proto: Option<String>,
+    host: Option<String>,
+}
+
+// parse Forwarded header(s) into list of hops. RFC 7239.
+fn forwarded(headers: &HeaderMap) -> Vec<Hop> {
+    headers
+        .get_all(FORWARDED)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|element| {
+            let mut hop = Hop::default();
+            for pair in element.split(';') {
+                if let Some((key, value)) = pair.split_once('=') {
+                    let key = key.trim();
+                    let value = value.trim().trim_matches('"');
+                    if key.eq_ignore_ascii_case("for") {
+                        hop.addr = parse_node(value);
+                    } else if key.eq_ignore_ascii_case("proto") {
+                        hop.proto = Some(value.to_ascii_lowercase());
+                    } else if key.eq_ignore_ascii_case("host") {
+                        hop.host = Some(value.to_owned());
+                    }
+                }
+            }
+            hop
+        })
+        .collect()
+}
+
+fn x_forwarded_for(headers: &HeaderMap) -> Vec<Hop> {
+    headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|node| Hop {
+            addr: parse_node(node.trim()),
+            ..Hop::default()
+        })
+        .collect()
+}
+
+fn last_value(headers: &HeaderMap, name: &HeaderName) -> Option<String> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .last()
+        .map(|v| v.trim().to_owned())
+        .filter(|v| !v.is_empty())
+}
+
+fn remote_port(addr: &RemoteAddr) -> u16 {
+    match *addr {
+        RemoteAddr::V4(_, port) | RemoteAddr::V6(_, port) => port,
+        RemoteAddr::None => 0,
+    }
+}
+
+// parse node in form of `ip`, `ip:port`, `[ipv6]` or `[ipv6]:port`.
+// obfuscated identifier and `unknown` are treated as invalid node.
+// obfuscated or absent port is treated as unknown port.
+fn parse_node(node: &str) -> Option<(IpAddr, Option<u16>)> {
+    if let Some(rest) = node.strip_prefix('[') {
+        let (ip, rest) = rest.split_once(']')?;
+        let port = rest.strip_prefix(':').and_then(|port| port.parse().ok());
+        return ip.parse().ok().map(|ip| (ip, port));
+    }
+
+    match node.parse() {
+        Ok(ip) => Some((ip, None)),
+        Err(_) => node
+            .parse::<SocketAddr>()
+            .ok()
+            .map(|addr| (addr.ip(), Some(addr.port()))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        handler::handler_service,
+        http::StatusCode,
+        test::{TestBody, TestClient},
+        App,
+    };
+
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use super::*;
+
+    async fn index(info: ConnectionInfo, req: &WebRequest<'_, (), TestBody>) -> String {
+        format!(
+            "{:?} {:?} {:?} {:?}",
+            info.client_ip(),
+            info.scheme(),
+            info.host(),
+            req.req().remote_addr()
+        )
+    }
+
+    #[test]
+    fn node() {
+        assert_eq!(parse_node("192.0.2.43"), Some(("192.0.2.43".parse().unwrap(), None)));
+        assert_eq!(
+            parse_node("192.0.2.43:47011"),
+            Some(("192.0.2.43".parse().unwrap(), Some(47011)))
+        );
+        assert_eq!(
+            parse_node("[2001:db8:cafe::17]:4711"),
+            Some(("2001:db8:cafe::17".parse().unwrap(), Some(4711)))
+        );
+        assert_eq!(
+            parse_node("[2001:db8:cafe::17]:_port"),
+            Some(("2001:db8:cafe::17".parse().unwrap(), None))
+        );
+        assert_eq!(
+            parse_node("2001:db8:cafe::17"),
+            Some(("2001:db8:cafe::17".parse().unwrap(), None))
+        );
+        assert_eq!(parse_node("unknown"), None);
+        assert_eq!(parse_node("_hidden"), None);
+    }
+
+    #[test]
+    fn resolve_x_forwarded() {
+        let client = TestClient::new(
+            App::new()
+                .at("/", handler_service(index))
+                .enclosed(ProxyHeaders::new(["10.0.0.0/8"]).x_forwarded_for())
+                .finish(),
+        )
+        .now_or_panic();
+
+        let proxy = SocketAddr::from(([10, 0, 0, 1], 80));
+
+        // untrusted peer can not spoof headers.
+        let res = client
+            .get("/")
+            .remote_addr(([1, 2, 3, 4], 80).into())
+            .header("x-forwarded-for", "5.6.7.8")
+            .header("host", "example.com")
+            .send()
+            .now_or_panic();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(
+            res.string().now_or_panic(),
+            r#"Some(1.2.3.4) None Some("example.com") V4(1.2.3.4, 80)"#
+        );
+
+        // client hop without port keeps the port of peer.
+        let res = client
+            .get("/")
+            .remote_addr(proxy)
+            .header("x-forwarded-for", "9.9.9.9, 5.6.7.8, 10.0.0.2")
+            .header("x-forwarded-proto", "https")
+            .header("x-forwarded-host", "example.com")
+            .send()
+            .now_or_panic();
+        assert_eq!(
+            res.string().now_or_panic(),
+            r#"Some(5.6.7.8) Some("https") Some("example.com") V4(5.6.7.8, 80)"#
+        );
+
+        // Forwarded header is not trusted.
+        let res = client
+            .get("/")
+            .remote_addr(proxy)
+            .header("x-forwarded-for", "5.6.7.8:4711")
+            .header("forwarded", "for=9.9.9.9;proto=https;host=evil.com")
+            .header("host", "example.com")
+            .send()
+            .now_or_panic();
+        assert_eq!(
+            res.string().now_or_panic(),
+            r#"Some(5.6.7.8) None Some("example.com") V4(5.6.7.8, 4711)"#
+        );
+
+        // all hops trusted. use the farthest one.
+        let res = client
+            .get("/")
+            .remote_addr(proxy)
+            .header("x-forwarded-for", "10.0.0.3, 10.0.0.2")
+            .send()
+            .now_or_panic();
+        assert_eq!(
+            res.string().now_or_panic(),
+            r#"Some(10.0.0.3) None None V4(10.0.0.3, 80)"#
+        );
+    }
+
+    #[test]
+    fn resolve_forwarded() {
+        let client = TestClient::new(
+            App::new()
+                .at("/", handler_service(index))
+                .enclosed(ProxyHeaders::new(["10.0.0.0/8"]).forwarded())
+                .finish(),
+        )
+        .now_or_panic();
+
+        let proxy = SocketAddr::from(([10, 0, 0, 1], 80));
+
+        // X-Forwarded-* headers are not trusted and not mixed with Forwarded.
+        let res = client
+            .get("/")
+            .remote_addr(proxy)
+            .header("x-forwarded-for", "5.6.7.8")
+            .header("x-forwarded-proto", "http")
+            .header("x-forwarded-host", "evil.com")
+            .header(
+                "forwarded",
+                r#"for="[2001:db8:cafe::17]:4711";proto=https, for=10.0.0.2"#,
+            )
+            .header("host", "example.com")
+            .send()
+            .now_or_panic();
+        assert_eq!(
+            res.string().now_or_panic(),
+            r#"Some(2001:db8:cafe::17) Some("https") Some("example.com") V6(2001:db8:cafe::17, 4711)"#
+        );
+
+        // absent Forwarded header.
+        let res = client
+            .get("/")
+            .remote_addr(proxy)
+            .header("x-forwarded-for", "5.6.7.8")
+            .send()
+            .now_or_panic();
+        assert_eq!(
+            res.string().now_or_panic(),
+            r#"Some(10.0.0.1) None None V4(10.0.0.1, 80)"#
+        );
+
+        // obfuscated client.
+        let res = client
+            .get("/")
+            .remote_addr(proxy)
+            .header("forwarded", "for=_hidden, for=10.0.0.2")
+            .send()
+            .now_or_panic();
+        assert_eq!(
+            res.string().now_or_panic(),
+            r#"Some(10.0.0.2) None None V4(10.0.0.2, 80)"#
+        );
+    }
+}
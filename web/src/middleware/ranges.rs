@@ -0,0 +1,361 @@
+use std::{
+    collections::hash_map::RandomState,
+    convert::Infallible,
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    ops::Range,
+};
+
+use crate::{
+    dev::{
+        bytes::{BufMut, BytesMut},
+        service::{ready::ReadyService, BuildService, Service},
+    },
+    http::{
+        header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    request::WebRequest,
+    response::{ResponseBody, WebResponse},
+};
+
+// maximum number of ranges in one request. Range header with more ranges is ignored.
+const MAX_RANGES: usize = 32;
+
+/// A middleware serve `Range` request for response with buffered body (`ResponseBody::Bytes`).
+///
+/// For successful response to `GET` request:
+/// - `Accept-Ranges: bytes` header is added to response.
+/// - Single range is responded with `206 Partial Content` and `Content-Range` header.
+/// - Multiple ranges are responded with `206 Partial Content` and `multipart/byteranges` body.
+/// - Unsatisfiable ranges are responded with `416 Range Not Satisfiable`.
+/// - `If-Range` header is evaluated against response's `ETag` and `Last-Modified` headers. Range is
+///   ignored and full response is sent when they don't match.
+///
+/// Malformed `Range` header is ignored. Streaming response body is passed through as is.
+#[derive(Clone, Copy, Default)]
+pub struct Ranges;
+
+impl<S> BuildService<S> for Ranges {
+    type Service = RangesService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        async { Ok(RangesService { service }) }
+    }
+}
+
+pub struct RangesService<S> {
+    service: S,
+}
+
+impl<'r, S, C, B, ResB, Err> Service<WebRequest<'r, C, B>> for RangesService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = WebResponse<ResponseBody<ResB>>, Error = Err>,
+{
+    type Response = WebResponse<ResponseBody<ResB>>;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let is_get = req.req().method() == Method::GET;
+            let headers = req.req().headers();
+            let range = headers.get(RANGE).cloned();
+            let if_range = headers.get(IF_RANGE).cloned();
+
+            let mut res = self.service.call(req).await?;
+
+            if !is_get || res.status() != StatusCode::OK {
+                return Ok(res);
+            }
+
+            let bytes = match *res.body() {
+                ResponseBody::Bytes { ref bytes } => bytes.clone(),
+                _ => return Ok(res),
+            };
+
+            res.headers_mut()
+                .entry(ACCEPT_RANGES)
+                .or_insert(HeaderValue::from_static("bytes"));
+
+            let range = match range {
+                Some(range) if if_range.map(|v| if_range_match(&v, res.headers())).unwrap_or(true) => range,
+                _ => return Ok(res),
+            };
+
+            let ranges = match range
+                .to_str()
+                .ok()
+                .and_then(|range| parse_ranges(range, bytes.len() as u64))
+            {
+                Some(ranges) => ranges,
+                None => return Ok(res),
+            };
+
+            let len = bytes.len();
+
+            match ranges.as_slice() {
+                [] => {
+                    *res.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+                    *res.body_mut() = ResponseBody::None;
+                    let headers = res.headers_mut();
+                    headers.remove(CONTENT_LENGTH);
+                    headers.remove(CONTENT_TYPE);
+                    headers.insert(CONTENT_RANGE, content_range(None, len));
+                }
+                [range] => {
+                    *res.status_mut() = StatusCode::PARTIAL_CONTENT;
+                    *res.body_mut() = ResponseBody::from(bytes.slice(range.clone()));
+                    let headers = res.headers_mut();
+                    headers.remove(CONTENT_LENGTH);
+                    headers.insert(CONTENT_RANGE, content_range(Some(range), len));
+                }
+                ranges => {
+                    let boundary = boundary();
+                    let content_type = res.headers_mut().remove(CONTENT_TYPE);
+
+                    let mut body = BytesMut::new();
+                    for range in ranges {
+                        body.put_slice(b"--");
+                        body.put_slice(boundary.as_bytes());
+                        body.put_slice(b"\r\n");
+                        if let Some(ref content_type) = content_type {
+                            body.put_slice(b"content-type: ");
+                            body.put_slice(content_type.as_bytes());
+                            body.put_slice(b"\r\n");
+                        }
+                        body.put_slice(b"content-range: ");
+                        body.put_slice(content_range(Some(range), len).as_bytes());
+                        body.put_slice(b"\r\n\r\n");
+                        body.put_slice(&bytes[range.clone()]);
+                        body.put_slice(b"\r\n");
+                    }
+                    body.put_slice(b"--");
+                    body.put_slice(boundary.as_bytes());
+                    body.put_slice(b"--\r\n");
+
+                    *res.status_mut() = StatusCode::PARTIAL_CONTENT;
+                    *res.body_mut() = ResponseBody::from(body);
+                    let headers = res.headers_mut();
+                    headers.remove(CONTENT_LENGTH);
+                    let content_type = format!("multipart/byteranges; boundary={boundary}");
+                    headers.insert(CONTENT_TYPE, HeaderValue::try_from(content_type).unwrap());
+                }
+            }
+
+            Ok(res)
+        }
+    }
+}
+
+impl<'r, S, C, B, ResB, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for RangesService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<
+        WebRequest<'rs, C, B>,
+        Response = WebResponse<ResponseBody<ResB>>,
+        Error = Err,
+        Ready = Rdy,
+    >,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+// If-Range matches strong ETag or exact Last-Modified date of response.
+fn if_range_match(if_range: &HeaderValue, headers: &HeaderMap) -> bool {
+    let if_range = if_range.as_bytes();
+
+    if if_range.starts_with(b"\"") {
+        headers
+            .get(ETAG)
+            .map(|etag| etag.as_bytes() == if_range)
+            .unwrap_or(false)
+    } else {
+        headers
+            .get(LAST_MODIFIED)
+            .map(|date| date.as_bytes() == if_range)
+            .unwrap_or(false)
+    }
+}
+
+// parse Range header against length of body.
+// return None when header is malformed and empty ranges when none of them is satisfiable.
+fn parse_ranges(header: &str, len: u64) -> Option<Vec<Range<usize>>> {
+    let specs = header.trim().strip_prefix("bytes=")?;
+
+    let mut ranges = Vec::new();
+
+    for spec in specs.split(',') {
+        let (first, last) = spec.trim().split_once('-')?;
+
+        let range = match (first.trim(), last.trim()) {
+            ("", suffix) => {
+                let suffix = suffix.parse::<u64>().ok()?;
+                (suffix > 0 && len > 0).then_some(len.saturating_sub(suffix)..len)
+            }
+            (first, "") => {
+                let first = first.parse::<u64>().ok()?;
+                (first < len).then_some(first..len)
+            }
+            (first, last) => {
+                let first = first.parse::<u64>().ok()?;
+                let last = last.parse::<u64>().ok()?;
+                if first > last {
+                    return None;
+                }
+                (first < len).then_some(first..last.saturating_add(1).min(len))
+            }
+        };
+
+        ranges.extend(range);
+
+        if ranges.len() > MAX_RANGES {
+            return None;
+        }
+    }
+
+    // merge overlapping and adjacent ranges.
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    Some(
+        merged
+            .into_iter()
+            .map(|range| range.start as usize..range.end as usize)
+            .collect(),
+    )
+}
+
+fn content_range(range: Option<&Range<usize>>, len: usize) -> HeaderValue {
+    let value = match range {
+        Some(range) if !range.is_empty() => format!("bytes {}-{}/{}", range.start, range.end - 1, len),
+        _ => format!("bytes */{len}"),
+    };
+    HeaderValue::try_from(value).unwrap()
+}
+
+fn boundary() -> String {
+    let n = RandomState::new().build_hasher().finish();
+    format!("{n:016x}")
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, test::TestClient, App};
+
+    use super::*;
+
+    fn parse(header: &str, len: u64) -> Option<Vec<(usize, usize)>> {
+        parse_ranges(header, len).map(|ranges| ranges.into_iter().map(|r| (r.start, r.end)).collect())
+    }
+
+    #[test]
+    fn parse_range() {
+        assert_eq!(parse("bytes=0-4", 10), Some(vec![(0, 5)]));
+        assert_eq!(parse("bytes=5-", 10), Some(vec![(5, 10)]));
+        assert_eq!(parse("bytes=-3", 10), Some(vec![(7, 10)]));
+        assert_eq!(parse("bytes=-30", 10), Some(vec![(0, 10)]));
+        assert_eq!(parse("bytes=8-20", 10), Some(vec![(8, 10)]));
+        assert_eq!(parse("bytes=0-18446744073709551615", 10), Some(vec![(0, 10)]));
+        assert_eq!(parse("bytes=0-1, 4-5", 10), Some(vec![(0, 2), (4, 6)]));
+        assert_eq!(parse("bytes=4-6, 0-4", 10), Some(vec![(0, 7)]));
+        assert_eq!(parse("bytes=10-", 10), Some(vec![]));
+        assert_eq!(parse("bytes=-0", 10), Some(vec![]));
+        assert_eq!(parse("bytes=5-4", 10), None);
+        assert_eq!(parse("bytes=a-4", 10), None);
+        assert_eq!(parse("items=0-4", 10), None);
+    }
+
+    #[test]
+    fn content_range_value() {
+        assert_eq!(content_range(Some(&(0..5)), 10), "bytes 0-4/10");
+        assert_eq!(content_range(Some(&(0..0)), 0), "bytes */0");
+        assert_eq!(content_range(None, 10), "bytes */10");
+    }
+
+    async fn handler() -> WebResponse {
+        let mut res = WebResponse::new(ResponseBody::from("0123456789"));
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        res.headers_mut().insert(ETAG, HeaderValue::from_static("\"v1\""));
+        res
+    }
+
+    #[test]
+    fn ranges() {
+        let client =
+            TestClient::new(App::new().at("/", handler_service(handler)).enclosed(Ranges).finish()).now_or_panic();
+
+        let res = client.get("/").send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+        res.assert_header("accept-ranges", "bytes");
+        assert_eq!(res.string().now_or_panic(), "0123456789");
+
+        let res = client.get("/").header("range", "bytes=2-4").send().now_or_panic();
+        res.assert_status(StatusCode::PARTIAL_CONTENT);
+        res.assert_header("content-range", "bytes 2-4/10");
+        assert_eq!(res.string().now_or_panic(), "234");
+
+        let res = client.get("/").header("range", "bytes=20-").send().now_or_panic();
+        res.assert_status(StatusCode::RANGE_NOT_SATISFIABLE);
+        res.assert_header("content-range", "bytes */10");
+        assert_eq!(res.string().now_or_panic(), "");
+
+        let res = client.get("/").header("range", "bytes=0-1,-2").send().now_or_panic();
+        res.assert_status(StatusCode::PARTIAL_CONTENT);
+        let content_type = res.headers().get(CONTENT_TYPE).unwrap().to_str().unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap()
+            .to_owned();
+        assert_eq!(
+            res.string().now_or_panic(),
+            format!(
+                "--{boundary}\r\ncontent-type: text/plain\r\ncontent-range: bytes 0-1/10\r\n\r\n01\r\n\
+                 --{boundary}\r\ncontent-type: text/plain\r\ncontent-range: bytes 8-9/10\r\n\r\n89\r\n\
+                 --{boundary}--\r\n"
+            )
+        );
+
+        // If-Range mismatch sends full response.
+        let res = client
+            .get("/")
+            .header("range", "bytes=2-4")
+            .header("if-range", "\"v0\"")
+            .send()
+            .now_or_panic();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string().now_or_panic(), "0123456789");
+
+        let res = client
+            .get("/")
+            .header("range", "bytes=2-4")
+            .header("if-range", "\"v1\"")
+            .send()
+            .now_or_panic();
+        res.assert_status(StatusCode::PARTIAL_CONTENT);
+
+        // malformed range is ignored.
+        let res = client.get("/").header("range", "bytes=4-2").send().now_or_panic();
+        res.assert_status(StatusCode::OK);
+    }
+}
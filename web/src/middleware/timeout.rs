@@ -0,0 +1,309 @@
+use std::{
+    convert::Infallible,
+    error, fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_core::stream::Stream;
+use pin_project_lite::pin_project;
+use tokio::time::{sleep_until, timeout_at, Instant, Sleep};
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, BuildService, Service},
+    },
+    handler::Responder,
+    http::StatusCode,
+    request::WebRequest,
+    response::WebResponse,
+};
+
+/// A middleware cancel the service it wraps when it does not produce response before deadline.
+///
+/// Timed out request is responded with `504 Gateway Timeout` by default. [Timeout::status] can be used
+/// to change the status code. e.g. `503 Service Unavailable`.
+///
+/// By default the deadline only covers producing response head. [Timeout::include_body] can be used to
+/// extend it to response body stream. When body stream passes the deadline it would yield an error and
+/// the connection would be closed by http layer.
+///
+/// Timeout can be applied to [App](crate::App) or to single route with [BuildServiceExt::enclosed].
+///
+/// [BuildServiceExt::enclosed]: crate::dev::service::BuildServiceExt::enclosed
+#[derive(Clone, Copy)]
+pub struct Timeout {
+    dur: Duration,
+    status: StatusCode,
+    body: bool,
+}
+
+impl Timeout {
+    /// Construct a new middleware with given duration as deadline of every request.
+    pub const fn new(dur: Duration) -> Self {
+        Self {
+            dur,
+            status: StatusCode::GATEWAY_TIMEOUT,
+            body: false,
+        }
+    }
+
+    /// Set status code of response when request timed out.
+    ///
+    /// Default to `504 Gateway Timeout`.
+    pub const fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Apply the deadline to response body stream too.
+    pub const fn include_body(mut self) -> Self {
+        self.body = true;
+        self
+    }
+}
+
+impl<S> BuildService<S> for Timeout {
+    type Service = TimeoutService<S>;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, service: S) -> Self::Future {
+        let config = *self;
+        async move { Ok(TimeoutService { service, config }) }
+    }
+}
+
+pub struct TimeoutService<S> {
+    service: S,
+    config: Timeout,
+}
+
+/// Error type of [TimeoutService].
+/// `First` variant contains [Elapsed] when request timed out.
+/// `Second` variant contains error returned by the service passed to Timeout.
+pub type TimeoutError<E> = PipelineE<Elapsed, E>;
+
+/// Request timed out before response is produced. Responds with status code configured by [Timeout].
+pub struct Elapsed {
+    status: StatusCode,
+}
+
+impl fmt::Debug for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Elapsed").field("status", &self.status).finish()
+    }
+}
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Request timed out")
+    }
+}
+
+impl error::Error for Elapsed {}
+
+impl<'r, S, C, B, ResB, Err> Service<WebRequest<'r, C, B>> for TimeoutService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = WebResponse<ResB>, Error = Err>,
+{
+    type Response = WebResponse<TimeoutBody<ResB>>;
+    type Error = TimeoutError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let deadline = Instant::now() + self.config.dur;
+
+            match timeout_at(deadline, self.service.call(req)).await {
+                Ok(res) => {
+                    let res = res.map_err(TimeoutError::Second)?;
+                    let sleep = self.config.body.then(|| Box::pin(sleep_until(deadline)));
+                    Ok(res.map(|body| TimeoutBody { body, sleep }))
+                }
+                Err(_) => Err(TimeoutError::First(Elapsed {
+                    status: self.config.status,
+                })),
+            }
+        }
+    }
+}
+
+impl<'r, S, C, B, ResB, Err, Rdy> ReadyService<WebRequest<'r, C, B>> for TimeoutService<S>
+where
+    C: 'static,
+    B: 'static,
+    S: for<'rs> ReadyService<WebRequest<'rs, C, B>, Response = WebResponse<ResB>, Error = Err, Ready = Rdy>,
+{
+    type Ready = Rdy;
+    type ReadyFuture<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::ReadyFuture<'_> {
+        async move { self.service.ready().await }
+    }
+}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for Elapsed {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = self.status;
+        async { res }
+    }
+}
+
+pin_project! {
+    /// Response body type of [TimeoutService].
+    pub struct TimeoutBody<B> {
+        #[pin]
+        body: B,
+        sleep: Option<Pin<Box<Sleep>>>,
+    }
+}
+
+/// Error type of [TimeoutBody].
+pub enum TimeoutBodyError<E> {
+    /// Body stream passed the deadline.
+    Elapsed,
+    /// Error from body stream.
+    Stream(E),
+}
+
+impl<E> fmt::Debug for TimeoutBodyError<E>
+where
+    E: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Elapsed => f.write_str("Elapsed"),
+            Self::Stream(ref e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+impl<E> fmt::Display for TimeoutBodyError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Elapsed => f.write_str("Response body timed out"),
+            Self::Stream(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E> error::Error for TimeoutBodyError<E> where E: fmt::Debug + fmt::Display {}
+
+impl<B, T, E> Stream for TimeoutBody<B>
+where
+    B: Stream<Item = Result<T, E>>,
+{
+    type Item = Result<T, TimeoutBodyError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if let Some(sleep) = this.sleep.as_mut() {
+            if sleep.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Some(Err(TimeoutBodyError::Elapsed)));
+            }
+        }
+
+        this.body
+            .poll_next(cx)
+            .map(|item| item.map(|res| res.map_err(TimeoutBodyError::Stream)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::future::poll_fn;
+
+    use tokio::time::sleep;
+    use xitca_unsafe_collection::pin;
+
+    use crate::{
+        handler::{handler_service, request::RequestRef},
+        test::TestClient,
+        App,
+    };
+
+    use super::*;
+
+    async fn handler(RequestRef(req): RequestRef<'_>) -> &'static str {
+        if req.uri().path() == "/slow" {
+            sleep(Duration::from_secs(5)).await;
+        }
+        "hello"
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        let client = TestClient::new(
+            App::new()
+                .at("/*path", handler_service(handler))
+                .enclosed(Timeout::new(Duration::from_millis(10)).status(StatusCode::SERVICE_UNAVAILABLE))
+                .finish(),
+        )
+        .await;
+
+        let res = client.get("/fast").send().await;
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string().await, "hello");
+
+        client
+            .get("/slow")
+            .send()
+            .await
+            .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn body_timeout() {
+        let body = TimeoutBody {
+            body: pending_body(),
+            sleep: Some(Box::pin(sleep(Duration::from_millis(10)))),
+        };
+        pin!(body);
+
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await.unwrap();
+        assert_eq!(chunk.unwrap(), "hello");
+
+        let err = poll_fn(|cx| body.as_mut().poll_next(cx)).await.unwrap().unwrap_err();
+        assert!(matches!(err, TimeoutBodyError::Elapsed));
+    }
+
+    // a stream yield one chunk and then pending forever.
+    fn pending_body() -> impl Stream<Item = Result<Bytes, Infallible>> {
+        struct Body(bool);
+
+        impl Stream for Body {
+            type Item = Result<Bytes, Infallible>;
+
+            fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                if self.0 {
+                    Poll::Pending
+                } else {
+                    self.0 = true;
+                    Poll::Ready(Some(Ok(Bytes::from_static(b"hello"))))
+                }
+            }
+        }
+
+        Body(false)
+    }
+}
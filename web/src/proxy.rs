@@ -0,0 +1,822 @@
+//! Reverse proxy service forwarding requests to upstream servers through [Client].
+
+use std::{
+    cell::{Cell, RefCell},
+    convert::Infallible,
+    error, fmt,
+    future::{poll_fn, Future},
+    mem,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_core::stream::Stream;
+use xitca_client::{error::Error as ClientError, Client};
+use xitca_http::{
+    body::{exact_body_hint, none_body_hint, BodySize},
+    request::RemoteAddr,
+};
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{BuildService, Service},
+    },
+    error::BodyError,
+    handler::Responder,
+    http::{
+        self,
+        header::{HeaderMap, HeaderName, HeaderValue, CONNECTION, CONTENT_LENGTH, FORWARDED, HOST, TRANSFER_ENCODING},
+        uri::{PathAndQuery, Uri},
+        StatusCode, Version,
+    },
+    request::WebRequest,
+    response::{ResponseBody, StreamBody, WebResponse},
+};
+
+/// Reverse proxy service factory.
+///
+/// Incoming request is forwarded to one of the upstream servers with its body streamed to
+/// upstream as it arrives. Upstream response body is streamed back in the same manner.
+///
+/// Hop-by-hop headers are stripped from both directions and `Forwarded`/`X-Forwarded-*`
+/// headers are added to forwarded request.
+///
+/// # Example:
+/// ```rust
+/// # use std::time::Duration;
+/// # use xitca_web::{proxy::ReverseProxy, App, HttpServer};
+/// # fn main() -> std::io::Result<()> {
+/// HttpServer::new(|| {
+///     App::new()
+///         .at(
+///             "/*path",
+///             ReverseProxy::new(["http://127.0.0.1:8081", "http://127.0.0.1:8082"])
+///                 .least_connections()
+///                 .max_fails(5)
+///                 .fail_timeout(Duration::from_secs(10)),
+///         )
+///         .finish()
+/// })
+/// .bind("127.0.0.1:8080")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ReverseProxy {
+    upstreams: Vec<Uri>,
+    balance: Balance,
+    max_fails: usize,
+    fail_timeout: Duration,
+    proto: &'static str,
+    client: fn() -> Client,
+}
+
+#[derive(Clone, Copy)]
+enum Balance {
+    RoundRobin,
+    LeastConnections,
+}
+
+impl ReverseProxy {
+    /// Construct a new reverse proxy with given upstream uris.
+    ///
+    /// The path of upstream uri is used as prefix of forwarded request's path.
+    ///
+    /// # Panics:
+    /// When receive empty upstreams or any of upstream is not a valid absolute uri.
+    pub fn new<I>(upstreams: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let upstreams = upstreams
+            .into_iter()
+            .map(|uri| {
+                let uri = uri.as_ref();
+                let parsed = uri
+                    .parse::<Uri>()
+                    .unwrap_or_else(|e| panic!("upstream: {uri} is not a valid uri: {e}"));
+                assert!(
+                    parsed.scheme().is_some() && parsed.authority().is_some(),
+                    "upstream: {uri} must be an absolute uri with scheme and authority"
+                );
+                parsed
+            })
+            .collect::<Vec<_>>();
+
+        assert!(!upstreams.is_empty(), "ReverseProxy must have at least one upstream");
+
+        Self {
+            upstreams,
+            balance: Balance::RoundRobin,
+            max_fails: 3,
+            fail_timeout: Duration::from_secs(10),
+            proto: "http",
+            client: Client::new,
+        }
+    }
+
+    /// Pick upstream in a rotating order.
+    ///
+    /// This is the default upstream selection strategy.
+    pub fn round_robin(mut self) -> Self {
+        self.balance = Balance::RoundRobin;
+        self
+    }
+
+    /// Pick upstream with the least in-flight requests.
+    ///
+    /// A request is treated as in-flight until its response body is fully streamed.
+    pub fn least_connections(mut self) -> Self {
+        self.balance = Balance::LeastConnections;
+        self
+    }
+
+    /// Set number of consecutive failed attempts for an upstream to be marked as unhealthy.
+    ///
+    /// A failed attempt is a transport error when connecting/communicating with upstream.
+    /// Any response from upstream regardless of its status code is treated as success.
+    ///
+    /// Default to 3. Passing 0 would disable passive health check.
+    pub fn max_fails(mut self, num: usize) -> Self {
+        self.max_fails = num;
+        self
+    }
+
+    /// Set duration an unhealthy upstream would be excluded from upstream selection.
+    ///
+    /// Default to 10 seconds.
+    pub fn fail_timeout(mut self, dur: Duration) -> Self {
+        self.fail_timeout = dur;
+        self
+    }
+
+    /// Set scheme of incoming connections used for `X-Forwarded-Proto` and `Forwarded` headers.
+    ///
+    /// Http/1 request does not carry the scheme it's served on and this value is used for it.
+    /// Request with scheme in its uri (Http/2, Http/3 and absolute form Http/1 request) always
+    /// forward its own scheme.
+    ///
+    /// Default to `"http"`. Server listening on tls connection should set it to `"https"`.
+    pub fn forwarded_proto(mut self, proto: &'static str) -> Self {
+        self.proto = proto;
+        self
+    }
+
+    /// Set constructor of [Client] used for forwarding requests.
+    ///
+    /// The constructor is called once for every built [ReverseProxyService]. (It would usually
+    /// be once for each server worker thread.)
+    ///
+    /// Default to [Client::new].
+    pub fn client(mut self, client: fn() -> Client) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+impl BuildService for ReverseProxy {
+    type Service = ReverseProxyService;
+    type Error = Infallible;
+    type Future = impl Future<Output = Result<Self::Service, Self::Error>>;
+
+    fn build(&self, _: ()) -> Self::Future {
+        let this = self.clone();
+        async move {
+            let client = Rc::new((this.client)());
+
+            let upstreams = this
+                .upstreams
+                .into_iter()
+                .map(|uri| Rc::new(Upstream::new(uri)))
+                .collect();
+
+            Ok(ReverseProxyService {
+                client,
+                upstreams,
+                balance: this.balance,
+                next: Cell::new(0),
+                max_fails: this.max_fails,
+                fail_timeout: this.fail_timeout,
+                proto: this.proto,
+            })
+        }
+    }
+}
+
+pub struct ReverseProxyService {
+    client: Rc<Client>,
+    upstreams: Vec<Rc<Upstream>>,
+    balance: Balance,
+    next: Cell<usize>,
+    max_fails: usize,
+    fail_timeout: Duration,
+    proto: &'static str,
+}
+
+struct Upstream {
+    uri: Uri,
+    active: Cell<usize>,
+    fails: Cell<usize>,
+    down_until: Cell<Option<Instant>>,
+}
+
+impl Upstream {
+    fn new(uri: Uri) -> Self {
+        Self {
+            uri,
+            active: Cell::new(0),
+            fails: Cell::new(0),
+            down_until: Cell::new(None),
+        }
+    }
+
+    fn is_available(&self, now: Instant) -> bool {
+        self.down_until.get().map(|until| now >= until).unwrap_or(true)
+    }
+}
+
+impl ReverseProxyService {
+    fn select(&self, now: Instant) -> Option<&Rc<Upstream>> {
+        match self.balance {
+            Balance::RoundRobin => {
+                let len = self.upstreams.len();
+                let start = self.next.get();
+                self.next.set(start.wrapping_add(1));
+                (0..len)
+                    .map(|i| &self.upstreams[start.wrapping_add(i) % len])
+                    .find(|upstream| upstream.is_available(now))
+            }
+            Balance::LeastConnections => self
+                .upstreams
+                .iter()
+                .filter(|upstream| upstream.is_available(now))
+                .min_by_key(|upstream| upstream.active.get()),
+        }
+    }
+
+    fn report(&self, upstream: &Upstream, success: bool) {
+        if success {
+            upstream.fails.set(0);
+            upstream.down_until.set(None);
+        } else if self.max_fails > 0 {
+            let fails = upstream.fails.get() + 1;
+            if fails >= self.max_fails {
+                upstream.fails.set(0);
+                upstream.down_until.set(Some(Instant::now() + self.fail_timeout));
+            } else {
+                upstream.fails.set(fails);
+            }
+        }
+    }
+}
+
+impl<'r, C, B, E> Service<WebRequest<'r, C, B>> for ReverseProxyService
+where
+    C: 'static,
+    B: Stream<Item = Result<Bytes, E>> + Default + Unpin + 'static,
+    BodyError: From<E>,
+{
+    type Response = WebResponse;
+    type Error = ProxyError;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call(&self, mut req: WebRequest<'r, C, B>) -> Self::Future<'_> {
+        async move {
+            let upstream = self.select(Instant::now()).ok_or(ProxyError::NoUpstream)?;
+
+            let head = req.req();
+            let uri = upstream_uri(&upstream.uri, head.uri())?;
+            let method = head.method().clone();
+            let remote_addr = *head.remote_addr();
+            let proto = head.uri().scheme_str().unwrap_or(self.proto).to_owned();
+            let version = head.version();
+
+            let mut headers = mem::take(req.req_mut().headers_mut());
+            let size = request_body_size(version, &headers, req.body_get_mut());
+            let host = headers
+                .remove(HOST)
+                .or_else(|| req.req().uri().authority().and_then(|a| a.as_str().parse().ok()));
+
+            remove_hop_headers(&mut headers);
+            forwarded_headers(&mut headers, &remote_addr, host.as_ref(), &proto);
+
+            let mut forward = http::Request::new(ForwardBody {
+                body: req.take_body_mut(),
+                size,
+            });
+            *forward.method_mut() = method;
+            *forward.uri_mut() = uri;
+            *forward.headers_mut() = headers;
+
+            let guard = ActiveGuard::new(upstream);
+
+            let mut body = ProxyBody::new(self.client.clone(), forward, guard);
+
+            match body.head().await {
+                Ok(mut parts) => {
+                    self.report(upstream, true);
+
+                    remove_hop_headers(&mut parts.headers);
+
+                    let body = Box::pin(body) as StreamBody;
+
+                    Ok(WebResponse::from_parts(parts, ResponseBody::stream(body)))
+                }
+                Err(e) => {
+                    self.report(upstream, false);
+                    Err(ProxyError::Client(e))
+                }
+            }
+        }
+    }
+}
+
+// keep track of in-flight request count of upstream.
+struct ActiveGuard(Rc<Upstream>);
+
+impl ActiveGuard {
+    fn new(upstream: &Rc<Upstream>) -> Self {
+        upstream.active.set(upstream.active.get() + 1);
+        Self(upstream.clone())
+    }
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.0.active.set(self.0.active.get() - 1);
+    }
+}
+
+// request body forwarded to upstream. size hint is overridden to keep the framing of
+// original request.
+struct ForwardBody<B> {
+    body: B,
+    size: BodySize,
+}
+
+impl<B> Stream for ForwardBody<B>
+where
+    B: Stream + Unpin,
+{
+    type Item = B::Item;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().body).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.size {
+            BodySize::None => none_body_hint(),
+            BodySize::Sized(size) => exact_body_hint(size),
+            BodySize::Stream => (0, None),
+        }
+    }
+}
+
+// response body streamed back from upstream.
+//
+// client response body borrows from client. forwarding is driven by a future owning both of
+// them and its outputs are passed through a shared slot one at a time.
+struct ProxyBody {
+    driver: Option<Pin<Box<dyn Future<Output = ()>>>>,
+    slot: Slot,
+    size_hint: (usize, Option<usize>),
+}
+
+type Slot = Rc<RefCell<Option<Message>>>;
+
+enum Message {
+    Head(Result<(http::response::Parts, (usize, Option<usize>)), ClientError>),
+    Chunk(Result<Bytes, BodyError>),
+}
+
+impl ProxyBody {
+    fn new<B, E>(client: Rc<Client>, req: http::Request<ForwardBody<B>>, guard: ActiveGuard) -> Self
+    where
+        B: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+        BodyError: From<E>,
+    {
+        let slot = Slot::default();
+        let tx = slot.clone();
+
+        let driver = async move {
+            let _guard = guard;
+
+            let res = match client.request(req).send().await {
+                Ok(res) => res,
+                Err(e) => return send(&tx, Message::Head(Err(e))).await,
+            };
+
+            let (parts, mut body) = res.into_inner().into_parts();
+            send(&tx, Message::Head(Ok((parts, body.size_hint())))).await;
+
+            while let Some(chunk) = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await {
+                send(&tx, Message::Chunk(chunk)).await;
+            }
+        };
+
+        Self {
+            driver: Some(Box::pin(driver)),
+            slot,
+            size_hint: (0, None),
+        }
+    }
+
+    async fn head(&mut self) -> Result<http::response::Parts, ClientError> {
+        match poll_fn(|cx| self.poll_message(cx)).await {
+            Some(Message::Head(Ok((parts, size_hint)))) => {
+                self.size_hint = size_hint;
+                Ok(parts)
+            }
+            Some(Message::Head(Err(e))) => Err(e),
+            _ => unreachable!("ProxyBody must yield response head before body"),
+        }
+    }
+
+    fn poll_message(&mut self, cx: &mut Context<'_>) -> Poll<Option<Message>> {
+        // driver is always polled before taking message so it can resume with an empty slot.
+        if let Some(driver) = self.driver.as_mut() {
+            if driver.as_mut().poll(cx).is_ready() {
+                self.driver = None;
+            }
+        }
+
+        match self.slot.borrow_mut().take() {
+            Some(msg) => Poll::Ready(Some(msg)),
+            None if self.driver.is_none() => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+// put message into slot and yield to ProxyBody.
+async fn send(slot: &Slot, msg: Message) {
+    *slot.borrow_mut() = Some(msg);
+
+    // ProxyBody would take the message and poll the driver again. no waker is needed.
+    let mut yielded = false;
+    poll_fn(|_| {
+        if mem::replace(&mut yielded, true) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+impl Stream for ProxyBody {
+    type Item = Result<Bytes, BodyError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.get_mut().poll_message(cx) {
+            Poll::Ready(Some(Message::Chunk(chunk))) => Poll::Ready(Some(chunk)),
+            Poll::Ready(Some(Message::Head(_))) => unreachable!("response head must be yielded once"),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.size_hint
+    }
+}
+
+/// Error type of [ReverseProxyService].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ProxyError {
+    /// All upstreams are marked as unhealthy.
+    NoUpstream,
+    /// Upstream uri can not be constructed from request.
+    InvalidUri,
+    /// Error when forwarding request to upstream.
+    Client(ClientError),
+}
+
+impl fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::NoUpstream => write!(f, "No upstream is available"),
+            Self::InvalidUri => write!(f, "Upstream uri is invalid"),
+            Self::Client(ref e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl error::Error for ProxyError {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for ProxyError {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = match self {
+            Self::Client(ClientError::Timeout(_)) => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::BAD_GATEWAY,
+        };
+        async { res }
+    }
+}
+
+fn upstream_uri(base: &Uri, uri: &Uri) -> Result<Uri, ProxyError> {
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    let prefix = base.path().trim_end_matches('/');
+
+    let path = PathAndQuery::try_from(format!("{prefix}{path}")).map_err(|_| ProxyError::InvalidUri)?;
+
+    let mut parts = base.clone().into_parts();
+    parts.path_and_query = Some(path);
+
+    Uri::from_parts(parts).map_err(|_| ProxyError::InvalidUri)
+}
+
+fn request_body_size<B>(version: Version, headers: &HeaderMap, body: &B) -> BodySize
+where
+    B: Stream,
+{
+    match BodySize::from_stream(body) {
+        BodySize::Stream => {}
+        size => return size,
+    }
+
+    if headers.contains_key(TRANSFER_ENCODING) {
+        return BodySize::Stream;
+    }
+
+    match headers.get(CONTENT_LENGTH).and_then(|v| v.to_str().ok()) {
+        Some(len) => len.parse().map(BodySize::Sized).unwrap_or(BodySize::Stream),
+        // http/1 request without framing headers has no body.
+        None if version < Version::HTTP_2 => BodySize::None,
+        // http/2 and http/3 request body can be streamed without content-length header.
+        None => BodySize::Stream,
+    }
+}
+
+const HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+fn remove_hop_headers(headers: &mut HeaderMap) {
+    // header names listed in connection header are hop-by-hop.
+    let listed = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect::<Vec<_>>();
+
+    for name in listed {
+        headers.remove(name);
+    }
+
+    for name in HOP_HEADERS {
+        headers.remove(name);
+    }
+}
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+
+fn forwarded_headers(headers: &mut HeaderMap, remote_addr: &RemoteAddr, host: Option<&HeaderValue>, proto: &str) {
+    let (ip, node) = match *remote_addr {
+        RemoteAddr::V4(ip, _) => (Some(ip.to_string()), ip.to_string()),
+        RemoteAddr::V6(ip, _) => (Some(ip.to_string()), format!("\"[{ip}]\"")),
+        RemoteAddr::None => (None, String::from("unknown")),
+    };
+
+    if let Some(ip) = ip {
+        let value = match headers.get(X_FORWARDED_FOR).and_then(|v| v.to_str().ok()) {
+            Some(prev) => format!("{prev}, {ip}"),
+            None => ip,
+        };
+        insert_str(headers, X_FORWARDED_FOR, value);
+    }
+
+    insert_str(headers, X_FORWARDED_PROTO, proto.to_owned());
+
+    let mut element = format!("for={node};proto={proto}");
+
+    if let Some(host) = host {
+        headers.insert(X_FORWARDED_HOST, host.clone());
+        if let Ok(host) = host.to_str() {
+            element.push_str(&format!(";host=\"{host}\""));
+        }
+    }
+
+    let value = match headers.get(FORWARDED).and_then(|v| v.to_str().ok()) {
+        Some(prev) => format!("{prev}, {element}"),
+        None => element,
+    };
+    insert_str(headers, FORWARDED, value);
+}
+
+fn insert_str<K>(headers: &mut HeaderMap, name: K, value: String)
+where
+    K: http::header::IntoHeaderName,
+{
+    if let Ok(value) = HeaderValue::try_from(value) {
+        headers.insert(name, value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+    use xitca_http::{body::Once, Request};
+
+    use crate::{request::RequestBody, test::collect_string_body, App};
+
+    use super::*;
+
+    #[test]
+    fn hop_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONNECTION, HeaderValue::from_static("keep-alive, x-custom"));
+        headers.insert("keep-alive", HeaderValue::from_static("timeout=5"));
+        headers.insert("x-custom", HeaderValue::from_static("996"));
+        headers.insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+        headers.insert("x-keep", HeaderValue::from_static("251"));
+
+        remove_hop_headers(&mut headers);
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.get("x-keep").unwrap(), "251");
+    }
+
+    #[test]
+    fn forwarded() {
+        let mut headers = HeaderMap::new();
+        headers.insert(X_FORWARDED_FOR, HeaderValue::from_static("10.0.0.1"));
+
+        let host = HeaderValue::from_static("example.com:8080");
+        let addr = RemoteAddr::V4(Ipv4Addr::new(192, 168, 0, 1), 996);
+        forwarded_headers(&mut headers, &addr, Some(&host), "http");
+
+        assert_eq!(headers.get(X_FORWARDED_FOR).unwrap(), "10.0.0.1, 192.168.0.1");
+        assert_eq!(headers.get(X_FORWARDED_PROTO).unwrap(), "http");
+        assert_eq!(headers.get(X_FORWARDED_HOST).unwrap(), "example.com:8080");
+        assert_eq!(
+            headers.get(FORWARDED).unwrap(),
+            "for=192.168.0.1;proto=http;host=\"example.com:8080\""
+        );
+
+        let mut headers = HeaderMap::new();
+        let addr = RemoteAddr::V6(Ipv6Addr::LOCALHOST, 996);
+        forwarded_headers(&mut headers, &addr, None, "https");
+
+        assert_eq!(headers.get(X_FORWARDED_FOR).unwrap(), "::1");
+        assert_eq!(headers.get(FORWARDED).unwrap(), "for=\"[::1]\";proto=https");
+    }
+
+    #[test]
+    fn uri() {
+        let base = Uri::from_static("http://127.0.0.1:8080/api/");
+        let uri = upstream_uri(&base, &Uri::from_static("/users?id=1")).unwrap();
+        assert_eq!(uri, "http://127.0.0.1:8080/api/users?id=1");
+
+        let base = Uri::from_static("http://127.0.0.1:8080");
+        let uri = upstream_uri(&base, &Uri::from_static("/")).unwrap();
+        assert_eq!(uri, "http://127.0.0.1:8080/");
+    }
+
+    #[test]
+    fn body_size() {
+        let body = RequestBody::default();
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_body_size(Version::HTTP_11, &headers, &body), BodySize::None);
+        assert_eq!(request_body_size(Version::HTTP_2, &headers, &body), BodySize::Stream);
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("996"));
+        assert_eq!(
+            request_body_size(Version::HTTP_2, &headers, &body),
+            BodySize::Sized(996)
+        );
+
+        headers.insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+        assert_eq!(request_body_size(Version::HTTP_11, &headers, &body), BodySize::Stream);
+
+        let body = Once::new(Bytes::from_static(b"996"));
+        assert_eq!(
+            request_body_size(Version::HTTP_2, &HeaderMap::new(), &body),
+            BodySize::Sized(3)
+        );
+    }
+
+    #[tokio::test]
+    async fn select() {
+        let service = ReverseProxy::new(["http://127.0.0.1:1", "http://127.0.0.1:2"])
+            .max_fails(2)
+            .build(())
+            .await
+            .unwrap();
+
+        let now = Instant::now();
+
+        let a = service.select(now).unwrap().clone();
+        let b = service.select(now).unwrap().clone();
+        assert_ne!(a.uri, b.uri);
+
+        service.report(&a, false);
+        assert!(a.is_available(now));
+        service.report(&a, false);
+        assert!(!a.is_available(Instant::now()));
+
+        // unhealthy upstream is skipped.
+        assert_eq!(service.select(Instant::now()).unwrap().uri, b.uri);
+        assert_eq!(service.select(Instant::now()).unwrap().uri, b.uri);
+
+        service.report(&b, false);
+        service.report(&b, false);
+        assert!(service.select(Instant::now()).is_none());
+    }
+
+    #[tokio::test]
+    async fn select_least_connections() {
+        let service = ReverseProxy::new(["http://127.0.0.1:1", "http://127.0.0.1:2"])
+            .least_connections()
+            .build(())
+            .await
+            .unwrap();
+
+        let a = service.select(Instant::now()).unwrap().clone();
+        let _guard = ActiveGuard::new(&a);
+
+        let b = service.select(Instant::now()).unwrap().clone();
+        assert_ne!(a.uri, b.uri);
+
+        drop(_guard);
+        assert_eq!(a.active.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn forward() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let upstream = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let n = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nconnection: x-custom\r\nx-custom: 996\r\ncontent-length: 5\r\n\r\nhello",
+                )
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_lowercase()
+        });
+
+        let service = App::new()
+            .at("/*path", ReverseProxy::new([format!("http://{addr}")]))
+            .finish()
+            .build(())
+            .await
+            .unwrap();
+
+        let mut req =
+            Request::with_remote_addr(RequestBody::default(), RemoteAddr::V4(Ipv4Addr::new(10, 0, 0, 1), 996));
+        *req.uri_mut() = Uri::from_static("/foo?bar=1");
+        req.headers_mut().insert(HOST, HeaderValue::from_static("example.com"));
+        req.headers_mut()
+            .insert(CONNECTION, HeaderValue::from_static("keep-alive"));
+
+        let res = service.call(req).await.ok().unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key("x-custom"));
+
+        let body = collect_string_body(res.into_body()).await.unwrap();
+        assert_eq!(body, "hello");
+
+        let forwarded = upstream.await.unwrap();
+        assert!(forwarded.starts_with("get /foo?bar=1 http/1.1\r\n"));
+        assert!(forwarded.contains(&format!("host: {addr}\r\n")));
+        assert!(forwarded.contains("x-forwarded-for: 10.0.0.1\r\n"));
+        assert!(forwarded.contains("x-forwarded-host: example.com\r\n"));
+        assert!(forwarded.contains("forwarded: for=10.0.0.1;proto=http;host=\"example.com\"\r\n"));
+        assert!(!forwarded.contains("transfer-encoding"));
+        assert!(!forwarded.contains("keep-alive"));
+    }
+}
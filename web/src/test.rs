@@ -1,9 +1,21 @@
-use std::future::poll_fn;
+use std::{cell::RefCell, fmt, future::poll_fn, marker::PhantomData, net::SocketAddr, ops::Deref};
 
 use futures_core::stream::Stream;
+use xitca_http::{
+    body::Once,
+    http::{
+        self,
+        header::{HeaderName, HeaderValue, CONTENT_TYPE, COOKIE, SET_COOKIE},
+        Method, Response, StatusCode,
+    },
+    request::{RemoteAddr, Request},
+};
 use xitca_unsafe_collection::pin;
 
-use crate::dev::service::pipeline::PipelineE;
+use crate::dev::{
+    bytes::{BufMut, Bytes, BytesMut},
+    service::{pipeline::PipelineE, BuildService, Service},
+};
 
 /// Collect request or response body to Vec.
 pub async fn collect_body<B, T, E>(body: B) -> Result<Vec<u8>, E>
@@ -33,3 +45,555 @@ where
     let body = collect_body(body).await.map_err(CollectStringError::Second)?;
     String::from_utf8(body).map_err(CollectStringError::First)
 }
+
+/// Default request body type used by [TestClient].
+pub type TestBody = Once<Bytes>;
+
+/// In process client for testing App without binding to any socket.
+///
+/// Cookies set by responses are kept and sent with following requests.
+///
+/// Request body type is [TestBody] by default. Other type constructable from [Bytes] can be
+/// used with [TestClient::with_body].
+///
+/// # Example:
+/// ```rust
+/// # use xitca_web::{handler::handler_service, http::StatusCode, test::TestClient, App};
+/// # async fn test() {
+/// let client = TestClient::new(App::new().at("/", handler_service(|| async { "hello" })).finish()).await;
+///
+/// let res = client.get("/").header("x-foo", "bar").send().await;
+/// res.assert_status(StatusCode::OK);
+/// assert_eq!(res.string().await, "hello");
+/// # }
+/// ```
+pub struct TestClient<S, B = TestBody> {
+    service: S,
+    cookies: RefCell<Vec<(String, String)>>,
+    _body: PhantomData<B>,
+}
+
+impl<S> TestClient<S> {
+    /// Construct client from service factory. Typically the output of [App::finish](crate::App::finish).
+    ///
+    /// # Panic:
+    ///
+    /// When service factory failed to build service.
+    pub async fn new<F>(factory: F) -> Self
+    where
+        F: BuildService<Service = S>,
+        F::Error: fmt::Debug,
+    {
+        Self::with_body(factory).await
+    }
+}
+
+impl<S, B> TestClient<S, B> {
+    /// Construct client from service factory with given request body type.
+    ///
+    /// # Example:
+    /// ```rust
+    /// # use xitca_web::{handler::handler_service, test::TestClient, App};
+    /// # use xitca_web::dev::bytes::Bytes;
+    /// # use xitca_http::body::Once;
+    /// # async fn test() {
+    /// let app = App::new().at("/", handler_service(|| async { "hello" })).finish();
+    /// let client = TestClient::<_, Once<Bytes>>::with_body(app).await;
+    /// let res = client.get("/").send().await;
+    /// assert_eq!(res.status(), 200);
+    /// # }
+    /// ```
+    ///
+    /// # Panic:
+    ///
+    /// When service factory failed to build service.
+    pub async fn with_body<F>(factory: F) -> Self
+    where
+        F: BuildService<Service = S>,
+        F::Error: fmt::Debug,
+    {
+        let service = factory.build(()).await.expect("TestClient failed to build service");
+        Self {
+            service,
+            cookies: RefCell::new(Vec::new()),
+            _body: PhantomData,
+        }
+    }
+
+    /// Start building a request with given method and uri.
+    pub fn request(&self, method: Method, uri: &str) -> TestRequest<'_, S, B> {
+        let mut req = Request::new(());
+        *req.method_mut() = method;
+        *req.uri_mut() = uri.parse().expect("TestRequest uri is not valid");
+
+        TestRequest {
+            client: self,
+            req,
+            body: Bytes::new(),
+        }
+    }
+
+    pub fn get(&self, uri: &str) -> TestRequest<'_, S, B> {
+        self.request(Method::GET, uri)
+    }
+
+    pub fn post(&self, uri: &str) -> TestRequest<'_, S, B> {
+        self.request(Method::POST, uri)
+    }
+
+    pub fn put(&self, uri: &str) -> TestRequest<'_, S, B> {
+        self.request(Method::PUT, uri)
+    }
+
+    pub fn patch(&self, uri: &str) -> TestRequest<'_, S, B> {
+        self.request(Method::PATCH, uri)
+    }
+
+    pub fn delete(&self, uri: &str) -> TestRequest<'_, S, B> {
+        self.request(Method::DELETE, uri)
+    }
+
+    pub fn head(&self, uri: &str) -> TestRequest<'_, S, B> {
+        self.request(Method::HEAD, uri)
+    }
+
+    pub fn options(&self, uri: &str) -> TestRequest<'_, S, B> {
+        self.request(Method::OPTIONS, uri)
+    }
+
+    /// Get value of cookie stored by client.
+    pub fn cookie(&self, name: &str) -> Option<String> {
+        self.cookies
+            .borrow()
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Remove all cookies stored by client.
+    pub fn clear_cookies(&self) {
+        self.cookies.borrow_mut().clear();
+    }
+
+    fn cookie_header(&self) -> Option<HeaderValue> {
+        let cookies = self.cookies.borrow();
+        if cookies.is_empty() {
+            return None;
+        }
+
+        let value = cookies
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        HeaderValue::try_from(value).ok()
+    }
+
+    fn store_cookies<ResB>(&self, res: &Response<ResB>) {
+        let mut cookies = self.cookies.borrow_mut();
+
+        for value in res.headers().get_all(SET_COOKIE) {
+            let mut attrs = match value.to_str() {
+                Ok(value) => value.split(';'),
+                Err(_) => continue,
+            };
+
+            let (name, value) = match attrs.next().and_then(|pair| pair.split_once('=')) {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => continue,
+            };
+
+            let expired = attrs.any(|attr| match attr.split_once('=') {
+                Some((key, age)) if key.trim().eq_ignore_ascii_case("max-age") => {
+                    age.trim().parse::<i64>().map(|age| age <= 0).unwrap_or(false)
+                }
+                _ => false,
+            });
+
+            cookies.retain(|(n, _)| n != name);
+
+            if !expired {
+                cookies.push((name.to_owned(), value.to_owned()));
+            }
+        }
+    }
+}
+
+/// Request builder of [TestClient].
+pub struct TestRequest<'a, S, B = TestBody> {
+    client: &'a TestClient<S, B>,
+    req: Request<()>,
+    body: Bytes,
+}
+
+impl<'a, S, B> TestRequest<'a, S, B> {
+    /// Append a header to request.
+    ///
+    /// # Panic:
+    ///
+    /// When header name or value is not valid.
+    pub fn header<K, V>(mut self, name: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: fmt::Debug,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: fmt::Debug,
+    {
+        let name = HeaderName::try_from(name).expect("TestRequest header name is not valid");
+        let value = HeaderValue::try_from(value).expect("TestRequest header value is not valid");
+        self.req.headers_mut().append(name, value);
+        self
+    }
+
+    /// Set remote address of request.
+    pub fn remote_addr(mut self, addr: SocketAddr) -> Self {
+        let (parts, body) = self.req.into_parts();
+        self.req = Request::from_http(http::Request::from_parts(parts, body), RemoteAddr::from(addr));
+        self
+    }
+
+    /// Set request body.
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    #[cfg(feature = "json")]
+    /// Serialize value to json and use it as request body. `Content-Type` header is set to `application/json`.
+    ///
+    /// # Panic:
+    ///
+    /// When serialization failed.
+    pub fn json<T>(mut self, value: &T) -> Self
+    where
+        T: serde::Serialize,
+    {
+        let body = serde_json::to_vec(value).expect("TestRequest failed to serialize json body");
+        self.req
+            .headers_mut()
+            .insert(CONTENT_TYPE, http::const_header_value::JSON);
+        self.body(body)
+    }
+
+    #[cfg(feature = "urlencoded")]
+    /// Serialize value to url encoded form and use it as request body.
+    /// `Content-Type` header is set to `application/x-www-form-urlencoded`.
+    ///
+    /// # Panic:
+    ///
+    /// When serialization failed.
+    pub fn form<T>(mut self, value: &T) -> Self
+    where
+        T: serde::Serialize,
+    {
+        let body = serde_urlencoded::to_string(value).expect("TestRequest failed to serialize form body");
+        self.req.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        self.body(body)
+    }
+
+    /// Use multipart form as request body. `Content-Type` header is set to `multipart/form-data`.
+    pub fn multipart(mut self, multipart: Multipart) -> Self {
+        let (content_type, body) = multipart.finish();
+        self.req.headers_mut().insert(CONTENT_TYPE, content_type);
+        self.body(body)
+    }
+
+    /// Send request to service and wait for response.
+    ///
+    /// # Panic:
+    ///
+    /// When service returns error.
+    pub async fn send<ResB>(self) -> TestResponse<ResB>
+    where
+        S: Service<Request<B>, Response = Response<ResB>>,
+        B: From<Bytes>,
+    {
+        let TestRequest { client, mut req, body } = self;
+
+        if !req.headers().contains_key(COOKIE) {
+            if let Some(cookie) = client.cookie_header() {
+                req.headers_mut().insert(COOKIE, cookie);
+            }
+        }
+
+        let (req, _) = req.replace_body(B::from(body));
+
+        let res = match client.service.call(req).await {
+            Ok(res) => res,
+            Err(_) => panic!("TestClient service returned error"),
+        };
+
+        client.store_cookies(&res);
+
+        TestResponse { res }
+    }
+}
+
+/// Builder type for `multipart/form-data` request body.
+pub struct Multipart {
+    body: BytesMut,
+}
+
+const BOUNDARY: &str = "xitca-web-test-boundary";
+
+impl Default for Multipart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Multipart {
+    pub fn new() -> Self {
+        Self { body: BytesMut::new() }
+    }
+
+    /// Add a text field.
+    pub fn text(mut self, name: &str, value: &str) -> Self {
+        self.part(format_args!("Content-Disposition: form-data; name=\"{name}\"\r\n"));
+        self.body.put_slice(value.as_bytes());
+        self.body.put_slice(b"\r\n");
+        self
+    }
+
+    /// Add a file field with given file name, content type and content.
+    pub fn file(mut self, name: &str, file_name: &str, content_type: &str, content: impl AsRef<[u8]>) -> Self {
+        self.part(format_args!(
+            "Content-Disposition: form-data; name=\"{name}\"; filename=\"{file_name}\"\r\nContent-Type: {content_type}\r\n"
+        ));
+        self.body.put_slice(content.as_ref());
+        self.body.put_slice(b"\r\n");
+        self
+    }
+
+    fn part(&mut self, headers: fmt::Arguments<'_>) {
+        self.body.put_slice(format!("--{BOUNDARY}\r\n{headers}\r\n").as_bytes());
+    }
+
+    fn finish(mut self) -> (HeaderValue, Bytes) {
+        self.body.put_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+        let content_type = HeaderValue::try_from(format!("multipart/form-data; boundary={BOUNDARY}")).unwrap();
+        (content_type, self.body.freeze())
+    }
+}
+
+/// Response type of [TestClient]. Derefs to [Response] type.
+pub struct TestResponse<B> {
+    res: Response<B>,
+}
+
+impl<B> Deref for TestResponse<B> {
+    type Target = Response<B>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.res
+    }
+}
+
+impl<B> TestResponse<B> {
+    /// Assert response status code.
+    pub fn assert_status(&self, status: StatusCode) -> &Self {
+        assert_eq!(self.res.status(), status, "unexpected response status code");
+        self
+    }
+
+    /// Assert response has header with given name and value.
+    pub fn assert_header(&self, name: impl AsRef<str>, value: &str) -> &Self {
+        let name = name.as_ref();
+        let header = self
+            .res
+            .headers()
+            .get(name)
+            .unwrap_or_else(|| panic!("response header {name} is missing"));
+        assert_eq!(header, value, "unexpected value of response header {name}");
+        self
+    }
+
+    /// Consume self and return the inner response type.
+    pub fn into_inner(self) -> Response<B> {
+        self.res
+    }
+
+    /// Consume self and return response body for streaming.
+    pub fn into_body(self) -> B {
+        self.res.into_body()
+    }
+
+    /// Collect response body as Vec<u8>.
+    ///
+    /// # Panic:
+    ///
+    /// When response body yields error.
+    pub async fn body<T, E>(self) -> Vec<u8>
+    where
+        B: Stream<Item = Result<T, E>>,
+        T: AsRef<[u8]>,
+        E: fmt::Debug,
+    {
+        collect_body(self.res.into_body())
+            .await
+            .expect("response body yields error")
+    }
+
+    /// Collect response body as String.
+    ///
+    /// # Panic:
+    ///
+    /// When response body yields error or it's not valid utf-8.
+    pub async fn string<T, E>(self) -> String
+    where
+        B: Stream<Item = Result<T, E>>,
+        T: AsRef<[u8]>,
+        E: fmt::Debug,
+    {
+        String::from_utf8(self.body().await).expect("response body is not valid utf-8")
+    }
+
+    #[cfg(feature = "json")]
+    /// Collect response body and deserialize it from json.
+    ///
+    /// # Panic:
+    ///
+    /// When response body yields error or deserialization failed.
+    pub async fn json<J, T, E>(self) -> J
+    where
+        J: serde::de::DeserializeOwned,
+        B: Stream<Item = Result<T, E>>,
+        T: AsRef<[u8]>,
+        E: fmt::Debug,
+    {
+        serde_json::from_slice(&self.body().await).expect("response body is not valid json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::handler_service,
+        http::const_header_value::TEXT_UTF8,
+        request::WebRequest,
+        response::{ResponseBody, WebResponse},
+        route::get,
+        App,
+    };
+
+    use super::*;
+
+    #[test]
+    fn request_and_cookie() {
+        async fn login(req: &WebRequest<'_, (), TestBody>) -> WebResponse {
+            let mut res = WebResponse::new(ResponseBody::from(format!("{:?}", req.req().remote_addr())));
+            res.headers_mut().insert(CONTENT_TYPE, TEXT_UTF8);
+            res.headers_mut()
+                .append(SET_COOKIE, HeaderValue::from_static("id=996; Path=/; HttpOnly"));
+            res.headers_mut()
+                .append(SET_COOKIE, HeaderValue::from_static("lang=en"));
+            res
+        }
+
+        async fn session(req: &WebRequest<'_, (), TestBody>) -> String {
+            let cookie = req.req().headers().get(COOKIE);
+            cookie.map(|c| c.to_str().unwrap().to_owned()).unwrap_or_default()
+        }
+
+        async fn logout(_: &WebRequest<'_, (), TestBody>) -> WebResponse {
+            let mut res = WebResponse::new(ResponseBody::None);
+            res.headers_mut()
+                .insert(SET_COOKIE, HeaderValue::from_static("id=; Max-Age=0"));
+            res
+        }
+
+        let client = TestClient::new(
+            App::new()
+                .at("/login", get(handler_service(login)))
+                .at("/session", get(handler_service(session)))
+                .at("/logout", get(handler_service(logout)))
+                .finish(),
+        )
+        .now_or_panic();
+
+        let res = client
+            .get("/login")
+            .remote_addr(([127, 0, 0, 1], 8080).into())
+            .send()
+            .now_or_panic();
+        res.assert_status(StatusCode::OK)
+            .assert_header("content-type", "text/plain; charset=utf-8");
+        assert_eq!(res.string().now_or_panic(), "V4(127.0.0.1, 8080)");
+
+        assert_eq!(client.cookie("id").as_deref(), Some("996"));
+
+        let res = client.get("/session").send().now_or_panic();
+        assert_eq!(res.string().now_or_panic(), "id=996; lang=en");
+
+        let res = client.get("/session").header("cookie", "a=b").send().now_or_panic();
+        assert_eq!(res.string().now_or_panic(), "a=b");
+
+        client.get("/logout").send().now_or_panic();
+        assert_eq!(client.cookie("id"), None);
+        assert_eq!(client.cookie("lang").as_deref(), Some("en"));
+
+        client.clear_cookies();
+        let res = client.get("/session").send().now_or_panic();
+        assert_eq!(res.string().now_or_panic(), "");
+
+        let res = client.post("/session").send().now_or_panic();
+        res.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn multipart_body() {
+        async fn echo(body: Vec<u8>) -> Vec<u8> {
+            body
+        }
+
+        let client = TestClient::new(App::new().at("/", handler_service(echo)).finish()).now_or_panic();
+
+        let multipart = Multipart::new()
+            .text("name", "xitca")
+            .file("file", "a.txt", "text/plain", "hello");
+
+        let res = client.post("/").multipart(multipart).send().now_or_panic();
+        let body = res.string().now_or_panic();
+
+        assert_eq!(
+            body,
+            "--xitca-web-test-boundary\r\n\
+             Content-Disposition: form-data; name=\"name\"\r\n\r\n\
+             xitca\r\n\
+             --xitca-web-test-boundary\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\n\
+             hello\r\n\
+             --xitca-web-test-boundary--\r\n"
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_body() {
+        use crate::handler::json::Json;
+
+        #[derive(serde::Deserialize, serde::Serialize, Debug, Eq, PartialEq)]
+        struct Data {
+            id: u64,
+        }
+
+        async fn echo(Json(data): Json<Data>) -> Json<Data> {
+            Json(data)
+        }
+
+        let client = TestClient::new(App::new().at("/", handler_service(echo)).finish()).now_or_panic();
+
+        let res = client.post("/").json(&Data { id: 996 }).send().now_or_panic();
+        res.assert_header("content-type", "application/json");
+        let data = res.json::<Data, _, _>().now_or_panic();
+
+        assert_eq!(data, Data { id: 996 });
+    }
+}